@@ -0,0 +1,248 @@
+//! Reading the text of an open item file: which key a line belongs to, the value on a line, and
+//! which line a validation message is about.
+//!
+//! Item files are edited while they're invalid YAML, so these work on the lines of the text
+//! rather than on a parsed document.
+
+/// the indentation of a line, in characters
+fn indent(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+/// a line without its comment
+fn without_comment(line: &str) -> &str {
+    match line.find(" #") {
+        Some(index) => &line[..index],
+        None if line.trim_start().starts_with('#') => "",
+        None => line,
+    }
+}
+
+/// the key a line's value belongs to. For `Material: (Uni)` that's `Material`, and for a list entry
+/// like `- (Bomb)` or `- Fire: 1` it's the key the list is under (e.g. `Category` or `Element`).
+/// `column` is the character offset of the cursor, so typing a key itself isn't mistaken for its
+/// value.
+pub fn key_at(text: &str, line: usize, column: usize) -> Option<String> {
+    let lines: Vec<&str> = text.lines().collect();
+    let current = without_comment(lines.get(line)?);
+    let trimmed = current.trim_start();
+
+    if !trimmed.starts_with('-') {
+        let colon = current.find(':')?;
+        let cursor = current
+            .char_indices()
+            .nth(column)
+            .map_or(current.len(), |(index, _)| index);
+        return if cursor > colon {
+            Some(current[..colon].trim().to_string())
+        } else {
+            None
+        };
+    }
+
+    // a list entry: find the key the list is under
+    let entry_indent = indent(current);
+    for previous in lines[..line].iter().rev() {
+        let previous = without_comment(previous);
+        let trimmed = previous.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        let previous_indent = indent(previous);
+        if previous_indent < entry_indent
+            || (previous_indent == entry_indent && !trimmed.starts_with('-'))
+        {
+            return trimmed
+                .trim_start_matches('-')
+                .trim()
+                .strip_suffix(':')
+                .map(|key| key.trim().to_string());
+        }
+    }
+    None
+}
+
+/// the value written on a line: `Red Supplement` from `Recipe: Red Supplement`, `(Bomb)` from
+/// `- (Bomb)` and `Fire` from `- Fire: 1`
+pub fn value_at(text: &str, line: usize) -> Option<String> {
+    let current = without_comment(text.lines().nth(line)?).trim();
+    let value = match current.strip_prefix('-') {
+        // list entries can be `name: value` pairs, where the name is what's wanted
+        Some(entry) => entry.split(':').next().unwrap_or_default(),
+        None => current.split_once(':')?.1,
+    };
+    let value = value.trim().trim_matches(|c| c == '\'' || c == '"');
+    if value.is_empty() {
+        None
+    } else {
+        Some(value.to_string())
+    }
+}
+
+/// the line a validation message is most likely about. Messages quoting a key or value in the
+/// text (e.g. `'Red Supplement'`) are put on the line it's on. Otherwise the message's prefixes are
+/// followed down the document, so `Synthesis: Effect 1: Scatter: Element: Fire is an unknown
+/// value` goes to the `Fire` line under the `Scatter` level. Messages about missing keys are put
+/// on the first line.
+pub fn message_line(text: &str, message: &str) -> usize {
+    let lines: Vec<&str> = text.lines().map(without_comment).collect();
+    for quoted in message.split('\'').skip(1).step_by(2) {
+        if quoted.is_empty() {
+            continue;
+        }
+        // the whole key or value, so `'Level'` isn't found in `Levels:`
+        if let Some(line) = (0..lines.len()).find(|&line| {
+            entry_key(lines[line]) == Some(quoted)
+                || value_at(text, line).as_deref() == Some(quoted)
+        }) {
+            return line;
+        }
+    }
+
+    let mut segments: Vec<&str> = message.split(": ").collect();
+    let last = segments.pop().unwrap_or_default();
+    let mut current = 0;
+    for segment in segments {
+        match (current..lines.len()).find(|&line| entry_key(lines[line]) == Some(segment)) {
+            Some(line) => current = line,
+            None => return current,
+        }
+    }
+    // the rest of the message usually starts with the key or value it's about
+    let starts_message = |word: &str| last.starts_with(&format!("{} ", word));
+    (current..lines.len())
+        .find(|&line| {
+            entry_key(lines[line]).is_some_and(starts_message)
+                || value_at(text, line).is_some_and(|value| starts_message(&value))
+        })
+        .unwrap_or(current)
+}
+
+/// the key of a line, or the name of a list entry like `- Effect 1:` or `- Fire: 1`
+fn entry_key(line: &str) -> Option<&str> {
+    let trimmed = line.trim().trim_start_matches('-').trim_start();
+    trimmed.find(':').map(|colon| trimmed[..colon].trim())
+}
+
+/// the length of a line in UTF-16 code units, which is how LSP counts characters
+pub fn line_length(text: &str, line: usize) -> u32 {
+    text.lines()
+        .nth(line)
+        .map_or(0, |line| line.encode_utf16().count() as u32)
+}
+
+/// convert an LSP character offset (in UTF-16 code units) on a line into a count of characters
+pub fn column(text: &str, line: usize, character: u32) -> usize {
+    let mut units = 0;
+    let line = text.lines().nth(line).unwrap_or_default();
+    for (index, c) in line.chars().enumerate() {
+        if units >= character as usize {
+            return index;
+        }
+        units += c.len_utf16();
+    }
+    line.chars().count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "\
+Name: Explosive Uni
+Item Number: 1
+Category: # the kinds of bomb
+- (Bomb)
+Element:
+- Lightning: 5
+# a comment line
+- Ice
+Synthesis:
+  Material Loops:
+  - Effect 1:
+      Material: 'Red Supplement' # quoted
+      Levels:
+      - Uni Spike S:
+          Element:
+          - Fire: 2
+      - Recipe Morph:
+          Recipe: Red Supplement
+";
+
+    #[test]
+    fn key_at_a_value() {
+        assert_eq!(key_at(TEXT, 0, 8).as_deref(), Some("Name"));
+        assert_eq!(key_at(TEXT, 11, 20).as_deref(), Some("Material"));
+        // the cursor is still on the key
+        assert_eq!(key_at(TEXT, 0, 2), None);
+        assert_eq!(key_at(TEXT, 99, 0), None);
+    }
+
+    #[test]
+    fn key_at_a_list_entry() {
+        // past the comment on the key's line
+        assert_eq!(key_at(TEXT, 3, 3).as_deref(), Some("Category"));
+        // past a comment line between the entries
+        assert_eq!(key_at(TEXT, 7, 3).as_deref(), Some("Element"));
+        assert_eq!(key_at(TEXT, 15, 14).as_deref(), Some("Element"));
+        assert_eq!(key_at(TEXT, 13, 10).as_deref(), Some("Levels"));
+        assert_eq!(key_at(TEXT, 10, 5).as_deref(), Some("Material Loops"));
+    }
+
+    #[test]
+    fn value_at_a_line() {
+        assert_eq!(value_at(TEXT, 0).as_deref(), Some("Explosive Uni"));
+        assert_eq!(value_at(TEXT, 3).as_deref(), Some("(Bomb)"));
+        assert_eq!(value_at(TEXT, 5).as_deref(), Some("Lightning"));
+        // without its quotes or comment
+        assert_eq!(value_at(TEXT, 11).as_deref(), Some("Red Supplement"));
+        // keys with only a comment, and comment lines, have no value
+        assert_eq!(value_at(TEXT, 2), None);
+        assert_eq!(value_at(TEXT, 6), None);
+        assert_eq!(value_at(TEXT, 8), None);
+    }
+
+    #[test]
+    fn message_line_of_a_quoted_value() {
+        assert_eq!(
+            message_line(
+                TEXT,
+                "Synthesis: Effect 1: key Material: unknown value 'Red Supplement'"
+            ),
+            11
+        );
+        // text in comments isn't matched
+        assert_eq!(message_line(TEXT, "Synthesis: 'quoted' is odd"), 8);
+    }
+
+    #[test]
+    fn message_line_follows_the_prefixes() {
+        assert_eq!(
+            message_line(TEXT, "Element: Lightning is an unknown value"),
+            5
+        );
+        assert_eq!(
+            message_line(
+                TEXT,
+                "Synthesis: Effect 1: Uni Spike S: Element: Fire value must be between 0 and 3"
+            ),
+            15
+        );
+        assert_eq!(
+            message_line(
+                TEXT,
+                "Synthesis: Effect 1: Recipe Morph: Recipe is not a known key"
+            ),
+            17
+        );
+    }
+
+    #[test]
+    fn message_line_of_something_missing() {
+        assert_eq!(message_line(TEXT, "'Level' key is missing"), 0);
+        assert_eq!(
+            message_line(TEXT, "ID 'explosive-uni' is also used by 002-uni.yml"),
+            0
+        );
+    }
+}
@@ -0,0 +1,433 @@
+//! A language server for item files, built on the item validator.
+//!
+//! It gives diagnostics from the validator as item files are edited, completion of the values in
+//! lists.yml and of item names, hover details for materials and recipes, and go to definition from
+//! a material or recipe to its item file. Editors start it over stdio, as `item-lsp [data folder]`;
+//! without a data folder it's read from settings.yml in the current directory, like the validator.
+
+mod document;
+
+use item_validator::dataset::Dataset;
+use item_validator::model::{self, Item, MaterialKind};
+use item_validator::{file_contents, settings, validate_item};
+
+use lsp_server::{Connection, Message, Notification, Request, RequestId, Response};
+use lsp_types::notification::{
+    DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, Notification as _,
+    PublishDiagnostics,
+};
+use lsp_types::request::{Completion, GotoDefinition, HoverRequest, Request as _};
+use lsp_types::{
+    CompletionItem, CompletionItemKind, CompletionOptions, CompletionParams, CompletionResponse,
+    Diagnostic, DiagnosticSeverity, GotoDefinitionParams, GotoDefinitionResponse, Hover,
+    HoverContents, HoverParams, HoverProviderCapability, Location, MarkupContent, MarkupKind,
+    OneOf, Position, PublishDiagnosticsParams, Range, ServerCapabilities,
+    TextDocumentSyncCapability, TextDocumentSyncKind, Url,
+};
+
+use std::any::Any;
+use std::collections::{BTreeSet, HashMap};
+use std::env;
+use std::error::Error;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+
+type LspResult<T> = Result<T, Box<dyn Error + Send + Sync>>;
+
+fn main() -> LspResult<()> {
+    let data_folder = match env::args().nth(1) {
+        Some(data_folder) => data_folder,
+        None => settings::get_settings().data_folder,
+    };
+    let dataset = Dataset::load(&data_folder).unwrap_or_else(|error| panic!("{}", error));
+
+    let (connection, io_threads) = Connection::stdio();
+    let capabilities = ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        completion_provider: Some(CompletionOptions::default()),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        definition_provider: Some(OneOf::Left(true)),
+        ..ServerCapabilities::default()
+    };
+    connection.initialize(serde_json::to_value(capabilities)?)?;
+
+    let mut server = Server {
+        items_folder: Path::new(&data_folder).join("items"),
+        dataset,
+        documents: HashMap::new(),
+    };
+    server.run(connection)?;
+    io_threads.join()?;
+    Ok(())
+}
+
+struct Server {
+    items_folder: PathBuf,
+    /// the dataset, with the contents of open item files in place of what's saved on disk
+    dataset: Dataset,
+    /// the text of the open item files
+    documents: HashMap<Url, String>,
+}
+
+impl Server {
+    /// handle messages until the client shuts the server down
+    fn run(&mut self, connection: Connection) -> LspResult<()> {
+        for message in &connection.receiver {
+            match message {
+                Message::Request(request) => {
+                    if connection.handle_shutdown(&request)? {
+                        return Ok(());
+                    }
+                    let id = request.id.clone();
+                    let response = self
+                        .request(request)
+                        .unwrap_or_else(|error| invalid_params(id, error));
+                    connection.sender.send(Message::Response(response))?;
+                }
+                Message::Notification(notification) => {
+                    // notifications have no reply, so ones that can't be read are only logged
+                    // (to stderr, which editors keep as the server's log)
+                    let changes = self.notification(notification).unwrap_or_else(|error| {
+                        eprintln!("ignoring a notification: {}", error);
+                        Vec::new()
+                    });
+                    for (uri, diagnostics) in changes {
+                        let params = PublishDiagnosticsParams {
+                            uri,
+                            diagnostics,
+                            version: None,
+                        };
+                        connection
+                            .sender
+                            .send(Message::Notification(Notification::new(
+                                PublishDiagnostics::METHOD.to_string(),
+                                params,
+                            )))?;
+                    }
+                }
+                Message::Response(_) => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// answer a request. Returns an error if its params can't be read.
+    fn request(&self, request: Request) -> LspResult<Response> {
+        match request.method.as_str() {
+            Completion::METHOD => {
+                let (id, params) = request.extract::<CompletionParams>(Completion::METHOD)?;
+                let position = params.text_document_position;
+                Ok(Response::new_ok(
+                    id,
+                    self.completion(&position.text_document.uri, position.position),
+                ))
+            }
+            HoverRequest::METHOD => {
+                let (id, params) = request.extract::<HoverParams>(HoverRequest::METHOD)?;
+                let position = params.text_document_position_params;
+                Ok(Response::new_ok(
+                    id,
+                    self.hover(&position.text_document.uri, position.position),
+                ))
+            }
+            GotoDefinition::METHOD => {
+                let (id, params) =
+                    request.extract::<GotoDefinitionParams>(GotoDefinition::METHOD)?;
+                let position = params.text_document_position_params;
+                Ok(Response::new_ok(
+                    id,
+                    self.definition(&position.text_document.uri, position.position),
+                ))
+            }
+            _ => Ok(method_not_found(request.id, &request.method)),
+        }
+    }
+
+    /// apply a change to the open documents, returning the diagnostics to publish
+    fn notification(
+        &mut self,
+        notification: Notification,
+    ) -> LspResult<Vec<(Url, Vec<Diagnostic>)>> {
+        let (uri, text) = match notification.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let params: lsp_types::DidOpenTextDocumentParams =
+                    notification.extract(DidOpenTextDocument::METHOD)?;
+                (params.text_document.uri, Some(params.text_document.text))
+            }
+            DidChangeTextDocument::METHOD => {
+                let params: lsp_types::DidChangeTextDocumentParams =
+                    notification.extract(DidChangeTextDocument::METHOD)?;
+                // with full sync, the last change holds the whole text
+                match params.content_changes.into_iter().last() {
+                    Some(change) => (params.text_document.uri, Some(change.text)),
+                    None => return Ok(Vec::new()),
+                }
+            }
+            DidCloseTextDocument::METHOD => {
+                let params: lsp_types::DidCloseTextDocumentParams =
+                    notification.extract(DidCloseTextDocument::METHOD)?;
+                (params.text_document.uri, None)
+            }
+            _ => return Ok(Vec::new()),
+        };
+
+        let name = match self.item_file_name(&uri) {
+            Some(name) => name,
+            None => return Ok(Vec::new()),
+        };
+        let changed_files = match text {
+            Some(text) => {
+                self.documents.insert(uri.clone(), text.clone());
+                self.dataset.update_file(&name, Some(text))
+            }
+            None => {
+                // back to what's saved on disk
+                self.documents.remove(&uri);
+                let path = self.items_folder.join(&name);
+                let contents = file_contents::load_file(&path.to_string_lossy()).ok();
+                self.dataset.update_file(&name, contents)
+            }
+        };
+
+        let mut diagnostics = Vec::new();
+        if !self.documents.contains_key(&uri) {
+            diagnostics.push((uri, Vec::new()));
+        }
+        // the changed document, and the open documents that refer to it
+        for (open_uri, text) in &self.documents {
+            match self.item_file_name(open_uri) {
+                Some(open_name) if changed_files.contains(&open_name) => {
+                    diagnostics.push((open_uri.clone(), self.diagnostics(&open_name, text)));
+                }
+                _ => {}
+            }
+        }
+        Ok(diagnostics)
+    }
+
+    /// the name of a document in the items folder (its path within the folder), if it's an item
+    /// file the dataset would load
+    fn item_file_name(&self, uri: &Url) -> Option<String> {
+        let path = uri.to_file_path().ok()?;
+        let name = file_contents::item_file_name(&self.items_folder, &path).or_else(|| {
+            match (path.canonicalize(), self.items_folder.canonicalize()) {
+                (Ok(path), Ok(items_folder)) => file_contents::item_file_name(&items_folder, &path),
+                _ => None,
+            }
+        })?;
+        if self.dataset.item_filter.includes(&name) {
+            Some(name)
+        } else {
+            None
+        }
+    }
+
+    fn diagnostics(&self, name: &str, text: &str) -> Vec<Diagnostic> {
+        // a new file that hasn't been started yet
+        if text.trim().is_empty() {
+            return Vec::new();
+        }
+        // a panic in the validator shouldn't take the server down with it
+        let validated = panic::catch_unwind(AssertUnwindSafe(|| {
+            validate_item::validate_item_contents(
+                text,
+                &self.dataset.item_validation_sets,
+                &self.dataset.item_rules,
+            )
+        }));
+        match validated {
+            Ok(Ok(mut results)) => {
+                // IDs and names shared with other items, which only the dataset knows about
+                if let Some(file) = self.dataset.file(name) {
+                    results
+                        .fail_messages
+                        .extend(self.dataset.duplicate_names(file));
+                }
+                results
+                    .fail_messages
+                    .iter()
+                    .map(|message| {
+                        let line = document::message_line(text, message);
+                        diagnostic(line_range(text, line), message.clone())
+                    })
+                    .collect()
+            }
+            Ok(Err(error)) => {
+                // markers count lines from 1
+                let line = error.marker().line().saturating_sub(1);
+                vec![diagnostic(line_range(text, line), error.to_string())]
+            }
+            Err(panic) => vec![diagnostic(
+                line_range(text, 0),
+                format!(
+                    "the validator crashed on this file: {}",
+                    panic_message(&*panic)
+                ),
+            )],
+        }
+    }
+
+    fn completion(&self, uri: &Url, position: Position) -> Option<CompletionResponse> {
+        let text = self.documents.get(uri)?;
+        let line = position.line as usize;
+        let column = document::column(text, line, position.character);
+        let key = document::key_at(text, line, column)?;
+
+        let sets = &self.dataset.item_validation_sets;
+        let values: BTreeSet<&String> = match key.as_str() {
+            "Category" => sets.categories.iter().collect(),
+            "Classifications" => sets.classifications.iter().collect(),
+            "Element" | "Unlock" => sets.elements.iter().collect(),
+            "Gathering Tools" => sets.gathering_tools.iter().collect(),
+            "Equippable By" => sets.characters.iter().collect(),
+            // the materials set holds both the categories and the item names
+            "Materials" | "Material" => sets.materials.iter().collect(),
+            "Recipe" => sets.materials.difference(&sets.categories).collect(),
+            _ => return None,
+        };
+        Some(CompletionResponse::Array(
+            values
+                .into_iter()
+                .map(|value| CompletionItem {
+                    label: value.clone(),
+                    kind: Some(match model::parse_material(value) {
+                        (MaterialKind::Item, _) if sets.materials.contains(value) => {
+                            CompletionItemKind::REFERENCE
+                        }
+                        _ => CompletionItemKind::ENUM_MEMBER,
+                    }),
+                    ..CompletionItem::default()
+                })
+                .collect(),
+        ))
+    }
+
+    /// the material or recipe on a line, if the line has one
+    fn reference(&self, uri: &Url, position: Position) -> Option<String> {
+        let text = self.documents.get(uri)?;
+        let line = position.line as usize;
+        let column = document::column(text, line, position.character);
+        match document::key_at(text, line, column)?.as_str() {
+            "Materials" | "Material" | "Recipe" => document::value_at(text, line),
+            _ => None,
+        }
+    }
+
+    fn hover(&self, uri: &Url, position: Position) -> Option<Hover> {
+        let reference = self.reference(uri, position)?;
+        let items = self.dataset.items();
+        let markdown = match model::parse_material(&reference) {
+            (MaterialKind::Category, _) => {
+                let names: Vec<&str> = items
+                    .iter()
+                    .filter(|item| item.categories.contains(&reference))
+                    .map(|item| item.name.as_str())
+                    .collect();
+                format!("**{}**\n\nItems: {}", reference, names.join(", "))
+            }
+            (MaterialKind::Item, name) => match items.iter().find(|item| item.is_named(name)) {
+                Some(item) => item_summary(item),
+                None => format!("**{}**\n\nNo item file yet", name),
+            },
+        };
+        Some(Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: markdown,
+            }),
+            range: None,
+        })
+    }
+
+    fn definition(&self, uri: &Url, position: Position) -> Option<GotoDefinitionResponse> {
+        let reference = self.reference(uri, position)?;
+        let name = match model::parse_material(&reference) {
+            (MaterialKind::Item, name) => name,
+            (MaterialKind::Category, _) => return None,
+        };
+        let file = self.dataset.item_contents.iter().find(|file| {
+            file.document()
+                .map(|yaml| Item::from_yaml(yaml).is_named(name))
+                .unwrap_or(false)
+        })?;
+        let target = Url::from_file_path(self.items_folder.join(&file.name)).ok()?;
+        Some(GotoDefinitionResponse::Scalar(Location::new(
+            target,
+            Range::default(),
+        )))
+    }
+}
+
+fn item_summary(item: &Item) -> String {
+    let elements: Vec<String> = item
+        .elements
+        .iter()
+        .map(|element| match element.value {
+            Some(value) => format!("{} {}", element.element, value),
+            None => element.element.clone(),
+        })
+        .collect();
+    let mut summary = format!(
+        "**{}** (No. {}, Level {})\n\nCategory: {}\n\nElement: {}",
+        item.name,
+        item.item_number,
+        item.level,
+        item.categories.join(", "),
+        elements.join(", ")
+    );
+    if item.is_gathered() {
+        summary.push_str(&format!(
+            "\n\nGathered at: {}",
+            item.gather_locations.join(", ")
+        ));
+    } else {
+        summary.push_str(&format!(
+            "\n\nSynthesized from: {}",
+            item.materials.join(", ")
+        ));
+    }
+    summary
+}
+
+fn line_range(text: &str, line: usize) -> Range {
+    Range::new(
+        Position::new(line as u32, 0),
+        Position::new(line as u32, document::line_length(text, line)),
+    )
+}
+
+fn diagnostic(range: Range, message: String) -> Diagnostic {
+    Diagnostic {
+        range,
+        severity: Some(DiagnosticSeverity::ERROR),
+        source: Some(String::from("item-validator")),
+        message,
+        ..Diagnostic::default()
+    }
+}
+
+fn panic_message(panic: &(dyn Any + Send)) -> &str {
+    if let Some(message) = panic.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = panic.downcast_ref::<String>() {
+        message
+    } else {
+        "unknown error"
+    }
+}
+
+fn invalid_params(id: RequestId, error: Box<dyn Error + Send + Sync>) -> Response {
+    Response::new_err(
+        id,
+        lsp_server::ErrorCode::InvalidParams as i32,
+        error.to_string(),
+    )
+}
+
+fn method_not_found(id: RequestId, method: &str) -> Response {
+    Response::new_err(
+        id,
+        lsp_server::ErrorCode::MethodNotFound as i32,
+        format!("unsupported request {}", method),
+    )
+}
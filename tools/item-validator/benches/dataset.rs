@@ -0,0 +1,152 @@
+//! Loading and validating a generated data folder of 1000 items, about twice the size of the
+//! game's item list.
+//!
+//! The items are written with `item_file::to_yaml` into a temporary folder next to a copy of the
+//! real lists.yml and rules.yml. Half are gathered and half are synthesized, with material loops
+//! that refer to earlier items, so they take the same validation paths as real item files.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use item_validator::dataset::Dataset;
+use item_validator::file_contents::FileContents;
+use item_validator::item_file;
+use item_validator::model::{ElementValue, Item, LoopLevel, MaterialLoop, Synthesis};
+use item_validator::validation_sets;
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+const ITEM_COUNT: i64 = 1000;
+
+/// the real data folder, for lists.yml and rules.yml
+const DATA_FOLDER: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../../data");
+
+fn sorted(set: &std::collections::HashSet<String>) -> Vec<String> {
+    let mut values: Vec<String> = set.iter().cloned().collect();
+    values.sort();
+    values
+}
+
+fn element(element: &str, value: i64) -> ElementValue {
+    ElementValue {
+        element: element.to_string(),
+        value: Some(value),
+    }
+}
+
+fn generated_item(number: i64, categories: &[String], elements: &[String]) -> Item {
+    let pick =
+        |values: &[String], offset: i64| values[(number + offset) as usize % values.len()].clone();
+    let mut item = Item {
+        name: format!("Generated Item {}", number),
+        // item numbers stop at 999, so the last item shares a number with the first
+        item_number: 1 + (number - 1) % 999,
+        level: 1 + number % 50,
+        categories: vec![pick(categories, 0)],
+        elements: vec![element(&pick(elements, 0), 1 + number % 3)],
+        ..Item::default()
+    };
+
+    if number % 2 == 1 {
+        item.classifications = vec!["Synthesis Materials".to_string(), "Materials".to_string()];
+        item.gather_locations = vec!["Kurken Island".to_string()];
+        item.gathering_tools = vec!["Staff".to_string()];
+        return item;
+    }
+
+    // synthesized items use a category and an earlier (gathered) item as materials
+    let earlier = format!("Generated Item {}", number - 1);
+    item.classifications = vec!["Item Used".to_string()];
+    item.materials = vec![pick(categories, 1), earlier.clone()];
+    let material_loops: Vec<MaterialLoop> = (0..6)
+        .map(|index| MaterialLoop {
+            name: format!("Effect {}", index + 1),
+            distance: (index + 1) / 2,
+            position: index,
+            linked_from_position: if index == 0 {
+                None
+            } else {
+                Some((index - 1) / 2)
+            },
+            material: if index % 2 == 0 {
+                earlier.clone()
+            } else {
+                pick(categories, index)
+            },
+            levels: (0..3)
+                .map(|level| LoopLevel {
+                    effect: format!("Effect Level {}", level + 1),
+                    elements: vec![element(&pick(elements, index + level), 1 + level)],
+                    recipe: None,
+                    ..LoopLevel::default()
+                })
+                .collect(),
+            unlock: vec![element(&pick(elements, index), 2)],
+        })
+        .collect();
+    item.synthesis = Some(Synthesis {
+        required_materials: 2,
+        required_alchemy_level: item.level,
+        material_loops,
+    });
+    item
+}
+
+/// write the generated data folder, returning its path
+fn generate_data_folder() -> PathBuf {
+    let folder = env::temp_dir().join(format!("ryza-items-bench-{}", std::process::id()));
+    let items_folder = folder.join("items");
+    fs::create_dir_all(&items_folder).expect("Unable to create the generated data folder");
+    for file in &["lists.yml", "rules.yml"] {
+        fs::copy(format!("{}/{}", DATA_FOLDER, file), folder.join(file))
+            .expect("Unable to copy the lists and rules");
+    }
+
+    let lists = fs::read_to_string(folder.join("lists.yml")).expect("Unable to read lists.yml");
+    let sets =
+        validation_sets::build_item_validation_sets(&lists).expect("Unable to parse lists.yml");
+    let categories = sorted(&sets.categories);
+    let elements = sorted(&sets.elements);
+    for number in 1..=ITEM_COUNT {
+        let item = generated_item(number, &categories, &elements);
+        fs::write(
+            items_folder.join(item_file::file_name(&item)),
+            item_file::to_yaml(&item),
+        )
+        .expect("Unable to write a generated item");
+    }
+    folder
+}
+
+fn dataset_benchmarks(c: &mut Criterion) {
+    let folder = generate_data_folder();
+    let data_folder = folder.to_string_lossy().to_string();
+
+    let dataset = Dataset::load(&data_folder).expect("Unable to load the generated data folder");
+    assert_eq!(dataset.item_contents.len(), ITEM_COUNT as usize);
+    assert!(
+        dataset.invalid_files().is_empty(),
+        "the generated items should validate"
+    );
+    let files: Vec<&FileContents> = dataset.item_contents.iter().collect();
+
+    c.bench_function("load 1000 items", |b| {
+        b.iter(|| Dataset::load(&data_folder).expect("Unable to load the generated data folder"))
+    });
+    c.bench_function("validate 1000 items", |b| {
+        b.iter(|| dataset.validate_files(&files))
+    });
+    c.bench_function("validate 1000 items one at a time", |b| {
+        b.iter(|| {
+            files
+                .iter()
+                .map(|file| dataset.validate_file(file))
+                .collect::<Vec<_>>()
+        })
+    });
+
+    fs::remove_dir_all(&folder).expect("Unable to remove the generated data folder");
+}
+
+criterion_group!(benches, dataset_benchmarks);
+criterion_main!(benches);
@@ -0,0 +1,333 @@
+//! Estimating what a synthesized attack item does in battle, from battle.yml.
+
+extern crate yaml_rust;
+use crate::model::Item;
+use std::fmt;
+use std::ops::RangeInclusive;
+use yaml_rust::{ScanError, Yaml, YamlLoader};
+
+/// who an attack item hits when none of its effects say otherwise
+pub const DEFAULT_AREA: &str = "Single Enemy";
+/// the quality a synthesized item can have
+pub const QUALITY_RANGE: RangeInclusive<i64> = 0..=999;
+
+const BATTLE_KEYS: [&str; 3] = ["Base Damage", "Quality Scaling", "Effects"];
+const EFFECT_KEYS: [&str; 4] = ["Name", "Area", "Damage Bonus", "Extra Damage"];
+
+/// what one loop effect adds to an attack item
+pub struct BattleEffect {
+    pub name: String,
+    pub area: Option<String>,
+    /// percent of extra damage
+    pub damage_bonus: f64,
+    /// damage added before the quality scaling
+    pub extra_damage: f64,
+}
+
+pub struct BattleData {
+    pub base_damage: f64,
+    /// percent of extra damage per point of quality
+    pub quality_scaling: f64,
+    pub effects: Vec<BattleEffect>,
+}
+
+pub struct BattleReport {
+    pub damage: f64,
+    pub area: String,
+    pub cc_cost: i64,
+    /// effects that battle.yml has no numbers for
+    pub unknown_effects: Vec<String>,
+}
+
+impl BattleData {
+    pub fn effect(&self, name: &str) -> Option<&BattleEffect> {
+        self.effects.iter().find(|effect| effect.name == name)
+    }
+
+    /// estimate the damage per use, area and CC cost of an item with the given
+    /// realised effects and quality
+    pub fn evaluate(&self, item: &Item, effects: &[String], quality: i64) -> BattleReport {
+        let mut extra_damage = 0.0;
+        let mut damage_bonus = 0.0;
+        let mut area = DEFAULT_AREA.to_string();
+        let mut unknown_effects = Vec::new();
+
+        for name in effects {
+            match self.effect(name) {
+                Some(effect) => {
+                    extra_damage += effect.extra_damage;
+                    damage_bonus += effect.damage_bonus;
+                    if let Some(effect_area) = &effect.area {
+                        area = effect_area.clone();
+                    }
+                }
+                None => unknown_effects.push(name.clone()),
+            }
+        }
+
+        let quality_multiplier = 1.0 + quality as f64 * self.quality_scaling / 100.0;
+        let damage =
+            (self.base_damage + extra_damage) * quality_multiplier * (1.0 + damage_bonus / 100.0);
+        BattleReport {
+            damage,
+            area,
+            cc_cost: item.cc_cost.unwrap_or(0),
+            unknown_effects,
+        }
+    }
+}
+
+impl BattleReport {
+    /// damage per point of CC, None for items without a CC cost
+    pub fn cc_efficiency(&self) -> Option<f64> {
+        if self.cc_cost > 0 {
+            Some(self.damage / self.cc_cost as f64)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum BattleDataError {
+    Yaml(ScanError),
+    /// keys that are missing, misspelled or hold the wrong kind of value
+    Keys(Vec<String>),
+}
+
+impl fmt::Display for BattleDataError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BattleDataError::Yaml(error) => write!(f, "{}", error),
+            BattleDataError::Keys(problems) => write!(f, "{}", problems.join(", ")),
+        }
+    }
+}
+
+impl From<ScanError> for BattleDataError {
+    fn from(error: ScanError) -> Self {
+        BattleDataError::Yaml(error)
+    }
+}
+
+/// read battle.yml. A missing or misspelled key would otherwise count as 0, so all of them are
+/// reported rather than guessed at.
+pub fn build_battle_data(contents: &str) -> Result<BattleData, BattleDataError> {
+    let docs = YamlLoader::load_from_str(contents)?;
+    // YAML files can actually contain multiple files inside, we want the first one (if any)
+    let yaml = docs.first().unwrap_or(&Yaml::BadValue);
+    let mut problems = Vec::new();
+
+    unknown_keys(yaml, &BATTLE_KEYS, "", &mut problems);
+    let base_damage = required_number(yaml, "Base Damage", "", &mut problems);
+    let quality_scaling = required_number(yaml, "Quality Scaling", "", &mut problems);
+
+    let mut effects = Vec::new();
+    match &yaml["Effects"] {
+        Yaml::Array(list) => {
+            for (index, effect) in list.iter().enumerate() {
+                let name = match effect["Name"].as_str() {
+                    Some(name) => name.to_string(),
+                    None => {
+                        problems.push(format!("effect {} has no 'Name'", index + 1));
+                        continue;
+                    }
+                };
+                let context = format!("effect '{}': ", name);
+                unknown_keys(effect, &EFFECT_KEYS, &context, &mut problems);
+                let area = match &effect["Area"] {
+                    Yaml::BadValue => None,
+                    Yaml::String(area) => Some(area.clone()),
+                    _ => {
+                        problems.push(format!("{}'Area' must be text", context));
+                        None
+                    }
+                };
+                effects.push(BattleEffect {
+                    name,
+                    area,
+                    damage_bonus: optional_number(effect, "Damage Bonus", &context, &mut problems),
+                    extra_damage: optional_number(effect, "Extra Damage", &context, &mut problems),
+                });
+            }
+        }
+        Yaml::BadValue => problems.push(String::from("'Effects' key is missing")),
+        _ => problems.push(String::from("'Effects' must be a list")),
+    }
+
+    if !problems.is_empty() {
+        return Err(BattleDataError::Keys(problems));
+    }
+    Ok(BattleData {
+        base_damage,
+        quality_scaling,
+        effects,
+    })
+}
+
+/// read a quality typed on the command line, which has to be within `QUALITY_RANGE`
+pub fn parse_quality(text: &str) -> Result<i64, String> {
+    match text.parse::<i64>() {
+        Ok(quality) if QUALITY_RANGE.contains(&quality) => Ok(quality),
+        Ok(quality) => Err(format!(
+            "quality must be between {} and {}, found {}",
+            QUALITY_RANGE.start(),
+            QUALITY_RANGE.end(),
+            quality
+        )),
+        Err(_) => Err(String::from("quality must be a number")),
+    }
+}
+
+fn unknown_keys(yaml: &Yaml, known: &[&str], context: &str, problems: &mut Vec<String>) {
+    if let Yaml::Hash(hash) = yaml {
+        for key in hash.keys() {
+            match key.as_str() {
+                Some(key) if known.contains(&key) => {}
+                Some(key) => problems.push(format!("{}unknown key '{}'", context, key)),
+                None => problems.push(format!("{}unknown key {:?}", context, key)),
+            }
+        }
+    }
+}
+
+fn required_number(yaml: &Yaml, key: &str, context: &str, problems: &mut Vec<String>) -> f64 {
+    if let Yaml::BadValue = yaml[key] {
+        problems.push(format!("{}'{}' key is missing", context, key));
+        return 0.0;
+    }
+    optional_number(yaml, key, context, problems)
+}
+
+/// numbers left out of an effect add nothing
+fn optional_number(yaml: &Yaml, key: &str, context: &str, problems: &mut Vec<String>) -> f64 {
+    match &yaml[key] {
+        Yaml::BadValue => 0.0,
+        value => number(value).unwrap_or_else(|| {
+            problems.push(format!("{}'{}' must be a number", context, key));
+            0.0
+        }),
+    }
+}
+
+/// YAML numbers can be written as integers or reals
+fn number(yaml: &Yaml) -> Option<f64> {
+    match yaml {
+        Yaml::Integer(value) => Some(*value as f64),
+        Yaml::Real(_) => yaml.as_f64(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model;
+
+    const BATTLE: &str = "
+Base Damage: 100
+Quality Scaling: 0.5
+Effects:
+- Name: Scatter
+  Area: All Enemies
+- Name: Explosive
+  Area: All Enemies
+  Damage Bonus: 10
+- Name: Uni Spike S
+  Extra Damage: 20
+";
+
+    fn bomb(cc_cost: &str) -> Item {
+        model::load_item(&format!(
+            "Name: Bomb\nItem Number: 1\nLevel: 1\nClassifications: [Battle Item]\n{}",
+            cc_cost
+        ))
+        .unwrap()
+    }
+
+    fn effects(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn quality_scales_the_base_damage() {
+        let battle_data = build_battle_data(BATTLE).unwrap();
+        let report = battle_data.evaluate(&bomb("CC Cost: 2"), &[], 0);
+        assert_eq!(report.damage, 100.0);
+        assert_eq!(report.area, DEFAULT_AREA);
+        assert_eq!(report.cc_efficiency(), Some(50.0));
+
+        let report = battle_data.evaluate(&bomb("CC Cost: 2"), &[], 100);
+        assert_eq!(report.damage, 150.0);
+    }
+
+    #[test]
+    fn effects_add_damage_and_area() {
+        let battle_data = build_battle_data(BATTLE).unwrap();
+        let report = battle_data.evaluate(
+            &bomb("CC Cost: 4"),
+            &effects(&["Uni Spike S", "Explosive", "Hidden Power"]),
+            100,
+        );
+        // (100 + 20) * 1.5 * 1.1
+        assert!((report.damage - 198.0).abs() < 1e-9);
+        assert_eq!(report.area, "All Enemies");
+        assert_eq!(report.cc_cost, 4);
+        assert_eq!(report.unknown_effects, ["Hidden Power"]);
+    }
+
+    #[test]
+    fn items_without_a_cc_cost_have_no_efficiency() {
+        let battle_data = build_battle_data(BATTLE).unwrap();
+        let report = battle_data.evaluate(&bomb(""), &[], 0);
+        assert_eq!(report.cc_cost, 0);
+        assert_eq!(report.cc_efficiency(), None);
+    }
+
+    #[test]
+    fn missing_and_unknown_keys_are_reported() {
+        let contents = BATTLE
+            .replace("Quality Scaling", "Quality Scalling")
+            .replace("Damage Bonus: 10", "Damage Bonus: ten")
+            .replace("Extra Damage: 20", "Extra Damge: 20");
+        match build_battle_data(&contents) {
+            Err(BattleDataError::Keys(problems)) => assert_eq!(
+                problems,
+                [
+                    "unknown key 'Quality Scalling'",
+                    "'Quality Scaling' key is missing",
+                    "effect 'Explosive': 'Damage Bonus' must be a number",
+                    "effect 'Uni Spike S': unknown key 'Extra Damge'",
+                ]
+            ),
+            _ => panic!("expected the keys to be reported"),
+        }
+
+        match build_battle_data("Base Damage: 100\nQuality Scaling: 0.5\n") {
+            Err(BattleDataError::Keys(problems)) => {
+                assert_eq!(problems, ["'Effects' key is missing"])
+            }
+            _ => panic!("expected the missing Effects to be reported"),
+        }
+    }
+
+    #[test]
+    fn qualities_outside_the_range_are_rejected() {
+        assert_eq!(parse_quality("0"), Ok(0));
+        assert_eq!(parse_quality("999"), Ok(999));
+        assert_eq!(
+            parse_quality("1000"),
+            Err(String::from(
+                "quality must be between 0 and 999, found 1000"
+            ))
+        );
+        assert_eq!(
+            parse_quality("-1"),
+            Err(String::from("quality must be between 0 and 999, found -1"))
+        );
+        assert_eq!(
+            parse_quality("high"),
+            Err(String::from("quality must be a number"))
+        );
+    }
+}
@@ -0,0 +1,527 @@
+//! Loading the whole data folder: lists.yml, rules.yml and every item file.
+
+use crate::file_contents::{self, FileContents, FileError, IgnoreFileError, ItemFilter};
+use crate::model::{self, Item};
+use crate::rules::{self, ItemRules};
+use crate::validate_item::{self, ValidationResults};
+use crate::validation_sets::{self, ItemValidationSets};
+
+use rayon::prelude::*;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::{fmt, io};
+
+extern crate yaml_rust;
+use yaml_rust::ScanError;
+
+pub struct Dataset {
+    pub item_validation_sets: ItemValidationSets,
+    pub item_rules: ItemRules,
+    pub item_contents: Vec<FileContents>,
+    /// which files in the items folder are item files
+    pub item_filter: ItemFilter,
+    /// the files each ID and name belongs to, so the ones used by more than one item are found
+    name_files: HashMap<String, Vec<String>>,
+}
+
+#[derive(Debug)]
+pub enum DatasetError {
+    Io(String, io::Error),
+    Yaml(String, ScanError),
+    Pattern(String, glob::PatternError),
+    Rules(String, rules::RulesError),
+}
+
+impl fmt::Display for DatasetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DatasetError::Io(path, error) => write!(f, "can't load {}: {}", path, error),
+            DatasetError::Yaml(path, error) => write!(f, "can't parse {}: {}", path, error),
+            DatasetError::Pattern(path, error) => {
+                write!(f, "bad ignore pattern in {}: {}", path, error)
+            }
+            DatasetError::Rules(path, error) => write!(f, "can't use {}: {}", path, error),
+        }
+    }
+}
+
+impl Dataset {
+    pub fn load(data_folder: &str) -> Result<Dataset, DatasetError> {
+        let list_path = format!("{}/lists.yml", data_folder);
+        let item_list_contents = file_contents::load_file(&list_path)
+            .map_err(|error| DatasetError::Io(list_path.clone(), error))?;
+        let mut item_validation_sets =
+            validation_sets::build_item_validation_sets(&item_list_contents)
+                .map_err(|error| DatasetError::Yaml(list_path, error))?;
+
+        let rules_path = format!("{}/rules.yml", data_folder);
+        let rules_contents = file_contents::load_file(&rules_path)
+            .map_err(|error| DatasetError::Io(rules_path.clone(), error))?;
+        let item_rules = rules::build_item_rules(&rules_contents)
+            .map_err(|error| DatasetError::Rules(rules_path, error))?;
+
+        let (item_contents, item_filter) = load_item_files(data_folder)?;
+
+        validation_sets::add_materials_to_validation_sets(
+            &mut item_validation_sets,
+            &item_contents,
+        );
+
+        let mut dataset = Dataset {
+            item_validation_sets,
+            item_rules,
+            item_contents,
+            item_filter,
+            name_files: HashMap::new(),
+        };
+        dataset.index_names();
+        Ok(dataset)
+    }
+
+    /// the typed model of every item file that parses. References to other items are resolved
+    /// to their English names, whether they're written as an ID or a name in any language.
+    pub fn items(&self) -> Vec<Item> {
+        items(&self.item_contents)
+    }
+
+    /// validate one item file against the dataset's lists and rules, and check that its ID and
+    /// names aren't used by another item
+    pub fn validate_file(&self, file: &FileContents) -> Result<ValidationResults, FileError> {
+        match &file.yaml {
+            Ok(docs) => {
+                let mut results = validate_item::validate_item_documents(
+                    docs,
+                    &self.item_validation_sets,
+                    &self.item_rules,
+                );
+                let duplicates = self.duplicate_names(file);
+                if !duplicates.is_empty() {
+                    results.valid = false;
+                    results.fail_messages.extend(duplicates);
+                }
+                Ok(results)
+            }
+            Err(error) => Err(error.clone()),
+        }
+    }
+
+    /// a message for each of the file's ID and names that another item file also uses, naming
+    /// the other file, e.g. `ID 'red-supplement' is also used by 060-red-supplement.yml`
+    pub fn duplicate_names(&self, file: &FileContents) -> Vec<String> {
+        let item = match file.document() {
+            Ok(yaml) => Item::from_yaml(yaml),
+            Err(_) => return Vec::new(),
+        };
+        let mut messages = Vec::new();
+        for (label, name) in labelled_names(&item) {
+            let others: Vec<&str> = self
+                .name_files
+                .get(&name)
+                .into_iter()
+                .flatten()
+                .filter(|other| **other != file.name)
+                .map(String::as_str)
+                .collect();
+            if !others.is_empty() {
+                messages.push(format!(
+                    "{} '{}' is also used by {}",
+                    label,
+                    name,
+                    others.join(", ")
+                ));
+            }
+        }
+        messages
+    }
+
+    /// validate several item files in parallel, returning their results in the same order
+    pub fn validate_files(
+        &self,
+        files: &[&FileContents],
+    ) -> Vec<Result<ValidationResults, FileError>> {
+        files
+            .par_iter()
+            .map(|file| self.validate_file(file))
+            .collect()
+    }
+
+    /// replace the contents of an item file, add it if it's new, or remove it when `contents` is
+    /// None. Returns the names of the files that need validating again: the file itself, and the
+    /// files referring to the item or sharing one of its names if the change added, removed or
+    /// renamed an item (its ID or any of its names).
+    pub fn update_file(&mut self, name: &str, contents: Option<String>) -> Vec<String> {
+        let file = contents.map(|contents| FileContents::parse(name.to_string(), contents));
+        self.replace_file(name, file)
+    }
+
+    /// like `update_file`, for a file that's already been read, or couldn't be
+    pub fn replace_file(&mut self, name: &str, file: Option<FileContents>) -> Vec<String> {
+        let index = self.item_contents.iter().position(|file| file.name == name);
+        let old_names = index.map_or_else(Vec::new, |index| item_names(&self.item_contents[index]));
+
+        match (index, file) {
+            (Some(index), Some(file)) => self.item_contents[index] = file,
+            (Some(index), None) => {
+                self.item_contents.remove(index);
+            }
+            (None, Some(file)) => self.item_contents.push(file),
+            (None, None) => {}
+        }
+        let new_names = self.file(name).map_or_else(Vec::new, item_names);
+
+        let mut changed_files = Vec::new();
+        if self.file(name).is_some() {
+            changed_files.push(name.to_string());
+        }
+        if old_names != new_names {
+            self.refresh_materials();
+            let renamed: Vec<&String> = old_names.iter().chain(&new_names).collect();
+            changed_files.extend(
+                self.item_contents
+                    .iter()
+                    .filter(|file| file.name != name)
+                    .filter(|file| match file.document() {
+                        Ok(yaml) => {
+                            let item = Item::from_yaml(yaml);
+                            item.referenced_items()
+                                .into_iter()
+                                .chain(item.reference_names().iter().map(String::as_str))
+                                .any(|reference| renamed.iter().any(|name| *name == reference))
+                        }
+                        Err(_) => false,
+                    })
+                    .map(|file| file.name.clone()),
+            );
+        }
+        changed_files
+    }
+
+    /// read the item files in a folder of the items folder again, after the folder was added,
+    /// removed or renamed. Files that are no longer there are removed. Returns the names of the
+    /// files that need validating again, as `update_file` does.
+    pub fn update_folder(&mut self, items_folder: &Path, folder: &str) -> Vec<String> {
+        let mut found = Vec::new();
+        let path = items_folder.join(folder);
+        if path.is_dir() && !self.item_filter.ignores(folder) {
+            // a folder that went away while it was read is treated like one that's gone
+            let _ =
+                file_contents::find_item_files(items_folder, &path, &self.item_filter, &mut found);
+        }
+
+        let prefix = format!("{}/", folder);
+        let removed: Vec<String> = self
+            .item_contents
+            .iter()
+            .map(|file| file.name.clone())
+            .filter(|name| name.starts_with(&prefix))
+            .filter(|name| !found.iter().any(|(found, _)| found == name))
+            .collect();
+
+        let mut changed_files = Vec::new();
+        for name in removed {
+            changed_files.extend(self.replace_file(&name, None));
+        }
+        found.sort();
+        for (name, path) in found {
+            let file = file_contents::read_item_file(name.clone(), &path);
+            changed_files.extend(self.replace_file(&name, Some(file)));
+        }
+        changed_files
+    }
+
+    /// rebuild the materials set from the categories and the names of the item files
+    fn refresh_materials(&mut self) {
+        self.item_validation_sets.materials = self.item_validation_sets.categories.clone();
+        // files that don't parse don't add a name, and are reported when they're validated
+        for file in &self.item_contents {
+            self.item_validation_sets.materials.extend(item_names(file));
+        }
+        self.index_names();
+    }
+
+    fn index_names(&mut self) {
+        self.name_files.clear();
+        for file in &self.item_contents {
+            let mut names = item_names(file);
+            names.sort();
+            names.dedup();
+            for name in names {
+                self.name_files
+                    .entry(name)
+                    .or_default()
+                    .push(file.name.clone());
+            }
+        }
+    }
+
+    /// the item file with the given name
+    pub fn file(&self, name: &str) -> Option<&FileContents> {
+        self.item_contents.iter().find(|file| file.name == name)
+    }
+
+    /// the items that are referred to (as materials or Recipe Morph targets) but have no item
+    /// file, with the names of the items referring to each
+    pub fn missing_items(&self) -> BTreeMap<String, Vec<String>> {
+        let mut missing: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for item in self.items() {
+            for reference in item.referenced_items() {
+                // materials that haven't been filled in yet aren't missing items
+                if !reference.is_empty() && !self.item_validation_sets.materials.contains(reference)
+                {
+                    let referenced_by = missing.entry(reference.to_string()).or_default();
+                    if !referenced_by.contains(&item.name) {
+                        referenced_by.push(item.name.clone());
+                    }
+                }
+            }
+        }
+        missing
+    }
+
+    /// the names of the item files that don't validate cleanly
+    pub fn invalid_files(&self) -> Vec<&str> {
+        self.item_contents
+            .par_iter()
+            .filter(|file| match self.validate_file(file) {
+                Ok(results) => !results.fail_messages.is_empty(),
+                Err(_) => true,
+            })
+            .map(|file| file.name.as_str())
+            .collect()
+    }
+}
+
+/// the items of a data folder, without its lists and rules, for comparing versions of the data
+/// that might not have them (or have older ones). Files that don't parse are left out.
+pub fn load_items(data_folder: &str) -> Result<Vec<Item>, DatasetError> {
+    let (item_contents, _) = load_item_files(data_folder)?;
+    Ok(items(&item_contents))
+}
+
+/// every item file in the items folder, and the filter that picked them
+fn load_item_files(data_folder: &str) -> Result<(Vec<FileContents>, ItemFilter), DatasetError> {
+    let item_filter = ItemFilter::load(data_folder).map_err(|(path, error)| match error {
+        IgnoreFileError::Io(error) => DatasetError::Io(path, error),
+        IgnoreFileError::Pattern(error) => DatasetError::Pattern(path, error),
+    })?;
+
+    // files that can't be read or parsed are kept, and reported when they're validated
+    let mut item_contents: Vec<FileContents> = Vec::new();
+    let item_dir_path = format!("{}/items", data_folder);
+    file_contents::load_directory(&mut item_contents, &item_dir_path, &item_filter)
+        .map_err(|error| DatasetError::Io(item_dir_path, error))?;
+    Ok((item_contents, item_filter))
+}
+
+fn items(item_contents: &[FileContents]) -> Vec<Item> {
+    let mut items: Vec<Item> = item_contents
+        .iter()
+        .filter_map(|file| file.document().ok().map(Item::from_yaml))
+        .collect();
+    model::resolve_references(&mut items);
+    items
+}
+
+/// the item's ID and names, each once, with the key it's written under, e.g. `Name (ja)`
+fn labelled_names(item: &Item) -> Vec<(String, String)> {
+    let mut names = vec![
+        ("ID".to_string(), item.id()),
+        ("Name".to_string(), item.name.clone()),
+    ];
+    for (language, name) in &item.names {
+        names.push((format!("Name ({})", language), name.clone()));
+    }
+    let mut seen = Vec::new();
+    names.retain(|(_, name)| {
+        let first = !name.is_empty() && !seen.contains(name);
+        seen.push(name.clone());
+        first
+    });
+    names
+}
+
+/// the ID and names other items can refer to the item of a file by
+fn item_names(file: &FileContents) -> Vec<String> {
+    file.document()
+        .map(|yaml| Item::from_yaml(yaml).reference_names())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::PathBuf;
+
+    const UNI: &str = "Name: Uni\nItem Number: 2\nLevel: 1\nCategory: [(Uni)]\n";
+    const EXPLOSIVE_UNI: &str = "Name: Explosive Uni\nItem Number: 1\nLevel: 1\nMaterials: [Uni]\n";
+    const UNI_BAG: &str = "Name: Uni Bag\nItem Number: 3\nLevel: 1\nMaterials: [(Uni)]\n";
+
+    /// a data folder in the temporary folder, removed when dropped
+    struct DataFolder(PathBuf);
+
+    impl DataFolder {
+        fn new(test: &str) -> DataFolder {
+            let path = std::env::temp_dir().join(format!(
+                "item-validator-dataset-{}-{}",
+                test,
+                std::process::id()
+            ));
+            let _ = fs::remove_dir_all(&path);
+            fs::create_dir_all(path.join("items")).unwrap();
+            fs::write(path.join("lists.yml"), "Item Categories: [(Uni)]\n").unwrap();
+            fs::write(path.join("rules.yml"), "Rules: []\n").unwrap();
+            for (name, contents) in &[
+                ("001-explosive-uni.yml", EXPLOSIVE_UNI),
+                ("002-uni.yml", UNI),
+                ("003-uni-bag.yml", UNI_BAG),
+            ] {
+                fs::write(path.join("items").join(name), contents).unwrap();
+            }
+            DataFolder(path)
+        }
+
+        fn dataset(&self) -> Dataset {
+            Dataset::load(&self.0.to_string_lossy()).unwrap()
+        }
+    }
+
+    impl Drop for DataFolder {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn sorted(mut names: Vec<String>) -> Vec<String> {
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn edits_that_keep_the_names_only_change_the_file() {
+        let folder = DataFolder::new("edit");
+        let mut dataset = folder.dataset();
+        let contents = UNI.replace("Level: 1", "Level: 2");
+        assert_eq!(
+            dataset.update_file("002-uni.yml", Some(contents.clone())),
+            ["002-uni.yml"]
+        );
+        assert_eq!(dataset.file("002-uni.yml").unwrap().contents, contents);
+    }
+
+    #[test]
+    fn renames_revalidate_the_items_referring_to_either_name() {
+        let folder = DataFolder::new("rename");
+        let mut dataset = folder.dataset();
+        let changed = dataset.update_file(
+            "002-uni.yml",
+            Some(UNI.replace("Name: Uni", "Name: Sea Uni")),
+        );
+        assert_eq!(sorted(changed), ["001-explosive-uni.yml", "002-uni.yml"]);
+
+        let materials = &dataset.item_validation_sets.materials;
+        assert!(materials.contains("Sea Uni") && !materials.contains("Uni"));
+        assert!(materials.contains("(Uni)"));
+        assert_eq!(dataset.missing_items().keys().collect::<Vec<_>>(), ["Uni"]);
+    }
+
+    #[test]
+    fn removed_and_added_files_revalidate_their_dependents() {
+        let folder = DataFolder::new("remove");
+        let mut dataset = folder.dataset();
+        assert_eq!(
+            dataset.update_file("002-uni.yml", None),
+            ["001-explosive-uni.yml"]
+        );
+        assert!(dataset.file("002-uni.yml").is_none());
+        assert!(!dataset.item_validation_sets.materials.contains("Uni"));
+
+        let changed = dataset.update_file("uni.yml", Some(UNI.to_string()));
+        assert_eq!(changed, ["uni.yml", "001-explosive-uni.yml"]);
+        assert!(dataset.missing_items().is_empty());
+    }
+
+    #[test]
+    fn names_shared_by_two_files_are_reported_on_both() {
+        let folder = DataFolder::new("duplicate");
+        let mut dataset = folder.dataset();
+        let changed = dataset.update_file("004-uni.yml", Some(UNI.to_string()));
+        // the new file uses the names of 002-uni.yml, which is validated again too
+        assert!(changed.contains(&String::from("002-uni.yml")));
+        let duplicates = dataset.duplicate_names(dataset.file("002-uni.yml").unwrap());
+        assert_eq!(
+            duplicates,
+            [
+                "ID 'uni' is also used by 004-uni.yml",
+                "Name 'Uni' is also used by 004-uni.yml",
+            ]
+        );
+    }
+
+    #[test]
+    fn unreadable_files_stay_in_the_dataset_and_fail_validation() {
+        let folder = DataFolder::new("unreadable");
+        let mut dataset = folder.dataset();
+        let missing = folder.0.join("items").join("no-such-file.yml");
+        let file = file_contents::read_item_file(String::from("002-uni.yml"), &missing);
+        // the Uni item went away with its contents
+        let changed = dataset.replace_file("002-uni.yml", Some(file));
+        assert_eq!(sorted(changed), ["001-explosive-uni.yml", "002-uni.yml"]);
+        match dataset.validate_file(dataset.file("002-uni.yml").unwrap()) {
+            Err(FileError::Unreadable(_)) => {}
+            _ => panic!("expected the file to be unreadable"),
+        }
+    }
+
+    #[test]
+    fn folders_are_read_again_when_they_change() {
+        let folder = DataFolder::new("folder");
+        let mut dataset = folder.dataset();
+        let items_folder = folder.0.join("items");
+        let sea = items_folder.join("sea");
+        fs::create_dir_all(sea.join("deep")).unwrap();
+        fs::write(
+            sea.join("010-sea-uni.yml"),
+            UNI.replace("Name: Uni", "Name: Sea Uni"),
+        )
+        .unwrap();
+        fs::write(
+            sea.join("deep").join("011-deep-uni.yml"),
+            UNI.replace("Name: Uni", "Name: Deep Uni"),
+        )
+        .unwrap();
+        fs::write(sea.join("notes.txt"), "not an item").unwrap();
+
+        let changed = dataset.update_folder(&items_folder, "sea");
+        assert_eq!(
+            changed,
+            ["sea/010-sea-uni.yml", "sea/deep/011-deep-uni.yml"]
+        );
+        assert!(dataset.item_validation_sets.materials.contains("Deep Uni"));
+
+        // a folder that's gone takes its files with it
+        fs::remove_dir_all(sea.join("deep")).unwrap();
+        assert_eq!(
+            dataset.update_folder(&items_folder, "sea"),
+            ["sea/010-sea-uni.yml"]
+        );
+        assert!(dataset.file("sea/deep/011-deep-uni.yml").is_none());
+        fs::remove_dir_all(&sea).unwrap();
+        assert!(dataset.update_folder(&items_folder, "sea").is_empty());
+        assert!(dataset.file("sea/010-sea-uni.yml").is_none());
+        assert!(!dataset.item_validation_sets.materials.contains("Sea Uni"));
+    }
+
+    #[test]
+    fn ignored_folders_are_left_out() {
+        let folder = DataFolder::new("ignored");
+        fs::write(folder.0.join(file_contents::IGNORE_FILE), "drafts\n").unwrap();
+        let mut dataset = folder.dataset();
+        let items_folder = folder.0.join("items");
+        fs::create_dir_all(items_folder.join("drafts")).unwrap();
+        fs::write(items_folder.join("drafts").join("draft.yml"), UNI).unwrap();
+
+        assert!(dataset.update_folder(&items_folder, "drafts").is_empty());
+        assert!(dataset.file("drafts/draft.yml").is_none());
+    }
+}
@@ -0,0 +1,429 @@
+//! Semantic differences between two versions of the items, for reviewing contributions without
+//! reading YAML line diffs.
+//!
+//! Items are matched by their stable ID, so an item renamed with `rename-item` (which keeps its
+//! ID) shows up as renamed rather than as one item removed and another added. Material loops are
+//! matched by their Position and levels by their place in the loop. Changes are described in the
+//! words of the item files, e.g. `loop Position 5 threshold Fire 2 → 3`.
+
+use crate::model::{ElementValue, Equipment, Item, LoopLevel, MaterialLoop, Synthesis, STAT_NAMES};
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+pub enum ItemDiff {
+    Added(String),
+    Removed(String),
+    /// an item in both versions: its (new) name and what changed about it
+    Changed(String, Vec<String>),
+}
+
+impl fmt::Display for ItemDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ItemDiff::Added(name) => write!(f, "new item {}", name),
+            ItemDiff::Removed(name) => write!(f, "removed item {}", name),
+            ItemDiff::Changed(name, changes) => {
+                let lines: Vec<String> = changes
+                    .iter()
+                    .map(|change| format!("{}: {}", name, change))
+                    .collect();
+                write!(f, "{}", lines.join("\n"))
+            }
+        }
+    }
+}
+
+/// how the items changed from `old` to `new`, in item number order. Items that didn't change
+/// are left out.
+pub fn diff(old: &[Item], new: &[Item]) -> Vec<ItemDiff> {
+    let old: BTreeMap<String, &Item> = old.iter().map(|item| (item.id(), item)).collect();
+    let new: BTreeMap<String, &Item> = new.iter().map(|item| (item.id(), item)).collect();
+
+    let mut diffs: Vec<(i64, ItemDiff)> = Vec::new();
+    for (id, old_item) in &old {
+        match new.get(id) {
+            Some(new_item) => {
+                let changes = item_changes(old_item, new_item);
+                if !changes.is_empty() {
+                    diffs.push((
+                        new_item.item_number,
+                        ItemDiff::Changed(new_item.name.clone(), changes),
+                    ));
+                }
+            }
+            None => diffs.push((
+                old_item.item_number,
+                ItemDiff::Removed(old_item.name.clone()),
+            )),
+        }
+    }
+    for (id, new_item) in &new {
+        if !old.contains_key(id) {
+            diffs.push((new_item.item_number, ItemDiff::Added(new_item.name.clone())));
+        }
+    }
+    // the sort is stable, so items with the same number stay in ID order
+    diffs.sort_by_key(|(item_number, _)| *item_number);
+    diffs.into_iter().map(|(_, diff)| diff).collect()
+}
+
+fn item_changes(old: &Item, new: &Item) -> Vec<String> {
+    let mut changes = Vec::new();
+    if old.name != new.name {
+        changes.push(format!("renamed from {}", old.name));
+    }
+    translation_changes(&mut changes, "Name", &old.names, &new.names);
+    value_change(
+        &mut changes,
+        "Item Number",
+        old.item_number,
+        new.item_number,
+    );
+    value_change(&mut changes, "Level", old.level, new.level);
+    list_changes(
+        &mut changes,
+        "Classifications",
+        &old.classifications,
+        &new.classifications,
+    );
+    value_change(
+        &mut changes,
+        "CC Cost",
+        optional(old.cc_cost),
+        optional(new.cc_cost),
+    );
+    list_changes(&mut changes, "Category", &old.categories, &new.categories);
+    element_changes(&mut changes, "Element", &old.elements, &new.elements);
+    list_changes(&mut changes, "Materials", &old.materials, &new.materials);
+    list_changes(
+        &mut changes,
+        "Gather Locations",
+        &old.gather_locations,
+        &new.gather_locations,
+    );
+    list_changes(
+        &mut changes,
+        "Gathering Tools",
+        &old.gathering_tools,
+        &new.gathering_tools,
+    );
+    match (&old.synthesis, &new.synthesis) {
+        (Some(old), Some(new)) => synthesis_changes(&mut changes, old, new),
+        (None, Some(_)) => changes.push("Synthesis added".to_string()),
+        (Some(_), None) => changes.push("Synthesis removed".to_string()),
+        (None, None) => {}
+    }
+    match (&old.equipment, &new.equipment) {
+        (Some(old), Some(new)) => equipment_changes(&mut changes, old, new),
+        (None, Some(_)) => changes.push("Equipment added".to_string()),
+        (Some(_), None) => changes.push("Equipment removed".to_string()),
+        (None, None) => {}
+    }
+    changes
+}
+
+fn synthesis_changes(changes: &mut Vec<String>, old: &Synthesis, new: &Synthesis) {
+    value_change(
+        changes,
+        "Required Materials",
+        old.required_materials,
+        new.required_materials,
+    );
+    value_change(
+        changes,
+        "Required Alchemy Level",
+        old.required_alchemy_level,
+        new.required_alchemy_level,
+    );
+
+    let positions: BTreeSet<i64> = old
+        .material_loops
+        .iter()
+        .chain(&new.material_loops)
+        .map(|material_loop| material_loop.position)
+        .collect();
+    for position in positions {
+        let label = format!("loop Position {}", position);
+        match (old.material_loop(position), new.material_loop(position)) {
+            (Some(old), Some(new)) => loop_changes(changes, &label, old, new),
+            (None, Some(new)) => changes.push(format!(
+                "{} added ({}, Material {})",
+                label, new.name, new.material
+            )),
+            (Some(old), None) => changes.push(format!("{} removed ({})", label, old.name)),
+            (None, None) => {}
+        }
+    }
+}
+
+fn loop_changes(changes: &mut Vec<String>, label: &str, old: &MaterialLoop, new: &MaterialLoop) {
+    value_change(changes, &format!("{} name", label), &old.name, &new.name);
+    value_change(
+        changes,
+        &format!("{} Distance", label),
+        old.distance,
+        new.distance,
+    );
+    value_change(
+        changes,
+        &format!("{} Linked From Position", label),
+        optional(old.linked_from_position),
+        optional(new.linked_from_position),
+    );
+    value_change(
+        changes,
+        &format!("{} Material", label),
+        &old.material,
+        &new.material,
+    );
+    element_changes(
+        changes,
+        &format!("{} threshold", label),
+        &old.unlock,
+        &new.unlock,
+    );
+
+    for index in 0..old.levels.len().max(new.levels.len()) {
+        let level_label = format!("{} level {}", label, index + 1);
+        match (old.levels.get(index), new.levels.get(index)) {
+            (Some(old), Some(new)) => level_changes(changes, &level_label, old, new),
+            (None, Some(new)) => changes.push(format!("{} added ({})", level_label, new.effect)),
+            (Some(old), None) => changes.push(format!("{} removed ({})", level_label, old.effect)),
+            (None, None) => {}
+        }
+    }
+}
+
+fn level_changes(changes: &mut Vec<String>, label: &str, old: &LoopLevel, new: &LoopLevel) {
+    value_change(
+        changes,
+        &format!("{} effect", label),
+        &old.effect,
+        &new.effect,
+    );
+    translation_changes(
+        changes,
+        &format!("{} effect", label),
+        &old.effect_names,
+        &new.effect_names,
+    );
+    element_changes(
+        changes,
+        &format!("{} Element", label),
+        &old.elements,
+        &new.elements,
+    );
+    value_change(
+        changes,
+        &format!("{} Recipe", label),
+        old.recipe.as_deref().unwrap_or("none"),
+        new.recipe.as_deref().unwrap_or("none"),
+    );
+}
+
+fn equipment_changes(changes: &mut Vec<String>, old: &Equipment, new: &Equipment) {
+    for stat in &STAT_NAMES {
+        value_change(
+            changes,
+            &format!("Stats {}", stat),
+            old.stats.get(stat).unwrap_or(0),
+            new.stats.get(stat).unwrap_or(0),
+        );
+    }
+    list_changes(
+        changes,
+        "Equippable By",
+        &old.equippable_by,
+        &new.equippable_by,
+    );
+}
+
+fn value_change<T: PartialEq + fmt::Display>(
+    changes: &mut Vec<String>,
+    label: &str,
+    old: T,
+    new: T,
+) {
+    if old != new {
+        changes.push(format!("{} {} → {}", label, old, new));
+    }
+}
+
+/// the entries added to and removed from a list. Reordering a list isn't a change.
+fn list_changes(changes: &mut Vec<String>, label: &str, old: &[String], new: &[String]) {
+    for value in new.iter().filter(|value| !old.contains(value)) {
+        changes.push(format!("{}: added {}", label, value));
+    }
+    for value in old.iter().filter(|value| !new.contains(value)) {
+        changes.push(format!("{}: removed {}", label, value));
+    }
+}
+
+/// elements added, removed, or with a different value, e.g. `threshold Fire 2 → 3`
+fn element_changes(
+    changes: &mut Vec<String>,
+    label: &str,
+    old: &[ElementValue],
+    new: &[ElementValue],
+) {
+    let find = |elements: &[ElementValue], element: &str| {
+        elements
+            .iter()
+            .find(|value| value.element == element)
+            .map(|value| value.value)
+    };
+    for value in new {
+        match find(old, &value.element) {
+            Some(old_value) => value_change(
+                changes,
+                &format!("{} {}", label, value.element),
+                optional(old_value),
+                optional(value.value),
+            ),
+            None => changes.push(format!("{}: added {}", label, element(value))),
+        }
+    }
+    for value in old {
+        if find(new, &value.element).is_none() {
+            changes.push(format!("{}: removed {}", label, element(value)));
+        }
+    }
+}
+
+/// translations added, removed or changed, by language
+fn translation_changes(
+    changes: &mut Vec<String>,
+    label: &str,
+    old: &BTreeMap<String, String>,
+    new: &BTreeMap<String, String>,
+) {
+    let languages: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+    for language in languages {
+        let label = format!("{} ({})", label, language);
+        match (old.get(language), new.get(language)) {
+            (Some(old), Some(new)) => value_change(changes, &label, old, new),
+            (None, Some(new)) => changes.push(format!("{}: added {}", label, new)),
+            (Some(old), None) => changes.push(format!("{}: removed {}", label, old)),
+            (None, None) => {}
+        }
+    }
+}
+
+fn element(value: &ElementValue) -> String {
+    match value.value {
+        Some(number) => format!("{} {}", value.element, number),
+        None => value.element.clone(),
+    }
+}
+
+/// missing values are shown as `none`
+fn optional(value: Option<i64>) -> String {
+    value
+        .map(|value| value.to_string())
+        .unwrap_or_else(|| "none".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model;
+
+    const RED_SUPPLEMENT: &str = "
+Name: Red Supplement
+Item Number: 59
+Level: 1
+Category: [(Supplement)]
+Synthesis:
+  Material Loops:
+  - Effect 1:
+      Position: 1
+      Material: (Flower)
+      Levels:
+      - Synth Quality +5:
+          Element:
+          - Fire: 2
+      Unlock:
+      - Fire: 2
+";
+
+    fn items(files: &[&str]) -> Vec<Item> {
+        files
+            .iter()
+            .map(|contents| model::load_item(contents).unwrap())
+            .collect()
+    }
+
+    fn item(name: &str, number: i64) -> String {
+        format!("Name: {}\nItem Number: {}\nLevel: 1\n", name, number)
+    }
+
+    /// the diffs, as they're printed
+    fn lines(old: &[&str], new: &[&str]) -> Vec<String> {
+        diff(&items(old), &items(new))
+            .iter()
+            .map(ItemDiff::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn unchanged_items_are_left_out() {
+        assert!(lines(&[RED_SUPPLEMENT], &[RED_SUPPLEMENT]).is_empty());
+        // reordering a list isn't a change
+        let reordered = RED_SUPPLEMENT.replace("[(Supplement)]", "[(Supplement), (Medicine)]");
+        let reordered_back = RED_SUPPLEMENT.replace("[(Supplement)]", "[(Medicine), (Supplement)]");
+        assert!(lines(&[&reordered], &[&reordered_back]).is_empty());
+    }
+
+    #[test]
+    fn added_and_removed_items_in_item_number_order() {
+        let uni = item("Uni", 3);
+        let ash = item("Burnt Ash", 389);
+        assert_eq!(
+            lines(&[RED_SUPPLEMENT, &ash], &[&uni, RED_SUPPLEMENT]),
+            ["new item Uni", "removed item Burnt Ash"]
+        );
+    }
+
+    #[test]
+    fn changes_are_described_item_by_item() {
+        let changed = RED_SUPPLEMENT
+            .replace("Level: 1", "Level: 2")
+            .replace("[(Supplement)]", "[(Medicine)]")
+            .replace("Unlock:\n      - Fire: 2", "Unlock:\n      - Fire: 3")
+            .replace(
+                "- Fire: 2\n      Unlock",
+                "- Fire: 2\n          Recipe: Uni\n      Unlock",
+            );
+        assert_eq!(
+            lines(&[RED_SUPPLEMENT], &[&changed]),
+            [[
+                "Red Supplement: Level 1 → 2",
+                "Red Supplement: Category: added (Medicine)",
+                "Red Supplement: Category: removed (Supplement)",
+                "Red Supplement: loop Position 1 threshold Fire 2 → 3",
+                "Red Supplement: loop Position 1 level 1 Recipe none → Uni",
+            ]
+            .join("\n")]
+        );
+    }
+
+    #[test]
+    fn renamed_items_are_matched_by_id() {
+        let renamed = RED_SUPPLEMENT.replace(
+            "Name: Red Supplement\n",
+            "Name: Crimson Supplement\nID: red-supplement\n",
+        );
+        assert_eq!(
+            lines(&[RED_SUPPLEMENT], &[&renamed]),
+            ["Crimson Supplement: renamed from Red Supplement"]
+        );
+
+        // without the ID, the old item is gone and a new one has appeared
+        let replaced = RED_SUPPLEMENT.replace("Red Supplement", "Crimson Supplement");
+        assert_eq!(
+            lines(&[RED_SUPPLEMENT], &[&replaced]),
+            ["removed item Red Supplement", "new item Crimson Supplement"]
+        );
+    }
+}
@@ -0,0 +1,298 @@
+//! CSV export: a folder of flat tables, one per entity, for spreadsheets.
+//!
+//! Rows refer to their item by its English name, and to material loops by item name and position.
+//! Values that appear once per item (including equipment stats) are columns of items.csv.
+//! Translated names and effects are in item_names.csv and loop_level_effects.csv, one row per
+//! language other than English.
+
+use crate::model::{self, ElementValue, Item};
+
+use std::fs;
+use std::path::Path;
+
+/// a table being built up in memory, before it's written out
+struct Table {
+    name: &'static str,
+    header: &'static [&'static str],
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    fn new(name: &'static str, header: &'static [&'static str]) -> Table {
+        Table {
+            name,
+            header,
+            rows: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, row: Vec<String>) {
+        self.rows.push(row);
+    }
+
+    fn write(&self, folder: &Path) -> csv::Result<()> {
+        let mut writer = csv::Writer::from_path(folder.join(format!("{}.csv", self.name)))?;
+        writer.write_record(self.header)?;
+        for row in &self.rows {
+            writer.write_record(row)?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+/// write the items as CSV files into the given folder, creating it if needed
+pub fn export(items: &[Item], folder: &str) -> csv::Result<()> {
+    let folder = Path::new(folder);
+    fs::create_dir_all(folder)?;
+
+    let mut item_table = Table::new(
+        "items",
+        &[
+            "name",
+            "id",
+            "item_number",
+            "level",
+            "cc_cost",
+            "required_materials",
+            "required_alchemy_level",
+            "hp",
+            "mp",
+            "atk",
+            "def",
+            "spd",
+        ],
+    );
+    let mut item_names = Table::new("item_names", &["item", "language", "name"]);
+    let mut classifications = Table::new("classifications", &["item", "classification"]);
+    let mut categories = Table::new("categories", &["item", "category"]);
+    let mut elements = Table::new("elements", &["item", "element", "value"]);
+    let mut materials = Table::new("materials", &["item", "kind", "material"]);
+    let mut gather_locations = Table::new("gather_locations", &["item", "location"]);
+    let mut gathering_tools = Table::new("gathering_tools", &["item", "tool"]);
+    let mut equippable_by = Table::new("equippable_by", &["item", "character"]);
+    let mut material_loops = Table::new(
+        "material_loops",
+        &[
+            "item",
+            "name",
+            "distance",
+            "position",
+            "linked_from_position",
+            "material_kind",
+            "material",
+        ],
+    );
+    let mut unlock_requirements = Table::new(
+        "unlock_requirements",
+        &["item", "position", "element", "value"],
+    );
+    let mut loop_levels = Table::new(
+        "loop_levels",
+        &["item", "position", "level", "effect", "recipe"],
+    );
+    let mut loop_level_effects = Table::new(
+        "loop_level_effects",
+        &["item", "position", "level", "language", "effect"],
+    );
+    let mut loop_level_elements = Table::new(
+        "loop_level_elements",
+        &["item", "position", "level", "element", "value"],
+    );
+
+    for item in items {
+        let name = &item.name;
+        let synthesis = item.synthesis.as_ref();
+        let stat = |stat: &str| {
+            item.equipment
+                .as_ref()
+                .and_then(|equipment| equipment.stats.get(stat))
+                .map(|value| value.to_string())
+                .unwrap_or_default()
+        };
+        item_table.push(vec![
+            name.clone(),
+            item.id(),
+            item.item_number.to_string(),
+            item.level.to_string(),
+            optional(item.cc_cost),
+            optional(synthesis.map(|synthesis| synthesis.required_materials)),
+            optional(synthesis.map(|synthesis| synthesis.required_alchemy_level)),
+            stat("HP"),
+            stat("MP"),
+            stat("ATK"),
+            stat("DEF"),
+            stat("SPD"),
+        ]);
+
+        for (language, translation) in &item.names {
+            item_names.push(vec![name.clone(), language.clone(), translation.clone()]);
+        }
+        for classification in &item.classifications {
+            classifications.push(vec![name.clone(), classification.clone()]);
+        }
+        for category in &item.categories {
+            let (_, category) = model::parse_material(category);
+            categories.push(vec![name.clone(), category.to_string()]);
+        }
+        for element in &item.elements {
+            elements.push(element_row(vec![name.clone()], element));
+        }
+        for material in &item.materials {
+            let (kind, material) = model::parse_material(material);
+            materials.push(vec![
+                name.clone(),
+                kind.as_str().to_string(),
+                material.to_string(),
+            ]);
+        }
+        for location in &item.gather_locations {
+            gather_locations.push(vec![name.clone(), location.clone()]);
+        }
+        for tool in &item.gathering_tools {
+            gathering_tools.push(vec![name.clone(), tool.clone()]);
+        }
+        for character in item
+            .equipment
+            .iter()
+            .flat_map(|equipment| &equipment.equippable_by)
+        {
+            equippable_by.push(vec![name.clone(), character.clone()]);
+        }
+
+        for material_loop in synthesis
+            .iter()
+            .flat_map(|synthesis| &synthesis.material_loops)
+        {
+            let position = material_loop.position.to_string();
+            let (kind, material) = model::parse_material(&material_loop.material);
+            material_loops.push(vec![
+                name.clone(),
+                material_loop.name.clone(),
+                material_loop.distance.to_string(),
+                position.clone(),
+                optional(material_loop.linked_from_position),
+                kind.as_str().to_string(),
+                material.to_string(),
+            ]);
+            for element in &material_loop.unlock {
+                unlock_requirements
+                    .push(element_row(vec![name.clone(), position.clone()], element));
+            }
+            for (index, level) in material_loop.levels.iter().enumerate() {
+                let level_number = (index + 1).to_string();
+                loop_levels.push(vec![
+                    name.clone(),
+                    position.clone(),
+                    level_number.clone(),
+                    level.effect.clone(),
+                    level.recipe.clone().unwrap_or_default(),
+                ]);
+                for (language, effect) in &level.effect_names {
+                    loop_level_effects.push(vec![
+                        name.clone(),
+                        position.clone(),
+                        level_number.clone(),
+                        language.clone(),
+                        effect.clone(),
+                    ]);
+                }
+                for element in &level.elements {
+                    loop_level_elements.push(element_row(
+                        vec![name.clone(), position.clone(), level_number.clone()],
+                        element,
+                    ));
+                }
+            }
+        }
+    }
+
+    for table in &[
+        item_table,
+        item_names,
+        classifications,
+        categories,
+        elements,
+        materials,
+        gather_locations,
+        gathering_tools,
+        equippable_by,
+        material_loops,
+        unlock_requirements,
+        loop_levels,
+        loop_level_effects,
+        loop_level_elements,
+    ] {
+        table.write(folder)?;
+    }
+    Ok(())
+}
+
+/// missing values are written as empty cells
+fn optional(value: Option<i64>) -> String {
+    value.map(|value| value.to_string()).unwrap_or_default()
+}
+
+fn element_row(mut row: Vec<String>, element: &ElementValue) -> Vec<String> {
+    row.push(element.element.clone());
+    row.push(optional(element.value));
+    row
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::{test_items, test_path};
+
+    /// the rows of one of the exported tables, header first
+    fn rows(folder: &Path, table: &str) -> Vec<Vec<String>> {
+        let mut reader = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .from_path(folder.join(format!("{}.csv", table)))
+            .unwrap();
+        reader
+            .records()
+            .map(|record| record.unwrap().iter().map(String::from).collect())
+            .collect()
+    }
+
+    #[test]
+    fn every_table_is_written_with_a_header() {
+        let folder = test_path("csv");
+        export(&test_items(), &folder.to_string_lossy()).unwrap();
+
+        let items = rows(&folder, "items");
+        assert_eq!(items.len(), 3);
+        assert_eq!(items[0][..3], ["name", "id", "item_number"]);
+        assert_eq!(
+            items[2][..7],
+            ["Explosive Uni", "explosive-uni", "1", "2", "2", "3", "1"]
+        );
+        // optional values are left empty
+        assert_eq!(items[1][4], "");
+
+        assert_eq!(
+            rows(&folder, "materials"),
+            [
+                ["item", "kind", "material"],
+                ["Explosive Uni", "item", "Uni"],
+                ["Explosive Uni", "category", "Gunpowder"],
+            ]
+        );
+        assert_eq!(rows(&folder, "item_names")[1..], [["Uni", "ja", "ウニ"]]);
+        assert_eq!(
+            rows(&folder, "loop_levels")[1..],
+            [
+                ["Explosive Uni", "0", "1", "Scatter", ""],
+                ["Explosive Uni", "1", "1", "Recipe Morph", "Uni"],
+            ]
+        );
+        assert_eq!(
+            rows(&folder, "unlock_requirements")[1..],
+            [["Explosive Uni", "1", "Ice", "1"]]
+        );
+        assert_eq!(rows(&folder, "equippable_by"), [["item", "character"]]);
+
+        fs::remove_dir_all(&folder).unwrap();
+    }
+}
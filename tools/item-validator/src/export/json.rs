@@ -0,0 +1,138 @@
+//! JSON export: a single document holding every item, shaped like the typed model.
+//!
+//! Materials are written as `{"kind": "category", "name": "Uni"}` or
+//! `{"kind": "item", "name": "Red Supplement"}`, and categories lose their parentheses, so readers
+//! don't need to know the conventions of the YAML files. `name` and `effect` are in English, with
+//! translations keyed by language in `names` and `effect_names`.
+
+use crate::model::{self, ElementValue, Item, MaterialLoop};
+
+use serde_json::{json, Value};
+use std::fs;
+use std::io;
+
+/// write the items to a JSON file at the given path
+pub fn export(items: &[Item], path: &str) -> io::Result<()> {
+    let document = json!({
+        "items": items.iter().map(item_json).collect::<Vec<_>>(),
+    });
+    fs::write(path, serde_json::to_string_pretty(&document)?)
+}
+
+pub fn item_json(item: &Item) -> Value {
+    json!({
+        "name": item.name,
+        "id": item.id(),
+        "names": item.names,
+        "item_number": item.item_number,
+        "level": item.level,
+        "classifications": item.classifications,
+        "cc_cost": item.cc_cost,
+        "categories": item
+            .categories
+            .iter()
+            .map(|category| model::parse_material(category).1)
+            .collect::<Vec<_>>(),
+        "elements": elements_json(&item.elements),
+        "materials": item.materials.iter().map(|m| material_json(m)).collect::<Vec<_>>(),
+        "gather_locations": item.gather_locations,
+        "gathering_tools": item.gathering_tools,
+        "synthesis": item.synthesis.as_ref().map(|synthesis| json!({
+            "required_materials": synthesis.required_materials,
+            "required_alchemy_level": synthesis.required_alchemy_level,
+            "material_loops": synthesis
+                .material_loops
+                .iter()
+                .map(material_loop_json)
+                .collect::<Vec<_>>(),
+        })),
+        "equipment": item.equipment.as_ref().map(|equipment| json!({
+            "stats": {
+                "hp": equipment.stats.hp,
+                "mp": equipment.stats.mp,
+                "atk": equipment.stats.atk,
+                "def": equipment.stats.def,
+                "spd": equipment.stats.spd,
+            },
+            "equippable_by": equipment.equippable_by,
+        })),
+    })
+}
+
+fn material_loop_json(material_loop: &MaterialLoop) -> Value {
+    json!({
+        "name": material_loop.name,
+        "distance": material_loop.distance,
+        "position": material_loop.position,
+        "linked_from_position": material_loop.linked_from_position,
+        "material": material_json(&material_loop.material),
+        "levels": material_loop
+            .levels
+            .iter()
+            .map(|level| json!({
+                "effect": level.effect,
+                "effect_names": level.effect_names,
+                "elements": elements_json(&level.elements),
+                "recipe": level.recipe,
+            }))
+            .collect::<Vec<_>>(),
+        "unlock": elements_json(&material_loop.unlock),
+    })
+}
+
+fn material_json(material: &str) -> Value {
+    let (kind, name) = model::parse_material(material);
+    json!({ "kind": kind.as_str(), "name": name })
+}
+
+fn elements_json(elements: &[ElementValue]) -> Value {
+    elements
+        .iter()
+        .map(|element| json!({ "element": element.element, "value": element.value }))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::{test_items, test_path};
+
+    #[test]
+    fn items_are_written_with_materials_spelled_out() {
+        let path = test_path("json");
+        export(&test_items(), &path.to_string_lossy()).unwrap();
+        let document: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let items = document["items"].as_array().unwrap();
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0]["names"], json!({ "ja": "ウニ" }));
+        assert_eq!(items[0]["categories"], json!(["Uni"]));
+        assert_eq!(items[0]["synthesis"], Value::Null);
+
+        let explosive_uni = &items[1];
+        assert_eq!(explosive_uni["id"], "explosive-uni");
+        assert_eq!(explosive_uni["cc_cost"], 2);
+        assert_eq!(
+            explosive_uni["materials"],
+            json!([
+                { "kind": "item", "name": "Uni" },
+                { "kind": "category", "name": "Gunpowder" },
+            ])
+        );
+        assert_eq!(
+            explosive_uni["elements"],
+            json!([{ "element": "Fire", "value": 2 }])
+        );
+
+        let loops = &explosive_uni["synthesis"]["material_loops"];
+        assert_eq!(loops[0]["levels"][0]["effect"], "Scatter");
+        assert_eq!(loops[0]["levels"][0]["effect_names"]["ja"], "拡散");
+        assert_eq!(loops[1]["linked_from_position"], 0);
+        assert_eq!(loops[1]["levels"][0]["recipe"], "Uni");
+        assert_eq!(
+            loops[1]["unlock"],
+            json!([{ "element": "Ice", "value": 1 }])
+        );
+    }
+}
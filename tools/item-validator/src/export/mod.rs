@@ -0,0 +1,76 @@
+//! Writing the dataset out in other formats.
+
+pub mod csv;
+pub mod json;
+pub mod sqlite;
+
+/// items for the exporter tests: a gathered item, and an item synthesized from it
+#[cfg(test)]
+fn test_items() -> Vec<crate::model::Item> {
+    let uni = "
+Name:
+  en: Uni
+  ja: ウニ
+Item Number: 2
+Level: 1
+Classifications: [Materials]
+Category: [(Uni)]
+Element: [Ice]
+Gather Locations: [Sandy Field]
+Gathering Tools: [Gathering Rod]
+";
+    let explosive_uni = "
+Name: Explosive Uni
+Item Number: 1
+Level: 2
+Classifications: [Battle Item]
+CC Cost: 2
+Category: [(Bomb)]
+Element:
+- Fire: 2
+Materials: [Uni, (Gunpowder)]
+Synthesis:
+  Required Materials: 3
+  Required Alchemy Level: 1
+  Material Loops:
+  - Effect 1:
+      Distance: 0
+      Position: 0
+      Material: Uni
+      Levels:
+      - Scatter:
+          Element:
+          - Fire: 1
+          Translations:
+            ja: 拡散
+  - Recipe:
+      Distance: 1
+      Position: 1
+      Linked From Position: 0
+      Material: (Gunpowder)
+      Levels:
+      - Recipe Morph:
+          Recipe: Uni
+          Element:
+          - Fire: 2
+      Unlock:
+      - Ice: 1
+";
+    vec![
+        crate::model::load_item(uni).unwrap(),
+        crate::model::load_item(explosive_uni).unwrap(),
+    ]
+}
+
+/// a path in the temporary folder for a test's export, with nothing there yet
+#[cfg(test)]
+fn test_path(name: &str) -> std::path::PathBuf {
+    let path = std::env::temp_dir().join(format!(
+        "item-validator-export-{}-{}",
+        name,
+        std::process::id()
+    ));
+    let _ = std::fs::remove_dir_all(&path);
+    let _ = std::fs::remove_file(&path);
+    path
+}
@@ -0,0 +1,444 @@
+//! SQLite export: one row per item, with everything that repeats in its own table.
+
+use crate::model::{ElementValue, Item};
+
+use rusqlite::{params, Connection, Transaction};
+use std::collections::HashMap;
+use std::path::Path;
+use std::{fmt, fs, io};
+
+const SCHEMA: &str = "
+CREATE TABLE items (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    stable_id TEXT NOT NULL UNIQUE,
+    item_number INTEGER NOT NULL,
+    level INTEGER NOT NULL,
+    cc_cost INTEGER,
+    required_materials INTEGER,
+    required_alchemy_level INTEGER
+);
+CREATE TABLE item_names (
+    item_id INTEGER NOT NULL REFERENCES items(id),
+    language TEXT NOT NULL,
+    name TEXT NOT NULL
+);
+CREATE TABLE classifications (
+    item_id INTEGER NOT NULL REFERENCES items(id),
+    classification TEXT NOT NULL
+);
+CREATE TABLE categories (
+    item_id INTEGER NOT NULL REFERENCES items(id),
+    category TEXT NOT NULL
+);
+CREATE TABLE elements (
+    item_id INTEGER NOT NULL REFERENCES items(id),
+    element TEXT NOT NULL,
+    value INTEGER
+);
+CREATE TABLE materials (
+    item_id INTEGER NOT NULL REFERENCES items(id),
+    material TEXT NOT NULL,
+    -- the item the material names, or NULL for a category
+    material_item_id INTEGER REFERENCES items(id)
+);
+CREATE TABLE gather_locations (
+    item_id INTEGER NOT NULL REFERENCES items(id),
+    location TEXT NOT NULL
+);
+CREATE TABLE gathering_tools (
+    item_id INTEGER NOT NULL REFERENCES items(id),
+    tool TEXT NOT NULL
+);
+CREATE TABLE equipment (
+    item_id INTEGER PRIMARY KEY REFERENCES items(id),
+    hp INTEGER NOT NULL,
+    mp INTEGER NOT NULL,
+    atk INTEGER NOT NULL,
+    def INTEGER NOT NULL,
+    spd INTEGER NOT NULL
+);
+CREATE TABLE equippable_by (
+    item_id INTEGER NOT NULL REFERENCES items(id),
+    character TEXT NOT NULL
+);
+CREATE TABLE material_loops (
+    id INTEGER PRIMARY KEY,
+    item_id INTEGER NOT NULL REFERENCES items(id),
+    name TEXT NOT NULL,
+    distance INTEGER NOT NULL,
+    position INTEGER NOT NULL,
+    linked_from_position INTEGER,
+    material TEXT NOT NULL,
+    -- the item the material names, or NULL for a category
+    material_item_id INTEGER REFERENCES items(id)
+);
+CREATE TABLE unlock_requirements (
+    loop_id INTEGER NOT NULL REFERENCES material_loops(id),
+    element TEXT NOT NULL,
+    value INTEGER
+);
+CREATE TABLE loop_levels (
+    id INTEGER PRIMARY KEY,
+    loop_id INTEGER NOT NULL REFERENCES material_loops(id),
+    level INTEGER NOT NULL,
+    effect TEXT NOT NULL
+);
+CREATE TABLE loop_level_effects (
+    level_id INTEGER NOT NULL REFERENCES loop_levels(id),
+    language TEXT NOT NULL,
+    effect TEXT NOT NULL
+);
+CREATE TABLE loop_level_elements (
+    level_id INTEGER NOT NULL REFERENCES loop_levels(id),
+    element TEXT NOT NULL,
+    value INTEGER
+);
+CREATE TABLE recipe_morphs (
+    level_id INTEGER NOT NULL REFERENCES loop_levels(id),
+    -- the item the level morphs the recipe into, or NULL if there's no item by that name
+    recipe_item_id INTEGER REFERENCES items(id),
+    recipe TEXT NOT NULL
+);
+";
+
+#[derive(Debug)]
+pub enum ExportError {
+    /// the existing file couldn't be replaced
+    Io(io::Error),
+    Sqlite(rusqlite::Error),
+}
+
+impl fmt::Display for ExportError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ExportError::Io(error) => write!(f, "{}", error),
+            ExportError::Sqlite(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl From<io::Error> for ExportError {
+    fn from(error: io::Error) -> ExportError {
+        ExportError::Io(error)
+    }
+}
+
+impl From<rusqlite::Error> for ExportError {
+    fn from(error: rusqlite::Error) -> ExportError {
+        ExportError::Sqlite(error)
+    }
+}
+
+/// write the items to a new SQLite database at the given path, replacing any existing file.
+/// The items are expected to refer to each other by their English names, as
+/// `Dataset::items` gives them.
+///
+/// The database is written beside the path and only moved over it once complete, so a failed
+/// export leaves any existing file as it was.
+pub fn export(items: &[Item], path: &str) -> Result<(), ExportError> {
+    let temp_path = format!("{}.exporting", path);
+    if Path::new(&temp_path).exists() {
+        fs::remove_file(&temp_path)?;
+    }
+    match write_database(items, &temp_path) {
+        Ok(()) => fs::rename(&temp_path, path)?,
+        Err(error) => {
+            // the error that stopped the export matters more than a leftover file
+            let _ = fs::remove_file(&temp_path);
+            return Err(error);
+        }
+    }
+    Ok(())
+}
+
+fn write_database(items: &[Item], path: &str) -> Result<(), ExportError> {
+    let mut connection = Connection::open(path)?;
+    let transaction = connection.transaction()?;
+    transaction.execute_batch(SCHEMA)?;
+    // every item row goes in first, so loops can point at the items they name
+    let mut item_ids = HashMap::new();
+    for item in items {
+        item_ids.insert(item.name.as_str(), insert_item_row(&transaction, item)?);
+    }
+    for item in items {
+        insert_item_details(&transaction, item, item_ids[item.name.as_str()], &item_ids)?;
+    }
+    transaction.commit()?;
+    Ok(())
+}
+
+/// insert the item's row of the items table, returning its id
+fn insert_item_row(transaction: &Transaction, item: &Item) -> rusqlite::Result<i64> {
+    let synthesis = item.synthesis.as_ref();
+    transaction.execute(
+        "INSERT INTO items (name, stable_id, item_number, level, cc_cost, required_materials, required_alchemy_level)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            item.name,
+            item.id(),
+            item.item_number,
+            item.level,
+            item.cc_cost,
+            synthesis.map(|synthesis| synthesis.required_materials),
+            synthesis.map(|synthesis| synthesis.required_alchemy_level),
+        ],
+    )?;
+    Ok(transaction.last_insert_rowid())
+}
+
+/// insert everything else about the item, which refers to its row and the rows of other items
+fn insert_item_details(
+    transaction: &Transaction,
+    item: &Item,
+    item_id: i64,
+    item_ids: &HashMap<&str, i64>,
+) -> rusqlite::Result<()> {
+    let synthesis = item.synthesis.as_ref();
+    for (language, name) in &item.names {
+        transaction.execute(
+            "INSERT INTO item_names (item_id, language, name) VALUES (?1, ?2, ?3)",
+            params![item_id, language, name],
+        )?;
+    }
+
+    insert_strings(
+        transaction,
+        "classifications",
+        "classification",
+        item_id,
+        &item.classifications,
+    )?;
+    insert_strings(
+        transaction,
+        "categories",
+        "category",
+        item_id,
+        &item.categories,
+    )?;
+    for material in &item.materials {
+        transaction.execute(
+            "INSERT INTO materials (item_id, material, material_item_id) VALUES (?1, ?2, ?3)",
+            params![item_id, material, item_ids.get(material.as_str())],
+        )?;
+    }
+    insert_strings(
+        transaction,
+        "gather_locations",
+        "location",
+        item_id,
+        &item.gather_locations,
+    )?;
+    insert_strings(
+        transaction,
+        "gathering_tools",
+        "tool",
+        item_id,
+        &item.gathering_tools,
+    )?;
+    insert_elements(transaction, "elements", "item_id", item_id, &item.elements)?;
+
+    if let Some(equipment) = &item.equipment {
+        let stats = &equipment.stats;
+        transaction.execute(
+            "INSERT INTO equipment (item_id, hp, mp, atk, def, spd) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![item_id, stats.hp, stats.mp, stats.atk, stats.def, stats.spd],
+        )?;
+        insert_strings(
+            transaction,
+            "equippable_by",
+            "character",
+            item_id,
+            &equipment.equippable_by,
+        )?;
+    }
+
+    for material_loop in synthesis
+        .iter()
+        .flat_map(|synthesis| &synthesis.material_loops)
+    {
+        transaction.execute(
+            "INSERT INTO material_loops (item_id, name, distance, position, linked_from_position, material, material_item_id)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                item_id,
+                material_loop.name,
+                material_loop.distance,
+                material_loop.position,
+                material_loop.linked_from_position,
+                material_loop.material,
+                item_ids.get(material_loop.material.as_str()),
+            ],
+        )?;
+        let loop_id = transaction.last_insert_rowid();
+        insert_elements(
+            transaction,
+            "unlock_requirements",
+            "loop_id",
+            loop_id,
+            &material_loop.unlock,
+        )?;
+
+        for (index, level) in material_loop.levels.iter().enumerate() {
+            transaction.execute(
+                "INSERT INTO loop_levels (loop_id, level, effect) VALUES (?1, ?2, ?3)",
+                params![loop_id, index as i64 + 1, level.effect],
+            )?;
+            let level_id = transaction.last_insert_rowid();
+            for (language, effect) in &level.effect_names {
+                transaction.execute(
+                    "INSERT INTO loop_level_effects (level_id, language, effect) VALUES (?1, ?2, ?3)",
+                    params![level_id, language, effect],
+                )?;
+            }
+            insert_elements(
+                transaction,
+                "loop_level_elements",
+                "level_id",
+                level_id,
+                &level.elements,
+            )?;
+            if let Some(recipe) = &level.recipe {
+                transaction.execute(
+                    "INSERT INTO recipe_morphs (level_id, recipe_item_id, recipe) VALUES (?1, ?2, ?3)",
+                    params![level_id, item_ids.get(recipe.as_str()), recipe],
+                )?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// insert a list of strings belonging to an item into a two column table
+fn insert_strings(
+    transaction: &Transaction,
+    table: &str,
+    column: &str,
+    item_id: i64,
+    values: &[String],
+) -> rusqlite::Result<()> {
+    let sql = format!(
+        "INSERT INTO {} (item_id, {}) VALUES (?1, ?2)",
+        table, column
+    );
+    let mut statement = transaction.prepare_cached(&sql)?;
+    for value in values {
+        statement.execute(params![item_id, value])?;
+    }
+    Ok(())
+}
+
+/// insert element/value pairs belonging to the row `owner_id` of another table
+fn insert_elements(
+    transaction: &Transaction,
+    table: &str,
+    owner_column: &str,
+    owner_id: i64,
+    elements: &[ElementValue],
+) -> rusqlite::Result<()> {
+    let sql = format!(
+        "INSERT INTO {} ({}, element, value) VALUES (?1, ?2, ?3)",
+        table, owner_column
+    );
+    let mut statement = transaction.prepare_cached(&sql)?;
+    for element in elements {
+        statement.execute(params![owner_id, element.element, element.value])?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::{test_items, test_path};
+
+    #[test]
+    fn materials_and_loops_refer_to_the_items_they_name() {
+        let path = test_path("sqlite.db");
+        export(&test_items(), &path.to_string_lossy()).unwrap();
+        let connection = Connection::open(&path).unwrap();
+
+        let materials: Vec<(String, Option<String>)> = connection
+            .prepare(
+                "SELECT material, items.name FROM materials
+                 LEFT JOIN items ON items.id = materials.material_item_id
+                 ORDER BY material",
+            )
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+        assert_eq!(
+            materials,
+            [
+                (String::from("(Gunpowder)"), None),
+                (String::from("Uni"), Some(String::from("Uni"))),
+            ]
+        );
+
+        let loop_material: Option<String> = connection
+            .query_row(
+                "SELECT items.name FROM material_loops
+                 JOIN items ON items.id = material_loops.material_item_id
+                 WHERE position = 0",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(loop_material.as_deref(), Some("Uni"));
+        let recipe: String = connection
+            .query_row(
+                "SELECT items.name FROM recipe_morphs JOIN items ON items.id = recipe_item_id",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(recipe, "Uni");
+
+        drop(connection);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn exports_replace_the_old_database() {
+        let path = test_path("replace.db");
+        let items = test_items();
+        export(&items, &path.to_string_lossy()).unwrap();
+        export(&items[..1], &path.to_string_lossy()).unwrap();
+
+        let connection = Connection::open(&path).unwrap();
+        let count: i64 = connection
+            .query_row("SELECT COUNT(*) FROM items", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 1);
+        assert!(!Path::new(&format!("{}.exporting", path.to_string_lossy())).exists());
+
+        drop(connection);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn failed_exports_leave_the_old_database() {
+        let path = test_path("failed.db");
+        let items = test_items();
+        export(&items, &path.to_string_lossy()).unwrap();
+
+        // two items with the same name break the unique constraint on names
+        let duplicated = vec![items[0].clone(), items[0].clone()];
+        assert!(matches!(
+            export(&duplicated, &path.to_string_lossy()),
+            Err(ExportError::Sqlite(_))
+        ));
+
+        let connection = Connection::open(&path).unwrap();
+        let count: i64 = connection
+            .query_row("SELECT COUNT(*) FROM items", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 2);
+        assert!(!Path::new(&format!("{}.exporting", path.to_string_lossy())).exists());
+
+        drop(connection);
+        fs::remove_file(&path).unwrap();
+    }
+}
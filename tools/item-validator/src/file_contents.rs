@@ -1,9 +1,118 @@
-use std::path::Path;
-use std::{fs, io};
+use glob::{Pattern, PatternError};
+use rayon::prelude::*;
+use std::path::{Path, PathBuf};
+use std::{fmt, fs, io};
+
+extern crate yaml_rust;
+use yaml_rust::{ScanError, Yaml, YamlLoader};
+
+/// the file in the data folder listing item files to leave out, one glob pattern per line
+pub const IGNORE_FILE: &str = ".itemignore";
 
 pub struct FileContents {
+    /// the path of the file within the items folder, e.g. `bombs/001-explosive-uni.yml`
     pub name: String,
     pub contents: String,
+    /// the documents of the file, parsed once when the file is loaded
+    pub yaml: Result<Vec<Yaml>, FileError>,
+}
+
+/// why an item file couldn't be loaded. These are reported for the file, rather than stopping
+/// the rest of the item files from loading.
+#[derive(Debug, Clone)]
+pub enum FileError {
+    /// the file couldn't be read, e.g. because it isn't UTF-8
+    Unreadable(String),
+    Yaml(ScanError),
+    /// the file has no YAML documents in it
+    Empty,
+}
+
+impl fmt::Display for FileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FileError::Unreadable(error) => write!(f, "can't read the file: {}", error),
+            FileError::Yaml(error) => write!(f, "can't parse the file: {}", error),
+            FileError::Empty => write!(f, "the file is empty"),
+        }
+    }
+}
+
+impl FileContents {
+    pub fn parse(name: String, contents: String) -> FileContents {
+        let yaml = YamlLoader::load_from_str(&contents).map_err(FileError::Yaml);
+        FileContents {
+            name,
+            contents,
+            yaml,
+        }
+    }
+
+    /// an item file that couldn't be read
+    pub fn unreadable(name: String, error: &io::Error) -> FileContents {
+        FileContents {
+            name,
+            contents: String::new(),
+            yaml: Err(FileError::Unreadable(error.to_string())),
+        }
+    }
+
+    /// the item of the file: its first document, or the error from loading it. Any further
+    /// documents are reported when the file is validated.
+    pub fn document(&self) -> Result<&Yaml, FileError> {
+        match &self.yaml {
+            Ok(docs) => docs.first().ok_or(FileError::Empty),
+            Err(error) => Err(error.clone()),
+        }
+    }
+}
+
+/// why the ignore file couldn't be loaded
+#[derive(Debug)]
+pub enum IgnoreFileError {
+    Io(io::Error),
+    Pattern(PatternError),
+}
+
+/// which files in the items folder are item files: `.yml` and `.yaml` files that aren't matched
+/// by a pattern in the ignore file
+#[derive(Default)]
+pub struct ItemFilter {
+    ignore: Vec<Pattern>,
+}
+
+impl ItemFilter {
+    /// read the ignore patterns of a data folder. A missing ignore file ignores nothing. Blank
+    /// lines and lines starting with `#` are skipped.
+    pub fn load(data_folder: &str) -> Result<ItemFilter, (String, IgnoreFileError)> {
+        let path = format!("{}/{}", data_folder, IGNORE_FILE);
+        let contents = match load_file(&path) {
+            Ok(contents) => contents,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(error) => return Err((path, IgnoreFileError::Io(error))),
+        };
+        let ignore = contents
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| {
+                Pattern::new(line).map_err(|error| (path.clone(), IgnoreFileError::Pattern(error)))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(ItemFilter { ignore })
+    }
+
+    /// true if the file (named by its path within the items folder) is an item file
+    pub fn includes(&self, name: &str) -> bool {
+        let extension = Path::new(name)
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase());
+        matches!(extension.as_deref(), Some("yml") | Some("yaml")) && !self.ignores(name)
+    }
+
+    pub(crate) fn ignores(&self, name: &str) -> bool {
+        self.ignore.iter().any(|pattern| pattern.matches(name))
+    }
 }
 
 pub fn load_file(file_path: &str) -> io::Result<String> {
@@ -12,22 +121,67 @@ pub fn load_file(file_path: &str) -> io::Result<String> {
     Ok(contents)
 }
 
-pub fn load_directory(item_contents: &mut Vec<FileContents>, path: &str) -> io::Result<()> {
-    let path = Path::new(path);
-    let files = fs::read_dir(path)?;
-    for file_result in files {
-        let file = file_result?;
+/// the name of a file within the items folder, with `/` between folders on every platform
+pub fn item_file_name(items_folder: &Path, path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(items_folder).ok()?;
+    let parts: Vec<String> = relative
+        .components()
+        .map(|part| part.as_os_str().to_string_lossy().to_string())
+        .collect();
+    Some(parts.join("/"))
+}
+
+/// read and parse the item files in a directory and its subdirectories. Files are read and
+/// parsed in parallel, since large data folders spend most of their loading time in the YAML
+/// parser. A file that can't be read is still added, with the error in place of its documents.
+pub fn load_directory(
+    item_contents: &mut Vec<FileContents>,
+    path: &str,
+    filter: &ItemFilter,
+) -> io::Result<()> {
+    let items_folder = Path::new(path);
+    let mut files = Vec::new();
+    find_item_files(items_folder, items_folder, filter, &mut files)?;
+    files.sort();
 
-        // get the file name, as a normal (utf8) string
-        let file_name = file.file_name().to_string_lossy().to_string();
+    let loaded: Vec<FileContents> = files
+        .into_par_iter()
+        .map(|(name, path)| read_item_file(name, &path))
+        .collect();
+    item_contents.extend(loaded);
+    Ok(())
+}
 
-        // read open and read file as a string
-        let contents = fs::read_to_string(file.path())?;
+/// read and parse an item file. A file that can't be read is kept, with the error in place of
+/// its documents.
+pub fn read_item_file(name: String, path: &Path) -> FileContents {
+    match fs::read_to_string(path) {
+        Ok(contents) => FileContents::parse(name, contents),
+        Err(error) => FileContents::unreadable(name, &error),
+    }
+}
 
-        item_contents.push(FileContents {
-            name: file_name,
-            contents,
-        });
+/// the item files in a folder of the items folder and its subfolders, by name and path
+pub(crate) fn find_item_files(
+    items_folder: &Path,
+    folder: &Path,
+    filter: &ItemFilter,
+    files: &mut Vec<(String, PathBuf)>,
+) -> io::Result<()> {
+    for entry in fs::read_dir(folder)? {
+        let entry = entry?;
+        let path = entry.path();
+        let name = match item_file_name(items_folder, &path) {
+            Some(name) => name,
+            None => continue,
+        };
+        if entry.file_type()?.is_dir() {
+            if !filter.ignores(&name) {
+                find_item_files(items_folder, &path, filter, files)?;
+            }
+        } else if filter.includes(&name) {
+            files.push((name, path));
+        }
     }
     Ok(())
 }
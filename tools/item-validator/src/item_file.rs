@@ -0,0 +1,470 @@
+//! Writing items back out as item files, in the canonical format described in CONTRIBUTING.md.
+
+use crate::model::{ElementValue, Item, MaterialLoop, DEFAULT_LANGUAGE, STAT_NAMES};
+
+use std::collections::BTreeMap;
+
+/// file names for items and pages, e.g. `Explosive Uni` becomes `explosive-uni`
+pub fn slug(name: &str) -> String {
+    let mut slug = String::new();
+    for c in name.chars() {
+        if c.is_alphanumeric() {
+            slug.extend(c.to_lowercase());
+        } else if !slug.ends_with('-') && !slug.is_empty() {
+            slug.push('-');
+        }
+    }
+    slug.trim_end_matches('-').to_string()
+}
+
+/// the item file name, e.g. `001-explosive-uni.yml`
+pub fn file_name(item: &Item) -> String {
+    numbered_file_name(item.item_number, &item.name)
+}
+
+pub(crate) fn numbered_file_name(item_number: i64, name: &str) -> String {
+    format!("{:03}-{}.yml", item_number, slug(name))
+}
+
+/// how an item is obtained, which decides the sections of a new item file
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ItemKind {
+    Gathered,
+    Synthesized,
+}
+
+/// a new item file, with the keys of the template for the kind of item left for the contributor
+/// to fill in. Returns the file name and contents.
+pub fn scaffold(item_number: i64, name: &str, kind: ItemKind) -> (String, String) {
+    let mut yaml = format!(
+        "---\nName: {}\nItem Number: {}\n",
+        scalar(name),
+        item_number
+    );
+    yaml.push_str(CLASSIFICATION_KEYS);
+    // rules.yml tells gathered and synthesized items apart by the Materials classification
+    if kind == ItemKind::Gathered {
+        yaml.push_str("- Materials\n");
+    }
+    yaml.push_str(DESCRIPTION_KEYS);
+    yaml.push_str(match kind {
+        ItemKind::Gathered => GATHERED_KEYS,
+        ItemKind::Synthesized => SYNTHESIZED_KEYS,
+    });
+    (numbered_file_name(item_number, name), yaml)
+}
+
+/// a placeholder file for an item that other items refer to, but that has no file yet. Its item
+/// number and kind aren't known, so the file is numbered 000 and only has the keys every item
+/// has.
+pub fn stub(name: &str, referenced_by: &[String]) -> (String, String) {
+    let mut yaml = format!(
+        "---\n# stub for the item referenced by {}: fill in the item number (and rename the file),\n\
+         # then add the gathering or Synthesis keys from the template\nName: {}\nItem Number:\n",
+        referenced_by.join(", "),
+        scalar(name)
+    );
+    yaml.push_str(CLASSIFICATION_KEYS);
+    yaml.push_str(DESCRIPTION_KEYS);
+    (numbered_file_name(0, name), yaml)
+}
+
+const CLASSIFICATION_KEYS: &str = "\
+Level:                   # the (minimum) level of the item
+Classifications:         # the menus that the item can be found / used in
+";
+
+const DESCRIPTION_KEYS: &str = "\
+Category:                # the categories of the item (does not include Add(...) Material Loops)
+Element:                 # the elements of the item (does not include Add(...) Material Loops)
+";
+
+const GATHERED_KEYS: &str = "\
+Gather Locations:        # where the items can be found
+Gathering Tools:         # the gathering tools used to collect items
+";
+
+const SYNTHESIZED_KEYS: &str = "\
+Materials:               # the materials (or categories) used to synthesize the item
+Synthesis:
+  Required Materials:    # the minimum number of materials needed to synthesize the item
+  Required Alchemy Level: # the required alchemist level needed to synthesize the item
+  Material Loops:        # a list of material loops for the item
+    - Effect 1:          # what the loop adds to the item
+        Distance: 0      # how many links it takes to get to the loop (starting loop is 0)
+        Position: 0      # a unique index of the loop, used for linking loops
+        Material:        # the material that can be used in the loop
+        Levels:          # the levels of effects, e.g. `- Scatter:` with an `Element:` list
+";
+
+/// the contents of an item file. Keys are written in the order of the template, and keys without
+/// a value are left out.
+pub fn to_yaml(item: &Item) -> String {
+    let mut yaml = String::from("---\n");
+    if item.names.is_empty() {
+        yaml.push_str(&format!("Name: {}\n", scalar(&item.name)));
+    } else {
+        yaml.push_str("Name:\n");
+        yaml.push_str(&format!("  {}: {}\n", DEFAULT_LANGUAGE, scalar(&item.name)));
+        push_translations(&mut yaml, "  ", &item.names);
+    }
+    if let Some(id) = &item.id {
+        yaml.push_str(&format!("ID: {}\n", scalar(id)));
+    }
+    yaml.push_str(&format!("Item Number: {}\n", item.item_number));
+    yaml.push_str(&format!("Level: {}\n", item.level));
+    push_list(&mut yaml, "", "Classifications", &item.classifications);
+    if let Some(cc_cost) = item.cc_cost {
+        yaml.push_str(&format!("CC Cost: {}\n", cc_cost));
+    }
+    push_list(&mut yaml, "", "Category", &item.categories);
+    push_elements(&mut yaml, "", "Element", &item.elements);
+    push_list(&mut yaml, "", "Materials", &item.materials);
+    push_list(&mut yaml, "", "Gather Locations", &item.gather_locations);
+    push_list(&mut yaml, "", "Gathering Tools", &item.gathering_tools);
+
+    if let Some(equipment) = &item.equipment {
+        let stats: Vec<(&str, i64)> = STAT_NAMES
+            .iter()
+            .filter_map(|stat| equipment.stats.get(stat).map(|value| (*stat, value)))
+            .filter(|(_, value)| *value != 0)
+            .collect();
+        if stats.is_empty() {
+            yaml.push_str("Equipment:\n  Stats: {}\n");
+        } else {
+            yaml.push_str("Equipment:\n  Stats:\n");
+        }
+        for (stat, value) in stats {
+            yaml.push_str(&format!("    {}: {}\n", stat, value));
+        }
+        push_list(&mut yaml, "  ", "Equippable By", &equipment.equippable_by);
+    }
+
+    if let Some(synthesis) = &item.synthesis {
+        yaml.push_str("Synthesis:\n");
+        yaml.push_str(&format!(
+            "  Required Materials: {}\n",
+            synthesis.required_materials
+        ));
+        yaml.push_str(&format!(
+            "  Required Alchemy Level: {}\n",
+            synthesis.required_alchemy_level
+        ));
+        yaml.push_str("  Material Loops:\n");
+        for material_loop in &synthesis.material_loops {
+            push_material_loop(&mut yaml, material_loop);
+        }
+    }
+    yaml
+}
+
+/// set the Position and Linked From Position of loops from the links between them.
+/// `linked_from[i]` is the index in `material_loops` of the loop that loop `i` is linked from.
+/// Loops are numbered by Distance, keeping the order they're given in within each Distance, and
+/// are returned in Position order.
+pub fn number_material_loops(
+    material_loops: &[MaterialLoop],
+    linked_from: &[Option<usize>],
+) -> Vec<MaterialLoop> {
+    let mut order: Vec<usize> = (0..material_loops.len()).collect();
+    order.sort_by_key(|&index| material_loops[index].distance);
+
+    let mut positions = vec![0; material_loops.len()];
+    for (position, &index) in order.iter().enumerate() {
+        positions[index] = position as i64;
+    }
+    order
+        .iter()
+        .map(|&index| MaterialLoop {
+            position: positions[index],
+            linked_from_position: linked_from
+                .get(index)
+                .copied()
+                .flatten()
+                .map(|parent| positions[parent]),
+            ..material_loops[index].clone()
+        })
+        .collect()
+}
+
+fn push_material_loop(yaml: &mut String, material_loop: &MaterialLoop) {
+    yaml.push_str(&format!("    - {}:\n", scalar(&material_loop.name)));
+    yaml.push_str(&format!("        Distance: {}\n", material_loop.distance));
+    yaml.push_str(&format!("        Position: {}\n", material_loop.position));
+    if let Some(position) = material_loop.linked_from_position {
+        yaml.push_str(&format!("        Linked From Position: {}\n", position));
+    }
+    yaml.push_str(&format!(
+        "        Material: {}\n",
+        scalar(&material_loop.material)
+    ));
+    yaml.push_str("        Levels:\n");
+    for level in &material_loop.levels {
+        yaml.push_str(&format!("        - {}:\n", scalar(&level.effect)));
+        if !level.effect_names.is_empty() {
+            yaml.push_str("            Translations:\n");
+            push_translations(yaml, "              ", &level.effect_names);
+        }
+        if let Some(recipe) = &level.recipe {
+            yaml.push_str(&format!("            Recipe: {}\n", scalar(recipe)));
+        }
+        push_elements(yaml, "            ", "Element", &level.elements);
+    }
+    push_elements(yaml, "        ", "Unlock", &material_loop.unlock);
+}
+
+fn push_translations(yaml: &mut String, indent: &str, translations: &BTreeMap<String, String>) {
+    for (language, text) in translations {
+        yaml.push_str(&format!(
+            "{}{}: {}\n",
+            indent,
+            scalar(language),
+            scalar(text)
+        ));
+    }
+}
+
+fn push_list(yaml: &mut String, indent: &str, key: &str, values: &[String]) {
+    if values.is_empty() {
+        return;
+    }
+    yaml.push_str(&format!("{}{}:\n", indent, key));
+    for value in values {
+        yaml.push_str(&format!("{}- {}\n", indent, scalar(value)));
+    }
+}
+
+fn push_elements(yaml: &mut String, indent: &str, key: &str, elements: &[ElementValue]) {
+    if elements.is_empty() {
+        return;
+    }
+    yaml.push_str(&format!("{}{}:\n", indent, key));
+    for element in elements {
+        match element.value {
+            Some(value) => yaml.push_str(&format!(
+                "{}- {}: {}\n",
+                indent,
+                scalar(&element.element),
+                value
+            )),
+            None => yaml.push_str(&format!("{}- {}\n", indent, scalar(&element.element))),
+        }
+    }
+}
+
+/// a string as a YAML scalar, quoted only when it would otherwise be read as something else
+pub(crate) fn scalar(value: &str) -> String {
+    let needs_quotes = value.is_empty()
+        || value.trim() != value
+        || value.contains(": ")
+        || value.contains(" #")
+        || value.ends_with(':')
+        || value.starts_with(|c: char| "-?:,[]{}#&*!|>'\"%@`".contains(c))
+        || value.parse::<f64>().is_ok()
+        || ["true", "false", "yes", "no", "null", "~"].contains(&value.to_lowercase().as_str());
+    if needs_quotes {
+        format!("'{}'", value.replace('\'', "''"))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model;
+
+    const ITEM: &str = "
+Name:
+  en: 'Uni: Deluxe'
+  ja: ウニ
+ID: uni
+Item Number: 7
+Level: 3
+Classifications: [Battle Item, Equip Item]
+CC Cost: 2
+Category: [(Uni)]
+Element:
+- Fire: 2
+- Ice
+Materials: [Red Supplement, (Gunpowder)]
+Gather Locations: ['true']
+Gathering Tools: [Bomb Rod]
+Equipment:
+  Stats:
+    ATK: 5
+  Equippable By: [Ryza]
+Synthesis:
+  Required Materials: 2
+  Required Alchemy Level: 1
+  Material Loops:
+  - Effect 1:
+      Distance: 0
+      Position: 0
+      Material: (Uni)
+      Levels:
+      - '#1 Effect':
+          Element:
+          - Fire: 1
+          Translations:
+            ja: 効果
+  - Recipe:
+      Distance: 1
+      Position: 1
+      Linked From Position: 0
+      Material: Red Supplement
+      Levels:
+      - Recipe Morph:
+          Recipe: '100'
+          Element:
+          - Ice: 2
+      Unlock:
+      - Fire: 3
+";
+
+    #[test]
+    fn written_items_read_back_the_same() {
+        let item = model::load_item(ITEM).unwrap();
+        let yaml = to_yaml(&item);
+        assert!(yaml.starts_with("---\nName:\n  en: 'Uni: Deluxe'\n  ja: ウニ\nID: uni\n"));
+        assert_eq!(model::load_item(&yaml).unwrap(), item);
+    }
+
+    #[test]
+    fn scalars_are_quoted_only_when_needed() {
+        assert_eq!(scalar("Explosive Uni"), "Explosive Uni");
+        assert_eq!(scalar("Surprise! L"), "Surprise! L");
+        assert_eq!(scalar("(Uni)"), "(Uni)");
+        assert_eq!(scalar("12"), "'12'");
+        assert_eq!(scalar("No"), "'No'");
+        assert_eq!(scalar("a: b"), "'a: b'");
+        assert_eq!(scalar("'quoted'"), "'''quoted'''");
+        assert_eq!(scalar(""), "''");
+    }
+
+    #[test]
+    fn file_names_come_from_the_number_and_name() {
+        let item = model::load_item(ITEM).unwrap();
+        assert_eq!(file_name(&item), "007-uni-deluxe.yml");
+        assert_eq!(slug("Surprise! L"), "surprise-l");
+        assert_eq!(slug("  Red  Supplement "), "red-supplement");
+    }
+
+    #[test]
+    fn loops_are_numbered_by_distance_and_keep_their_links() {
+        let material_loop = |name: &str, distance: i64| MaterialLoop {
+            name: name.to_string(),
+            distance,
+            material: String::from("(Uni)"),
+            levels: vec![model::LoopLevel {
+                effect: String::from("Scatter"),
+                elements: vec![ElementValue {
+                    element: String::from("Fire"),
+                    value: Some(1),
+                }],
+                ..model::LoopLevel::default()
+            }],
+            ..MaterialLoop::default()
+        };
+        // drawn in any order: the loops at Distance 1 are linked from the one at Distance 0
+        let drawn = vec![
+            material_loop("Effect 2", 1),
+            material_loop("Effect 3", 2),
+            material_loop("Effect 1", 0),
+            material_loop("Quality", 1),
+        ];
+        let linked_from = vec![Some(2), Some(3), None, Some(2)];
+        let numbered = number_material_loops(&drawn, &linked_from);
+
+        let summary: Vec<(&str, i64, Option<i64>)> = numbered
+            .iter()
+            .map(|l| (l.name.as_str(), l.position, l.linked_from_position))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("Effect 1", 0, None),
+                ("Effect 2", 1, Some(0)),
+                ("Quality", 2, Some(0)),
+                ("Effect 3", 3, Some(2)),
+            ]
+        );
+
+        // the numbered loops survive a trip through an item file
+        let item = Item {
+            name: String::from("Explosive Uni"),
+            item_number: 1,
+            level: 1,
+            synthesis: Some(model::Synthesis {
+                required_materials: 2,
+                required_alchemy_level: 1,
+                material_loops: numbered,
+            }),
+            ..Item::default()
+        };
+        let read_back = model::load_item(&to_yaml(&item)).unwrap();
+        assert_eq!(read_back, item);
+        let synthesis = read_back.synthesis.unwrap();
+        assert_eq!(synthesis.material_loop(2).unwrap().name, "Quality");
+    }
+
+    /// the top level keys of an item file, in order
+    fn keys(contents: &str) -> Vec<String> {
+        let docs = yaml_rust::YamlLoader::load_from_str(contents).unwrap();
+        docs[0]
+            .as_hash()
+            .unwrap()
+            .keys()
+            .map(|key| key.as_str().unwrap().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn scaffolds_have_the_keys_for_their_kind_of_item() {
+        let (file, contents) = scaffold(12, "Sandy Uni", ItemKind::Gathered);
+        assert_eq!(file, "012-sandy-uni.yml");
+        assert!(contents.starts_with("---\nName: Sandy Uni\nItem Number: 12\n"));
+        assert!(contents.contains("Classifications:         # the menus that the item can be found / used in\n- Materials\n"));
+        assert_eq!(
+            keys(&contents),
+            [
+                "Name",
+                "Item Number",
+                "Level",
+                "Classifications",
+                "Category",
+                "Element",
+                "Gather Locations",
+                "Gathering Tools",
+            ]
+        );
+
+        let (file, contents) = scaffold(1, "Explosive Uni", ItemKind::Synthesized);
+        assert_eq!(file, "001-explosive-uni.yml");
+        assert!(!contents.contains("- Materials\n"));
+        assert_eq!(
+            keys(&contents)[4..],
+            ["Category", "Element", "Materials", "Synthesis"]
+        );
+    }
+
+    #[test]
+    fn stubs_say_who_refers_to_them() {
+        let referenced_by = vec![String::from("Explosive Uni"), String::from("Craft")];
+        let (file, contents) = stub("Red: Supplement", &referenced_by);
+        assert_eq!(file, "000-red-supplement.yml");
+        assert!(contents.contains("# stub for the item referenced by Explosive Uni, Craft:"));
+        assert!(contents.contains("\nName: 'Red: Supplement'\n"));
+        assert_eq!(
+            keys(&contents),
+            [
+                "Name",
+                "Item Number",
+                "Level",
+                "Classifications",
+                "Category",
+                "Element"
+            ]
+        );
+    }
+}
@@ -0,0 +1,23 @@
+//! Loading and validation of the Atelier Ryza item data files.
+
+pub mod battle;
+pub mod dataset;
+pub mod diff;
+pub mod export;
+pub mod file_contents;
+pub mod item_file;
+pub mod loadout;
+pub mod model;
+pub mod query;
+pub mod rename;
+pub mod render;
+pub mod rules;
+pub mod schema;
+pub mod search;
+pub mod settings;
+pub mod simulator;
+pub mod site;
+pub mod validate_item;
+pub mod validation_sets;
+pub mod watch;
+pub mod worklist;
@@ -0,0 +1,201 @@
+//! Ranking equipment for a character, and suggesting the loadout that maximizes a stat.
+
+use crate::model::Item;
+use crate::simulator;
+
+/// the equipment slots, named by the classification of the items that go in them
+pub const SLOTS: [&str; 3] = ["Weapon", "Armor", "Accessories"];
+
+/// a piece of equipment, scored on one stat
+#[derive(Clone)]
+pub struct Candidate<'a> {
+    pub item: &'a Item,
+    /// the stat from the item's base stats
+    pub base: i64,
+    /// the stat added by the loop effects of the item's best roll
+    pub bonus: i64,
+    pub trait_slots: usize,
+}
+
+impl Candidate<'_> {
+    pub fn score(&self) -> i64 {
+        self.base + self.bonus
+    }
+}
+
+pub struct Loadout<'a> {
+    pub slots: Vec<(&'static str, Option<Candidate<'a>>)>,
+}
+
+impl Loadout<'_> {
+    pub fn total(&self) -> i64 {
+        self.slots
+            .iter()
+            .filter_map(|(_, candidate)| candidate.as_ref())
+            .map(|candidate| candidate.score())
+            .sum()
+    }
+}
+
+/// the equipment for a slot that the character can equip, best first.
+/// Ties go to the item with more trait slots, then by name.
+pub fn rank_equipment<'a>(
+    items: &'a [Item],
+    character: &str,
+    stat: &str,
+    slot: &str,
+    max_element_value: i64,
+) -> Vec<Candidate<'a>> {
+    let mut candidates: Vec<Candidate> = items
+        .iter()
+        .filter(|item| item.has_classification(slot))
+        .filter_map(|item| {
+            let equipment = item.equipment.as_ref()?;
+            if !equipment.equippable_by(character) {
+                return None;
+            }
+            let roll = simulator::best_roll(item, max_element_value);
+            Some(Candidate {
+                item,
+                base: equipment.stats.get(stat).unwrap_or(0),
+                bonus: roll.stat_bonus(stat),
+                trait_slots: roll.trait_slots,
+            })
+        })
+        .collect();
+
+    candidates.sort_by(|a, b| {
+        b.score()
+            .cmp(&a.score())
+            .then(b.trait_slots.cmp(&a.trait_slots))
+            .then(a.item.name.cmp(&b.item.name))
+    });
+    candidates
+}
+
+/// the equipment for every slot, each ranked with `rank_equipment`
+pub fn rank_slots<'a>(
+    items: &'a [Item],
+    character: &str,
+    stat: &str,
+    max_element_value: i64,
+) -> Vec<(&'static str, Vec<Candidate<'a>>)> {
+    SLOTS
+        .iter()
+        .map(|slot| {
+            let candidates = rank_equipment(items, character, stat, slot, max_element_value);
+            (*slot, candidates)
+        })
+        .collect()
+}
+
+/// the best item for each slot of the rankings from `rank_slots`. Slots don't affect each
+/// other, so the best item in each slot gives the best loadout.
+pub fn best_loadout<'a>(rankings: &[(&'static str, Vec<Candidate<'a>>)]) -> Loadout<'a> {
+    let slots = rankings
+        .iter()
+        .map(|(slot, candidates)| (*slot, candidates.first().cloned()))
+        .collect();
+    Loadout { slots }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model;
+
+    /// an equipment item with an ATK base stat and, optionally, a loop effect raising it
+    fn equipment(
+        name: &str,
+        slot: &str,
+        atk: i64,
+        effect: Option<&str>,
+        equippable_by: &str,
+    ) -> Item {
+        let mut contents = format!(
+            "Name: {}\nItem Number: 900\nLevel: 1\nClassifications: [Equip Item, {}]\n\
+             Equipment:\n  Stats:\n    ATK: {}\n  Equippable By: [{}]\n",
+            name, slot, atk, equippable_by
+        );
+        if let Some(effect) = effect {
+            contents.push_str(&format!(
+                "Synthesis:\n  Required Materials: 1\n  Required Alchemy Level: 1\n  \
+                 Material Loops:\n  - Effect 1:\n      Distance: 0\n      Position: 0\n      \
+                 Material: (Ore)\n      Levels:\n      - {}:\n          Element:\n          \
+                 - Fire: 2\n",
+                effect
+            ));
+        }
+        model::load_item(&contents).unwrap()
+    }
+
+    fn names(candidates: &[Candidate]) -> Vec<String> {
+        candidates
+            .iter()
+            .map(|candidate| candidate.item.name.clone())
+            .collect()
+    }
+
+    #[test]
+    fn equipment_is_ranked_by_base_stat_and_bonus() {
+        let items = vec![
+            equipment("Plain Sword", "Weapon", 20, None, "Ryza"),
+            equipment("Magic Sword", "Weapon", 15, Some("ATK +10"), "Ryza"),
+            equipment("Lent's Sword", "Weapon", 50, None, "Lent"),
+            equipment("Armor", "Armor", 5, None, "Ryza"),
+        ];
+        let ranked = rank_equipment(&items, "Ryza", "ATK", "Weapon", simulator::UNLIMITED);
+        assert_eq!(names(&ranked), vec!["Magic Sword", "Plain Sword"]);
+        assert_eq!(
+            (ranked[0].base, ranked[0].bonus, ranked[0].score()),
+            (15, 10, 25)
+        );
+
+        // without enough Fire the effect isn't reached
+        let ranked = rank_equipment(&items, "Ryza", "ATK", "Weapon", 1);
+        assert_eq!(names(&ranked), vec!["Plain Sword", "Magic Sword"]);
+    }
+
+    #[test]
+    fn ties_go_by_name() {
+        let items = vec![
+            equipment("B Sword", "Weapon", 20, None, "Ryza"),
+            equipment("A Sword", "Weapon", 20, None, "Ryza"),
+        ];
+        let ranked = rank_equipment(&items, "Ryza", "ATK", "Weapon", simulator::UNLIMITED);
+        assert_eq!(names(&ranked), vec!["A Sword", "B Sword"]);
+    }
+
+    #[test]
+    fn the_loadout_takes_the_best_of_each_slot() {
+        let items = vec![
+            equipment("Plain Sword", "Weapon", 20, None, "Ryza"),
+            equipment("Magic Sword", "Weapon", 15, Some("ATK +10"), "Ryza"),
+            equipment("Armor", "Armor", 5, None, "Ryza"),
+        ];
+        let rankings = rank_slots(&items, "Ryza", "ATK", simulator::UNLIMITED);
+        assert_eq!(
+            rankings
+                .iter()
+                .map(|(slot, candidates)| (*slot, candidates.len()))
+                .collect::<Vec<_>>(),
+            vec![("Weapon", 2), ("Armor", 1), ("Accessories", 0)]
+        );
+
+        let loadout = best_loadout(&rankings);
+        let picked: Vec<_> = loadout
+            .slots
+            .iter()
+            .map(|(slot, candidate)| (*slot, candidate.as_ref().map(|c| c.item.name.as_str())))
+            .collect();
+        assert_eq!(
+            picked,
+            vec![
+                ("Weapon", Some("Magic Sword")),
+                ("Armor", Some("Armor")),
+                ("Accessories", None)
+            ]
+        );
+        assert_eq!(loadout.total(), 30);
+    }
+}
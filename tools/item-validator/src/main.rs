@@ -1,75 +1,689 @@
+use std::collections::BTreeSet;
 use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
 
-mod file_contents;
-mod settings;
-mod validate_item;
-mod validation_sets;
-
-use file_contents::FileContents;
-use validation_sets::ItemValidationSets;
+use item_validator::dataset::{self, Dataset};
+use item_validator::file_contents::{self, FileContents};
+use item_validator::item_file::ItemKind;
+use item_validator::model::{self, STAT_NAMES};
+use item_validator::settings::Settings;
+use item_validator::watch::{self, Change};
+use item_validator::{
+    battle, diff, export, item_file, loadout, query, rename, render, schema, search, settings,
+    simulator, site, worklist,
+};
 
 extern crate term;
 
-fn get_item_validation_sets(path: &str) -> validation_sets::ItemValidationSets {
-    let list_path = format!("{}/lists.yml", path);
-    let item_list_contents = file_contents::load_file(&list_path).expect("can't load lists.yml");
+/// how many items `search` lists
+const MAX_SEARCH_RESULTS: usize = 20;
 
-    let item_validation_sets = validation_sets::build_item_validation_sets(&item_list_contents)
-        .expect("can't parse item validation lists");
-    println!("Parsed lists.yml:");
-    item_validation_sets
+fn get_dataset(settings: &Settings) -> Dataset {
+    let dataset = Dataset::load(&settings.data_folder).unwrap_or_else(|error| panic!("{}", error));
+    println!(
+        "Loaded {} item files from {}",
+        dataset.item_contents.len(),
+        settings.data_folder
+    );
+    dataset
 }
 
-fn validate_items(
-    item_contents: &[FileContents],
-    item_validation_sets: &ItemValidationSets,
+/// validate the given item files, printing their results. Returns how many had problems.
+fn validate_files<'a>(
+    dataset: &Dataset,
+    files: impl Iterator<Item = &'a FileContents>,
     verbose: bool,
-) {
-    // validate the items
-    for file in item_contents {
+) -> usize {
+    let files: Vec<&FileContents> = files.collect();
+    // files are validated in parallel, then reported in order
+    let results = dataset.validate_files(&files);
+    let mut failed = 0;
+    for (file, result) in files.iter().zip(results) {
         println!("Validating {}", file.name);
-        let result = validate_item::validate_item_contents(&file.contents, &item_validation_sets);
 
         // there's a some .unwrap() calls with the term crate
         // if we can't work with the terminal, just panic
         let mut terminal = term::stdout().unwrap();
-        if let Ok(results) = result {
+        if let Ok(results) = &result {
             // display results
             if verbose {
                 terminal.fg(term::color::BRIGHT_GREEN).unwrap();
-                for msg in results.pass_messages {
+                for msg in &results.pass_messages {
                     println!("- {}", msg);
                 }
                 terminal.reset().unwrap();
             }
+            if !results.fail_messages.is_empty() {
+                failed += 1;
+            }
             terminal.fg(term::color::BRIGHT_RED).unwrap();
-            for msg in results.fail_messages {
+            for msg in &results.fail_messages {
                 println!("- {}", msg);
             }
             terminal.reset().unwrap();
-        } else {
+        } else if let Err(error) = result {
+            failed += 1;
             terminal.fg(term::color::BRIGHT_RED).unwrap();
-            println!("unable to validate {}", file.name);
+            println!("- {}", error);
             terminal.reset().unwrap();
         }
     }
+    failed
+}
+
+fn get_battle_data(path: &str) -> battle::BattleData {
+    let battle_path = format!("{}/battle.yml", path);
+    let battle_contents = file_contents::load_file(&battle_path).expect("can't load battle.yml");
+
+    let battle_data = battle::build_battle_data(&battle_contents)
+        .unwrap_or_else(|error| panic!("can't use {}: {}", battle_path, error));
+    println!("Parsed battle.yml:");
+    battle_data
+}
+
+fn run_validation(settings: &Settings, verbose: bool) {
+    let dataset = get_dataset(settings);
+    validate_files(&dataset, dataset.item_contents.iter(), verbose);
+}
+
+/// `--watch`: validate everything, then keep the dataset loaded and validate item files again as
+/// they change, along with the item files that refer to an item that was added, removed or renamed.
+/// Adding, removing or renaming a folder of item files reads the whole folder again, and a change
+/// to lists.yml or rules.yml validates everything again.
+fn run_watch(settings: &Settings, verbose: bool) {
+    let mut dataset = get_dataset(settings);
+    validate_files(&dataset, dataset.item_contents.iter(), verbose);
+
+    let watcher = watch::DataWatcher::new(&settings.data_folder)
+        .unwrap_or_else(|error| panic!("unable to watch {}: {}", settings.data_folder, error));
+    println!("Watching {} for changes", settings.data_folder);
+    loop {
+        let changes = match watcher.next_changes() {
+            Ok(changes) => changes,
+            Err(error) => {
+                println!("stopped watching: {}", error);
+                return;
+            }
+        };
+
+        let failed = if changes.contains(&Change::Lists)
+            || changes.contains(&Change::Rules)
+            || changes.contains(&Change::Ignore)
+        {
+            println!("lists.yml, rules.yml or the ignore file changed, validating every item file");
+            match Dataset::load(&settings.data_folder) {
+                Ok(reloaded) => dataset = reloaded,
+                Err(error) => {
+                    println!("{}", error);
+                    continue;
+                }
+            }
+            validate_files(&dataset, dataset.item_contents.iter(), verbose)
+        } else {
+            let items_folder = Path::new(&settings.data_folder).join("items");
+            let mut changed_files = BTreeSet::new();
+            for change in changes {
+                let name = match change {
+                    Change::ItemFile(name) if dataset.item_filter.includes(&name) => name,
+                    Change::Folder(folder) => {
+                        changed_files.extend(dataset.update_folder(&items_folder, &folder));
+                        continue;
+                    }
+                    _ => continue,
+                };
+                // a file that can't be read is reported when it's validated
+                let file =
+                    match file_contents::load_file(&items_folder.join(&name).to_string_lossy()) {
+                        Ok(contents) => Some(FileContents::parse(name.clone(), contents)),
+                        Err(error) if error.kind() == io::ErrorKind::NotFound => None,
+                        Err(error) => Some(FileContents::unreadable(name.clone(), &error)),
+                    };
+                if file.is_none() && dataset.file(&name).is_some() {
+                    println!("Removed {}", name);
+                }
+                changed_files.extend(dataset.replace_file(&name, file));
+            }
+            validate_files(
+                &dataset,
+                changed_files.iter().filter_map(|name| dataset.file(name)),
+                verbose,
+            )
+        };
+        println!("{} of the validated item file(s) have problems", failed);
+    }
+}
+
+/// `loadout <character> <stat> [max element value]`: rank the equipment the character can use
+/// on one stat, then suggest the best item for each slot
+fn run_loadout(settings: &Settings, args: &[String]) {
+    let (character, stat) = match args {
+        [character, stat, ..] if STAT_NAMES.contains(&stat.as_str()) => (character, stat),
+        _ => {
+            println!(
+                "usage: loadout <character> <stat> [max element value], where stat is one of {}",
+                STAT_NAMES.join(", ")
+            );
+            return;
+        }
+    };
+    let max_element_value = match args.get(2).map(|value| value.parse()) {
+        Some(Ok(value)) => value,
+        Some(Err(_)) => {
+            println!("max element value must be a number");
+            return;
+        }
+        None => simulator::UNLIMITED,
+    };
+
+    let dataset = get_dataset(settings);
+    if !dataset.item_validation_sets.characters.contains(character) {
+        println!("{} is not in the Characters list of lists.yml", character);
+        return;
+    }
+    let items = dataset.items();
+
+    // each slot is ranked once, for both the rankings and the suggested loadout
+    let rankings = loadout::rank_slots(&items, character, stat, max_element_value);
+    for (slot, candidates) in &rankings {
+        println!("{} ranking for {} ({}):", slot, character, stat);
+        if candidates.is_empty() {
+            println!("- none");
+        }
+        for (rank, candidate) in candidates.iter().enumerate() {
+            println!(
+                "{}. {}: {} {} (base {}, loops +{}), {} trait slot(s)",
+                rank + 1,
+                candidate.item.name,
+                stat,
+                candidate.score(),
+                candidate.base,
+                candidate.bonus,
+                candidate.trait_slots
+            );
+        }
+    }
+
+    let best = loadout::best_loadout(&rankings);
+    println!("Suggested loadout for {} ({}):", character, stat);
+    for (slot, candidate) in &best.slots {
+        match candidate {
+            Some(candidate) => println!(
+                "- {}: {} ({})",
+                slot,
+                candidate.item.name,
+                candidate.score()
+            ),
+            None => println!("- {}: nothing equippable", slot),
+        }
+    }
+    println!("Total {}: {}", stat, best.total());
+}
+
+/// `battle <item name> <quality> [effect]...`: estimate the damage, area and CC efficiency of an
+/// attack item. Without effects, the effects of the item's best roll are used.
+fn run_battle(settings: &Settings, args: &[String]) {
+    let (name, quality) = match args {
+        [name, quality, ..] => match battle::parse_quality(quality) {
+            Ok(quality) => (name, quality),
+            Err(error) => {
+                println!("{}", error);
+                return;
+            }
+        },
+        _ => {
+            println!("usage: battle <item name> <quality> [effect]...");
+            return;
+        }
+    };
+
+    let battle_data = get_battle_data(&settings.data_folder);
+    let items = get_dataset(settings).items();
+    let item = match items.iter().find(|item| item.is_named(name)) {
+        Some(item) => item,
+        None => {
+            println!("no item named {}", name);
+            return;
+        }
+    };
+    if !item.has_classification("Battle Item") {
+        println!("{} is not a battle item", name);
+        return;
+    }
+
+    let effects: Vec<String> = if args.len() > 2 {
+        args[2..].to_vec()
+    } else {
+        simulator::best_roll(item, simulator::UNLIMITED)
+            .effects
+            .into_iter()
+            .map(|effect| effect.effect)
+            .collect()
+    };
+
+    let report = battle_data.evaluate(item, &effects, quality);
+    println!(
+        "{} (quality {}): {}",
+        item.name,
+        quality,
+        effects.join(", ")
+    );
+    println!("- damage per use: {:.0}", report.damage);
+    println!("- area: {}", report.area);
+    match report.cc_efficiency() {
+        Some(efficiency) => println!(
+            "- CC cost: {}, {:.1} damage per CC",
+            report.cc_cost, efficiency
+        ),
+        None => println!("- CC cost: none"),
+    }
+    for effect in &report.unknown_effects {
+        println!(
+            "- no battle data for effect '{}' (add it to battle.yml)",
+            effect
+        );
+    }
+}
+
+/// true if every item file validates, otherwise lists the ones that don't
+fn is_dataset_valid(dataset: &Dataset) -> bool {
+    let invalid_files = dataset.invalid_files();
+    if !invalid_files.is_empty() {
+        println!("these item files don't validate, run the validator for details:");
+        for name in &invalid_files {
+            println!("- {}", name);
+        }
+    }
+    invalid_files.is_empty()
+}
+
+/// `diff <data folder> <data folder>`: list the items added, removed and changed between two
+/// versions of the data folder, e.g. before and after a contribution
+fn run_diff(args: &[String]) {
+    let (old_folder, new_folder) = match args {
+        [old, new] => (old, new),
+        _ => {
+            println!("usage: diff <data folder> <data folder>");
+            return;
+        }
+    };
+    // only the items are compared, so older data folders without lists.yml or rules.yml work
+    let load = |folder: &str| match dataset::load_items(folder) {
+        Ok(items) => Some(items),
+        Err(error) => {
+            println!("unable to load {}: {}", folder, error);
+            None
+        }
+    };
+    let (old, new) = match (load(old_folder), load(new_folder)) {
+        (Some(old), Some(new)) => (old, new),
+        _ => return,
+    };
+
+    let diffs = diff::diff(&old, &new);
+    if diffs.is_empty() {
+        println!("No changes to the items");
+        return;
+    }
+    let (mut added, mut removed, mut changed) = (0, 0, 0);
+    for item_diff in &diffs {
+        println!("{}", item_diff);
+        match item_diff {
+            diff::ItemDiff::Added(_) => added += 1,
+            diff::ItemDiff::Removed(_) => removed += 1,
+            diff::ItemDiff::Changed(..) => changed += 1,
+        }
+    }
+    println!(
+        "{} items added, {} removed, {} changed",
+        added, removed, changed
+    );
+}
+
+/// `export <sqlite|json|csv> [path]`: write the dataset out as a SQLite database, a JSON
+/// document, or a folder of CSV files. Nothing is written unless every item file validates.
+fn run_export(settings: &Settings, args: &[String]) {
+    let (format, default_path) = match args.first().map(String::as_str) {
+        Some("sqlite") => ("sqlite", "items.sqlite"),
+        Some("json") => ("json", "items.json"),
+        Some("csv") => ("csv", "items-csv"),
+        _ => {
+            println!("usage: export <sqlite|json|csv> [path]");
+            return;
+        }
+    };
+
+    let dataset = get_dataset(settings);
+    if !is_dataset_valid(&dataset) {
+        return;
+    }
+
+    let path = args.get(1).map(String::as_str).unwrap_or(default_path);
+    let items = dataset.items();
+    let result = match format {
+        "sqlite" => export::sqlite::export(&items, path).map_err(|error| error.to_string()),
+        "json" => export::json::export(&items, path).map_err(|error| error.to_string()),
+        _ => export::csv::export(&items, path).map_err(|error| error.to_string()),
+    };
+    match result {
+        Ok(()) => println!("Exported {} items to {}", items.len(), path),
+        Err(error) => println!("unable to export to {}: {}", path, error),
+    }
+}
+
+/// `render <item name> [path]`: draw the material loops of an item as SVG
+/// (into `<item name>.svg` by default)
+fn run_render(settings: &Settings, args: &[String]) {
+    let name = match args.first() {
+        Some(name) => name,
+        None => {
+            println!("usage: render <item name> [path]");
+            return;
+        }
+    };
+    let items = get_dataset(settings).items();
+    let synthesis = match items.iter().find(|item| item.is_named(name)) {
+        Some(item) => match &item.synthesis {
+            Some(synthesis) => synthesis,
+            None => {
+                println!("{} is gathered, it has no material loops", name);
+                return;
+            }
+        },
+        None => {
+            println!("no item named {}", name);
+            return;
+        }
+    };
+
+    let path = match args.get(1) {
+        Some(path) => path.clone(),
+        None => format!("{}.svg", item_file::slug(name)),
+    };
+    match fs::write(&path, render::loop_tree_svg(synthesis)) {
+        Ok(()) => println!("Rendered {} to {}", name, path),
+        Err(error) => println!("unable to write {}: {}", path, error),
+    }
+}
+
+/// `search <words>`: find items by part of their name, an effect, a category or a gather
+/// location, forgiving small typos
+fn run_search(settings: &Settings, args: &[String]) {
+    if args.is_empty() {
+        println!("usage: search <words>, e.g. search uni spike");
+        return;
+    }
+    let dataset = get_dataset(settings);
+    let items = dataset.items();
+    let results = search::SearchIndex::new(&items).search(&args.join(" "));
+    for result in results.iter().take(MAX_SEARCH_RESULTS) {
+        let item = &items[result.item];
+        if result.field == search::SearchField::Name && result.text == item.name {
+            println!("{:03} {}", item.item_number, item.name);
+        } else {
+            println!(
+                "{:03} {} ({}: {})",
+                item.item_number,
+                item.name,
+                result.field.as_str(),
+                result.text
+            );
+        }
+    }
+    if results.len() > MAX_SEARCH_RESULTS {
+        println!("... and {} more", results.len() - MAX_SEARCH_RESULTS);
+    }
+    println!("{} items found", results.len());
+}
+
+/// `site [folder] [--language xx]`: generate the static HTML encyclopedia (into `site` by
+/// default), with item names and effects in the given language (English by default)
+fn run_site(settings: &Settings, args: &[String]) {
+    let mut folder = "site";
+    let mut language = model::DEFAULT_LANGUAGE;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--language" => match args.next() {
+                Some(value) => language = value,
+                None => {
+                    println!("--language needs a language, like --language ja");
+                    return;
+                }
+            },
+            _ => folder = arg,
+        }
+    }
+
+    let dataset = get_dataset(settings);
+    if !is_dataset_valid(&dataset) {
+        return;
+    }
+
+    let items = dataset.items();
+    match site::generate(&items, folder, language) {
+        Ok(()) => println!("Generated pages for {} items in {}", items.len(), folder),
+        Err(error) => println!("unable to generate the site in {}: {}", folder, error),
+    }
+}
+
+/// `schema [path]`: write a JSON Schema for item files (into `item.schema.json` by default), for
+/// editors that can check YAML against a schema
+fn run_schema(settings: &Settings, args: &[String]) {
+    let dataset = get_dataset(settings);
+    let path = args
+        .first()
+        .map(String::as_str)
+        .unwrap_or("item.schema.json");
+    match schema::export(&dataset.item_validation_sets, &dataset.item_rules, path) {
+        Ok(()) => println!("Wrote the item schema to {}", path),
+        Err(error) => println!("unable to write {}: {}", path, error),
+    }
+}
+
+/// `new-item --number <number> --name <name> --gathered|--synthesized`: create an item file
+/// from the template, then offer to create stubs for the items the dataset refers to that don't
+/// have files yet
+fn run_new_item(settings: &Settings, args: &[String]) {
+    let usage = "usage: new-item --number <number> --name <name> --gathered|--synthesized";
+    let mut number = None;
+    let mut name = None;
+    let mut kind = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--number" => number = args.next().and_then(|value| value.parse::<i64>().ok()),
+            "--name" => name = args.next().filter(|value| !value.trim().is_empty()),
+            "--gathered" => kind = Some(ItemKind::Gathered),
+            "--synthesized" => kind = Some(ItemKind::Synthesized),
+            _ => {}
+        }
+    }
+    let (number, name, kind) = match (number, name, kind) {
+        (Some(number), Some(name), Some(kind)) => (number, name.trim(), kind),
+        _ => {
+            println!("{}", usage);
+            return;
+        }
+    };
+
+    let mut dataset = get_dataset(settings);
+    if dataset.item_validation_sets.materials.contains(name) {
+        println!("{} already has an item file", name);
+        return;
+    }
+    if let Some(item) = dataset
+        .items()
+        .iter()
+        .find(|item| item.item_number == number)
+    {
+        println!("note: item number {} is also used by {}", number, item.name);
+    }
+
+    let (file_name, contents) = item_file::scaffold(number, name, kind);
+    if !write_new_file(settings, &file_name, &contents) {
+        return;
+    }
+    dataset.update_file(&file_name, Some(contents));
+
+    let missing = dataset.missing_items();
+    if missing.is_empty() {
+        return;
+    }
+    println!("these items are referred to, but have no item file:");
+    for (name, referenced_by) in &missing {
+        println!("- {} (referenced by {})", name, referenced_by.join(", "));
+    }
+    print!("Create stub files for them? [y/N] ");
+    io::stdout()
+        .flush()
+        .expect("Unable to write to the terminal");
+    let mut answer = String::new();
+    io::stdin()
+        .read_line(&mut answer)
+        .expect("Unable to read the answer");
+    if answer.trim().eq_ignore_ascii_case("y") {
+        for (name, referenced_by) in &missing {
+            let (file_name, contents) = item_file::stub(name, referenced_by);
+            write_new_file(settings, &file_name, &contents);
+        }
+    }
+}
+
+/// write a file into the items folder, unless it's already there. Returns true if it was written.
+fn write_new_file(settings: &Settings, file_name: &str, contents: &str) -> bool {
+    let path = format!("{}/items/{}", settings.data_folder, file_name);
+    if Path::new(&path).exists() {
+        println!("{} already exists, leaving it alone", path);
+        return false;
+    }
+    match fs::write(&path, contents) {
+        Ok(()) => {
+            println!("Created {}", path);
+            true
+        }
+        Err(error) => {
+            println!("unable to write {}: {}", path, error);
+            false
+        }
+    }
+}
+
+/// `query <query>`: list the items matching a query like `category:(Fuel) level<=10 gathered`.
+/// Quote the whole query so the shell keeps any quotes inside it.
+fn run_query(settings: &Settings, args: &[String]) {
+    if args.is_empty() {
+        println!("usage: query <query>, e.g. query 'element:Fire level<=10 tool:\"Bomb Rod\"'");
+        return;
+    }
+    let dataset = get_dataset(settings);
+    let mut items = dataset.items();
+    items.sort_by_key(|item| item.item_number);
+    let query = match query::Query::parse(&args.join(" "), &dataset.item_validation_sets, &items) {
+        Ok(query) => query,
+        Err(error) => {
+            println!("unable to read the query: {}", error);
+            return;
+        }
+    };
+
+    let matches = query.filter(&items);
+    for item in &matches {
+        println!(
+            "{:03} {} (Level {})",
+            item.item_number, item.name, item.level
+        );
+    }
+    println!("{} items match", matches.len());
+}
+
+/// `rename-item <name> <new name>`: rename an item, its file and every reference to it, then
+/// validate the dataset again to check that nothing refers to the old name
+fn run_rename_item(settings: &Settings, args: &[String]) {
+    let (old, new) = match args {
+        [old, new] => (old.as_str(), new.as_str()),
+        _ => {
+            println!("usage: rename-item <name> <new name>");
+            return;
+        }
+    };
+
+    let dataset = get_dataset(settings);
+    let changes = match rename::plan(&dataset, old, new) {
+        Ok(changes) => changes,
+        Err(error) => {
+            println!("unable to rename {}: {}", old, error);
+            return;
+        }
+    };
+    let items_folder = format!("{}/items", settings.data_folder);
+    if let Err(error) = rename::apply(Path::new(&items_folder), &changes) {
+        println!("unable to rename {}: {}", old, error);
+        return;
+    }
+    for change in &changes {
+        if change.new_file != change.file {
+            println!("Renamed {} to {}", change.file, change.new_file);
+        }
+        if change.references > 0 {
+            println!(
+                "Updated {} references in {}",
+                change.references, change.new_file
+            );
+        }
+    }
+
+    let dataset = get_dataset(settings);
+    if let Some(referenced_by) = dataset.missing_items().get(old) {
+        println!("{} is still referred to by:", old);
+        for name in referenced_by {
+            println!("- {}", name);
+        }
+    }
+    if is_dataset_valid(&dataset) {
+        println!("Every item file validates");
+    }
 }
+
+/// `worklist [path]`: write the items that are referenced but have no item file as Markdown
+/// (into `missing-items.md` by default), most referenced first
+fn run_worklist(settings: &Settings, args: &[String]) {
+    let dataset = get_dataset(settings);
+    let path = args
+        .first()
+        .map(String::as_str)
+        .unwrap_or("missing-items.md");
+    let missing = worklist::missing_items(&dataset);
+    match fs::write(path, worklist::markdown(&missing)) {
+        Ok(()) => println!("Listed {} missing items in {}", missing.len(), path),
+        Err(error) => println!("unable to write {}: {}", path, error),
+    }
+}
+
 fn main() {
     // setup the verbose parameter
     let args: Vec<String> = env::args().collect();
     println!("{:?}", args);
     let verbose = args.contains(&"verbose".to_string());
+    let watch = args.contains(&"--watch".to_string());
 
     let settings = settings::get_settings();
 
-    let mut item_validation_sets = get_item_validation_sets(&settings.data_folder);
-
-    let mut item_contents: Vec<file_contents::FileContents> = Vec::new();
-    let item_dir_path = format!("{}/items", settings.data_folder);
-    file_contents::load_directory(&mut item_contents, &item_dir_path).unwrap();
-
-    validation_sets::add_materials_to_validation_sets(&mut item_validation_sets, &item_contents)
-        .unwrap();
-
-    validate_items(&item_contents, &item_validation_sets, verbose);
+    match args.get(1).map(String::as_str) {
+        Some("battle") => run_battle(&settings, &args[2..]),
+        Some("diff") => run_diff(&args[2..]),
+        Some("export") => run_export(&settings, &args[2..]),
+        Some("loadout") => run_loadout(&settings, &args[2..]),
+        Some("new-item") => run_new_item(&settings, &args[2..]),
+        Some("query") => run_query(&settings, &args[2..]),
+        Some("rename-item") => run_rename_item(&settings, &args[2..]),
+        Some("render") => run_render(&settings, &args[2..]),
+        Some("schema") => run_schema(&settings, &args[2..]),
+        Some("search") => run_search(&settings, &args[2..]),
+        Some("site") => run_site(&settings, &args[2..]),
+        Some("worklist") => run_worklist(&settings, &args[2..]),
+        _ if watch => run_watch(&settings, verbose),
+        _ => run_validation(&settings, verbose),
+    }
 }
@@ -0,0 +1,519 @@
+//! A typed view of an item file.
+//!
+//! Loading into the model is lenient: missing or malformed values fall back to defaults, since
+//! reporting problems is the job of `validate_item`. Validate an item before trusting its model.
+
+use crate::item_file::slug;
+
+use std::collections::BTreeMap;
+
+extern crate yaml_rust;
+use yaml_rust::yaml::Hash;
+use yaml_rust::{ScanError, Yaml, YamlLoader};
+
+/// the stats an equipment item can raise, in the order the game lists them
+pub const STAT_NAMES: [&str; 5] = ["HP", "MP", "ATK", "DEF", "SPD"];
+
+/// the language of `Item::name` and `LoopLevel::effect`, and of item files written as plain text
+pub const DEFAULT_LANGUAGE: &str = "en";
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Item {
+    /// the stable ID from the `ID` key, if the item has one (see `Item::id`)
+    pub id: Option<String>,
+    /// the English name
+    pub name: String,
+    /// the name in other languages, by language code (e.g. `ja`)
+    pub names: BTreeMap<String, String>,
+    pub item_number: i64,
+    pub level: i64,
+    pub classifications: Vec<String>,
+    pub cc_cost: Option<i64>,
+    pub categories: Vec<String>,
+    pub elements: Vec<ElementValue>,
+    pub materials: Vec<String>,
+    pub gather_locations: Vec<String>,
+    pub gathering_tools: Vec<String>,
+    pub synthesis: Option<Synthesis>,
+    pub equipment: Option<Equipment>,
+}
+
+/// an element, with its value when one is given (e.g. `- Fire: 2`, or just `- Fire`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct ElementValue {
+    pub element: String,
+    pub value: Option<i64>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Synthesis {
+    pub required_materials: i64,
+    pub required_alchemy_level: i64,
+    pub material_loops: Vec<MaterialLoop>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MaterialLoop {
+    /// what the loop adds to the item (e.g. Effect 1)
+    pub name: String,
+    pub distance: i64,
+    pub position: i64,
+    pub linked_from_position: Option<i64>,
+    pub material: String,
+    pub levels: Vec<LoopLevel>,
+    pub unlock: Vec<ElementValue>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LoopLevel {
+    /// the name/type of effect (e.g. Scatter), in English
+    pub effect: String,
+    /// the effect in other languages, by language code
+    pub effect_names: BTreeMap<String, String>,
+    pub elements: Vec<ElementValue>,
+    /// only for Recipe Morph effects
+    pub recipe: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Equipment {
+    pub stats: Stats,
+    /// the characters that can equip the item (empty means everyone)
+    pub equippable_by: Vec<String>,
+}
+
+/// equipment base stats, stats left out of the item file are 0
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Stats {
+    pub hp: i64,
+    pub mp: i64,
+    pub atk: i64,
+    pub def: i64,
+    pub spd: i64,
+}
+
+impl Item {
+    pub fn from_yaml(yaml: &Yaml) -> Item {
+        let (name, names) = localized_names(&yaml["Name"]);
+        Item {
+            id: yaml["ID"].as_str().map(|id| id.trim().to_string()),
+            name,
+            names,
+            item_number: yaml["Item Number"].as_i64().unwrap_or(0),
+            level: yaml["Level"].as_i64().unwrap_or(0),
+            classifications: string_list(&yaml["Classifications"]),
+            cc_cost: yaml["CC Cost"].as_i64(),
+            categories: string_list(&yaml["Category"]),
+            elements: element_list(&yaml["Element"]),
+            materials: string_list(&yaml["Materials"]),
+            gather_locations: string_list(&yaml["Gather Locations"]),
+            gathering_tools: string_list(&yaml["Gathering Tools"]),
+            synthesis: match &yaml["Synthesis"] {
+                Yaml::Hash(_) => Some(Synthesis::from_yaml(&yaml["Synthesis"])),
+                _ => None,
+            },
+            equipment: match &yaml["Equipment"] {
+                Yaml::Hash(_) => Some(Equipment::from_yaml(&yaml["Equipment"])),
+                _ => None,
+            },
+        }
+    }
+
+    /// the stable ID of the item. Items without an `ID` key use their English name as a slug
+    /// (e.g. `explosive-uni`), so adding translations doesn't change it.
+    pub fn id(&self) -> String {
+        match &self.id {
+            Some(id) => id.clone(),
+            None => slug(&self.name),
+        }
+    }
+
+    /// the name in the given language, or the English name if there's no translation
+    pub fn name_in(&self, language: &str) -> &str {
+        self.names.get(language).unwrap_or(&self.name)
+    }
+
+    /// every way other items can refer to this one: its ID, and its name in any language
+    pub fn reference_names(&self) -> Vec<String> {
+        let mut names = vec![self.id(), self.name.clone()];
+        names.extend(self.names.values().cloned());
+        names.retain(|name| !name.is_empty());
+        names.dedup();
+        names
+    }
+
+    /// true if a Materials, Material or Recipe value refers to this item
+    pub fn is_named(&self, reference: &str) -> bool {
+        !reference.is_empty() && self.reference_names().iter().any(|name| name == reference)
+    }
+
+    pub fn has_classification(&self, classification: &str) -> bool {
+        self.classifications.iter().any(|c| c == classification)
+    }
+
+    /// gathered items have no synthesis details
+    pub fn is_gathered(&self) -> bool {
+        self.synthesis.is_none()
+    }
+
+    /// true if the item lists the material (an item name, or a category in parentheses) in its
+    /// Materials or in one of its material loops
+    pub fn uses_material(&self, material: &str) -> bool {
+        self.materials.iter().any(|m| m == material)
+            || self.synthesis.iter().any(|synthesis| {
+                synthesis
+                    .material_loops
+                    .iter()
+                    .any(|material_loop| material_loop.material == material)
+            })
+    }
+
+    /// the names of the items (not categories) this item refers to, in its Materials, its loop
+    /// Materials and its Recipe Morphs
+    pub fn referenced_items(&self) -> Vec<&str> {
+        let mut references: Vec<&str> = self.materials.iter().map(String::as_str).collect();
+        for material_loop in self
+            .synthesis
+            .iter()
+            .flat_map(|synthesis| &synthesis.material_loops)
+        {
+            references.push(&material_loop.material);
+            references.extend(
+                material_loop
+                    .levels
+                    .iter()
+                    .filter_map(|level| level.recipe.as_deref()),
+            );
+        }
+        references
+            .into_iter()
+            .filter_map(|reference| match parse_material(reference) {
+                (MaterialKind::Item, name) => Some(name),
+                (MaterialKind::Category, _) => None,
+            })
+            .collect()
+    }
+}
+
+impl Synthesis {
+    fn from_yaml(yaml: &Yaml) -> Synthesis {
+        let mut material_loops = Vec::new();
+        if let Yaml::Array(list) = &yaml["Material Loops"] {
+            for (name, details) in list.iter().filter_map(|value| value.as_hash()).flatten() {
+                material_loops.push(MaterialLoop::from_yaml(&string_value(name), details));
+            }
+        }
+        Synthesis {
+            required_materials: yaml["Required Materials"].as_i64().unwrap_or(0),
+            required_alchemy_level: yaml["Required Alchemy Level"].as_i64().unwrap_or(0),
+            material_loops,
+        }
+    }
+
+    /// the loop found at the given position
+    pub fn material_loop(&self, position: i64) -> Option<&MaterialLoop> {
+        self.material_loops
+            .iter()
+            .find(|material_loop| material_loop.position == position)
+    }
+}
+
+impl MaterialLoop {
+    fn from_yaml(name: &str, yaml: &Yaml) -> MaterialLoop {
+        let mut levels = Vec::new();
+        if let Yaml::Array(list) = &yaml["Levels"] {
+            for (effect, details) in list.iter().filter_map(|value| value.as_hash()).flatten() {
+                levels.push(LoopLevel {
+                    effect: string_value(effect),
+                    effect_names: translations(&details["Translations"]),
+                    elements: element_list(&details["Element"]),
+                    recipe: details["Recipe"].as_str().map(|s| s.trim().to_string()),
+                });
+            }
+        }
+        MaterialLoop {
+            name: name.to_string(),
+            distance: yaml["Distance"].as_i64().unwrap_or(0),
+            position: yaml["Position"].as_i64().unwrap_or(0),
+            linked_from_position: yaml["Linked From Position"].as_i64(),
+            material: string_value(&yaml["Material"]),
+            levels,
+            unlock: element_list(&yaml["Unlock"]),
+        }
+    }
+}
+
+impl Equipment {
+    fn from_yaml(yaml: &Yaml) -> Equipment {
+        let stats = &yaml["Stats"];
+        let stat = |name: &str| stats[name].as_i64().unwrap_or(0);
+        Equipment {
+            stats: Stats {
+                hp: stat("HP"),
+                mp: stat("MP"),
+                atk: stat("ATK"),
+                def: stat("DEF"),
+                spd: stat("SPD"),
+            },
+            equippable_by: string_list(&yaml["Equippable By"]),
+        }
+    }
+
+    /// true if the character can equip the item
+    pub fn equippable_by(&self, character: &str) -> bool {
+        self.equippable_by.is_empty() || self.equippable_by.iter().any(|c| c == character)
+    }
+}
+
+impl Stats {
+    /// look up a stat by the name used in item files (e.g. ATK)
+    pub fn get(&self, stat: &str) -> Option<i64> {
+        match stat {
+            "HP" => Some(self.hp),
+            "MP" => Some(self.mp),
+            "ATK" => Some(self.atk),
+            "DEF" => Some(self.def),
+            "SPD" => Some(self.spd),
+            _ => None,
+        }
+    }
+}
+
+impl LoopLevel {
+    /// the effect in the given language, or in English if there's no translation
+    pub fn effect_in(&self, language: &str) -> &str {
+        self.effect_names.get(language).unwrap_or(&self.effect)
+    }
+}
+
+/// point item references written as an ID or a translated name at the English name, so items
+/// can be matched up by name. References to items that aren't in the list are left alone.
+pub fn resolve_references(items: &mut [Item]) {
+    let mut english_names = BTreeMap::new();
+    for item in items.iter() {
+        for name in item.reference_names() {
+            english_names
+                .entry(name)
+                .or_insert_with(|| item.name.clone());
+        }
+    }
+    let resolve = |reference: &mut String| {
+        if let Some(name) = english_names.get(reference.as_str()) {
+            *reference = name.clone();
+        }
+    };
+    for item in items.iter_mut() {
+        item.materials.iter_mut().for_each(resolve);
+        for material_loop in item
+            .synthesis
+            .iter_mut()
+            .flat_map(|synthesis| &mut synthesis.material_loops)
+        {
+            resolve(&mut material_loop.material);
+            material_loop
+                .levels
+                .iter_mut()
+                .filter_map(|level| level.recipe.as_mut())
+                .for_each(resolve);
+        }
+    }
+}
+
+/// parse the contents of an item file into the model. Only the first document is read, and an
+/// empty file gives an item with nothing filled in.
+pub fn load_item(contents: &str) -> Result<Item, ScanError> {
+    let docs = YamlLoader::load_from_str(contents)?;
+    Ok(Item::from_yaml(docs.first().unwrap_or(&Yaml::BadValue)))
+}
+
+/// material lists mix item categories, written in parentheses (e.g. `(Uni)`), with item names
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MaterialKind {
+    Category,
+    Item,
+}
+
+impl MaterialKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MaterialKind::Category => "category",
+            MaterialKind::Item => "item",
+        }
+    }
+}
+
+/// split a material (or category) into its kind and its name without parentheses
+pub fn parse_material(value: &str) -> (MaterialKind, &str) {
+    match value
+        .strip_prefix('(')
+        .and_then(|value| value.strip_suffix(')'))
+    {
+        Some(category) => (MaterialKind::Category, category),
+        None => (MaterialKind::Item, value),
+    }
+}
+
+/// a name written either as plain (English) text, or as a map of language codes to names like
+/// `{en: Explosive Uni, ja: ...}`. Returns the English name and the other languages.
+fn localized_names(yaml: &Yaml) -> (String, BTreeMap<String, String>) {
+    let mut names = translations(yaml);
+    let name = match yaml {
+        Yaml::Hash(_) => names.remove(DEFAULT_LANGUAGE).unwrap_or_default(),
+        _ => string_value(yaml),
+    };
+    (name, names)
+}
+
+/// a map of language codes to text
+fn translations(yaml: &Yaml) -> BTreeMap<String, String> {
+    match yaml {
+        Yaml::Hash(hash) => hash
+            .iter()
+            .map(|(language, text)| (string_value(language), string_value(text)))
+            .filter(|(language, text)| !language.is_empty() && !text.is_empty())
+            .collect(),
+        _ => BTreeMap::new(),
+    }
+}
+
+fn string_value(yaml: &Yaml) -> String {
+    yaml.as_str().unwrap_or_default().trim().to_string()
+}
+
+fn string_list(yaml: &Yaml) -> Vec<String> {
+    if let Yaml::Array(list) = yaml {
+        list.iter()
+            .filter_map(|value| value.as_str())
+            .map(|value| value.trim().to_string())
+            .collect()
+    } else {
+        Vec::new()
+    }
+}
+
+/// element lists hold either plain names (`- Fire`) or name/value pairs (`- Fire: 2`)
+fn element_list(yaml: &Yaml) -> Vec<ElementValue> {
+    let mut elements = Vec::new();
+    if let Yaml::Array(list) = yaml {
+        for value in list {
+            match value {
+                Yaml::String(element) => elements.push(ElementValue {
+                    element: element.trim().to_string(),
+                    value: None,
+                }),
+                Yaml::Hash(hash) => elements.extend(element_pairs(hash)),
+                _ => {}
+            }
+        }
+    }
+    elements
+}
+
+fn element_pairs(hash: &Hash) -> impl Iterator<Item = ElementValue> + '_ {
+    hash.iter().map(|(element, value)| ElementValue {
+        element: string_value(element),
+        value: value.as_i64(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SWORD: &str = "
+Name: Test Sword
+Item Number: 900
+Level: 5
+Classifications: [Equip Item, Weapon]
+Category: [(Weapon)]
+Element:
+- Fire: 2
+- Ice
+Materials: [(Ore), Red Supplement]
+Equipment:
+  Stats:
+    ATK: 25
+    SPD: 4
+  Equippable By: [Ryza, Lent]
+Synthesis:
+  Required Materials: 2
+  Required Alchemy Level: 3
+  Material Loops:
+  - Effect 1:
+      Distance: 0
+      Position: 0
+      Material: Gunpowder
+      Levels:
+      - Recipe Morph:
+          Recipe: Big Sword
+          Element:
+          - Fire: 2
+";
+
+    #[test]
+    fn items_load_into_the_model() {
+        let item = load_item(SWORD).unwrap();
+        assert_eq!(
+            (item.name.as_str(), item.item_number, item.level),
+            ("Test Sword", 900, 5)
+        );
+        assert_eq!(
+            item.elements,
+            [
+                ElementValue {
+                    element: String::from("Fire"),
+                    value: Some(2)
+                },
+                ElementValue {
+                    element: String::from("Ice"),
+                    value: None
+                },
+            ]
+        );
+        assert!(item.has_classification("Weapon"));
+        assert!(!item.is_gathered());
+
+        let synthesis = item.synthesis.as_ref().unwrap();
+        assert_eq!(synthesis.required_alchemy_level, 3);
+        let level = &synthesis.material_loop(0).unwrap().levels[0];
+        assert_eq!(level.recipe.as_deref(), Some("Big Sword"));
+        assert!(synthesis.material_loop(1).is_none());
+    }
+
+    #[test]
+    fn equipment_stats_default_to_zero() {
+        let item = load_item(SWORD).unwrap();
+        let equipment = item.equipment.as_ref().unwrap();
+        assert_eq!(equipment.stats.get("ATK"), Some(25));
+        assert_eq!(equipment.stats.get("HP"), Some(0));
+        assert_eq!(equipment.stats.get("LUCK"), None);
+        assert!(equipment.equippable_by("Lent"));
+        assert!(!equipment.equippable_by("Klaudia"));
+
+        // anyone can equip items without an Equippable By list
+        let item = load_item("Name: Ring\nEquipment:\n  Stats: {}\n").unwrap();
+        assert!(item.equipment.unwrap().equippable_by("Klaudia"));
+        assert!(load_item("Name: Uni\n").unwrap().equipment.is_none());
+    }
+
+    #[test]
+    fn references_leave_out_categories() {
+        let item = load_item(SWORD).unwrap();
+        assert_eq!(
+            item.referenced_items(),
+            ["Red Supplement", "Gunpowder", "Big Sword"]
+        );
+        assert!(item.uses_material("(Ore)"));
+        assert!(item.uses_material("Gunpowder"));
+        assert!(!item.uses_material("Big Sword"));
+        assert_eq!(parse_material("(Ore)"), (MaterialKind::Category, "Ore"));
+        assert_eq!(parse_material("Ore)"), (MaterialKind::Item, "Ore)"));
+    }
+
+    #[test]
+    fn element_values_must_be_listed() {
+        // `Unlock:` followed by `Fire: 2` without the dash isn't a list
+        let item = load_item("Name: Uni\nElement:\n  Fire: 2\n").unwrap();
+        assert!(item.elements.is_empty());
+    }
+}
@@ -0,0 +1,480 @@
+//! A small query language for finding items, e.g.
+//! `category:(Fuel) element:Fire level<=10 gathered tool:"Bomb Rod"`.
+//!
+//! A query is a list of terms separated by spaces, and an item matches when it matches every
+//! term. Terms are:
+//!
+//! - `field:value` for `category`, `element`, `classification`, `tool`, `character`, `material`,
+//!   `location` and `name`. Values from `lists.yml` are checked when the query is parsed, so a
+//!   typo is an error rather than an empty result. Case doesn't matter, and categories can be
+//!   written with or without parentheses.
+//! - `level`, `number` and `cc` compared with `:`, `=`, `<`, `<=`, `>` or `>=`, e.g. `level<=10`
+//! - `gathered`, `synthesized` and `equipment`, for how the item is obtained and what it is
+//! - any other word, which matches item names containing it
+//!
+//! Values with spaces are written in double quotes, and a term starting with `-` matches the
+//! items that don't match the rest of it.
+
+use crate::model::{self, Item, MaterialKind};
+use crate::validation_sets::ItemValidationSets;
+
+use std::collections::HashSet;
+use std::fmt;
+
+pub struct Query {
+    terms: Vec<Term>,
+}
+
+struct Term {
+    negated: bool,
+    condition: Condition,
+}
+
+enum Condition {
+    Category(String),
+    Element(String),
+    Classification(String),
+    Tool(String),
+    Character(String),
+    Material(String),
+    /// gather locations aren't listed in lists.yml, so any location can be asked for
+    Location(String),
+    /// part of the item's name, in lowercase
+    Name(String),
+    Number(NumberField, Comparison, i64),
+    Gathered,
+    Synthesized,
+    Equipment,
+}
+
+#[derive(Clone, Copy)]
+enum NumberField {
+    Level,
+    ItemNumber,
+    CcCost,
+}
+
+#[derive(Clone, Copy)]
+enum Comparison {
+    Equal,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+#[derive(Debug)]
+pub enum QueryError {
+    UnclosedQuote,
+    UnknownField(String),
+    /// a value that isn't in the field's list in lists.yml
+    UnknownValue(String, String),
+    /// a field compared with `<` and the like that doesn't hold a number
+    NotANumberField(String),
+    BadNumber(String),
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            QueryError::UnclosedQuote => write!(f, "a quote isn't closed"),
+            QueryError::UnknownField(field) => write!(f, "unknown field {}", field),
+            QueryError::UnknownValue(field, value) => {
+                write!(f, "{} isn't a known value for {}", value, field)
+            }
+            QueryError::NotANumberField(field) => {
+                write!(
+                    f,
+                    "{} can't be compared, only level, number and cc can",
+                    field
+                )
+            }
+            QueryError::BadNumber(value) => write!(f, "{} isn't a number", value),
+        }
+    }
+}
+
+impl Query {
+    /// parse a query, checking its values against the lists. Materials can be given by any of
+    /// the names or the ID of one of the items, which are matched by its English name.
+    pub fn parse(
+        text: &str,
+        sets: &ItemValidationSets,
+        items: &[Item],
+    ) -> Result<Query, QueryError> {
+        let terms = tokens(text)?
+            .iter()
+            .map(|token| term(token, sets, items))
+            .collect::<Result<_, _>>()?;
+        Ok(Query { terms })
+    }
+
+    /// true if the item matches every term. An empty query matches every item.
+    pub fn matches(&self, item: &Item) -> bool {
+        self.terms
+            .iter()
+            .all(|term| term.condition.matches(item) != term.negated)
+    }
+
+    /// the items matching the query, in the order given
+    pub fn filter<'a>(&self, items: &'a [Item]) -> Vec<&'a Item> {
+        items.iter().filter(|item| self.matches(item)).collect()
+    }
+}
+
+impl Condition {
+    fn matches(&self, item: &Item) -> bool {
+        match self {
+            Condition::Category(category) => item.categories.contains(category),
+            Condition::Element(element) => {
+                item.elements.iter().any(|value| &value.element == element)
+            }
+            Condition::Classification(classification) => item.has_classification(classification),
+            Condition::Tool(tool) => item.gathering_tools.contains(tool),
+            Condition::Character(character) => item
+                .equipment
+                .as_ref()
+                .is_some_and(|equipment| equipment.equippable_by(character)),
+            Condition::Material(material) => item.uses_material(material),
+            Condition::Location(location) => item
+                .gather_locations
+                .iter()
+                .any(|value| value.eq_ignore_ascii_case(location)),
+            Condition::Name(part) => item.name.to_lowercase().contains(part),
+            Condition::Number(field, comparison, number) => {
+                let value = match field {
+                    NumberField::Level => Some(item.level),
+                    NumberField::ItemNumber => Some(item.item_number),
+                    NumberField::CcCost => item.cc_cost,
+                };
+                value.is_some_and(|value| comparison.compare(value, *number))
+            }
+            Condition::Gathered => item.is_gathered(),
+            Condition::Synthesized => !item.is_gathered(),
+            Condition::Equipment => item.equipment.is_some(),
+        }
+    }
+}
+
+impl Comparison {
+    fn compare(self, value: i64, number: i64) -> bool {
+        match self {
+            Comparison::Equal => value == number,
+            Comparison::Less => value < number,
+            Comparison::LessOrEqual => value <= number,
+            Comparison::Greater => value > number,
+            Comparison::GreaterOrEqual => value >= number,
+        }
+    }
+}
+
+/// split a query into terms at spaces outside double quotes, dropping the quotes
+fn tokens(text: &str) -> Result<Vec<String>, QueryError> {
+    let mut tokens = Vec::new();
+    let mut token = String::new();
+    let mut quoted = false;
+    for c in text.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !token.is_empty() {
+                    tokens.push(std::mem::take(&mut token));
+                }
+            }
+            c => token.push(c),
+        }
+    }
+    if quoted {
+        return Err(QueryError::UnclosedQuote);
+    }
+    if !token.is_empty() {
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+fn term(token: &str, sets: &ItemValidationSets, items: &[Item]) -> Result<Term, QueryError> {
+    let (negated, token) = match token.strip_prefix('-') {
+        Some(rest) if !rest.is_empty() => (true, rest),
+        _ => (false, token),
+    };
+    let field_end = token
+        .find(|c: char| !c.is_ascii_alphabetic())
+        .unwrap_or(token.len());
+    let (field, rest) = token.split_at(field_end);
+    let field = field.to_lowercase();
+
+    let condition = if rest.is_empty() {
+        match field.as_str() {
+            "gathered" => Condition::Gathered,
+            "synthesized" => Condition::Synthesized,
+            "equipment" => Condition::Equipment,
+            _ => Condition::Name(token.to_lowercase()),
+        }
+    } else if let Some(value) = rest.strip_prefix(':') {
+        field_condition(&field, value, sets, items)?
+    } else if let Some((comparison, value)) = comparison(rest) {
+        let number_field =
+            number_field(&field).ok_or_else(|| QueryError::NotANumberField(field.clone()))?;
+        Condition::Number(number_field, comparison, number(value)?)
+    } else {
+        // a word with punctuation in it, like `S-Rank`
+        Condition::Name(token.to_lowercase())
+    };
+    Ok(Term { negated, condition })
+}
+
+fn field_condition(
+    field: &str,
+    value: &str,
+    sets: &ItemValidationSets,
+    items: &[Item],
+) -> Result<Condition, QueryError> {
+    let known = |set: &HashSet<String>, value: &str| {
+        set.iter()
+            .find(|known| known.eq_ignore_ascii_case(value))
+            .cloned()
+            .ok_or_else(|| QueryError::UnknownValue(field.to_string(), value.to_string()))
+    };
+    Ok(match field {
+        "category" => Condition::Category(known(&sets.categories, &category(value))?),
+        "element" => Condition::Element(known(&sets.elements, value)?),
+        "classification" => Condition::Classification(known(&sets.classifications, value)?),
+        "tool" => Condition::Tool(known(&sets.gathering_tools, value)?),
+        "character" => Condition::Character(known(&sets.characters, value)?),
+        "material" => {
+            let material = known(&sets.materials, value)
+                .or_else(|_| known(&sets.materials, &category(value)))?;
+            // item files refer to items by their English name once references are resolved
+            Condition::Material(
+                items
+                    .iter()
+                    .find(|item| item.is_named(&material))
+                    .map_or(material, |item| item.name.clone()),
+            )
+        }
+        "location" => Condition::Location(value.to_string()),
+        "name" => Condition::Name(value.to_lowercase()),
+        _ => match number_field(field) {
+            Some(number_field) => {
+                Condition::Number(number_field, Comparison::Equal, number(value)?)
+            }
+            None => return Err(QueryError::UnknownField(field.to_string())),
+        },
+    })
+}
+
+/// a category written with its parentheses, as it is in item files
+fn category(value: &str) -> String {
+    match model::parse_material(value) {
+        (MaterialKind::Category, _) => value.to_string(),
+        (MaterialKind::Item, name) => format!("({})", name),
+    }
+}
+
+fn number_field(field: &str) -> Option<NumberField> {
+    match field {
+        "level" => Some(NumberField::Level),
+        "number" => Some(NumberField::ItemNumber),
+        "cc" => Some(NumberField::CcCost),
+        _ => None,
+    }
+}
+
+/// the comparison at the start of the text, and the rest of the text
+fn comparison(text: &str) -> Option<(Comparison, &str)> {
+    [
+        ("<=", Comparison::LessOrEqual),
+        (">=", Comparison::GreaterOrEqual),
+        ("<", Comparison::Less),
+        (">", Comparison::Greater),
+        ("=", Comparison::Equal),
+    ]
+    .iter()
+    .find_map(|(operator, comparison)| text.strip_prefix(operator).map(|rest| (*comparison, rest)))
+}
+
+fn number(value: &str) -> Result<i64, QueryError> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| QueryError::BadNumber(value.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validation_sets;
+
+    const LISTS: &str = "
+Item Categories: [(Supplement), (Bomb), (Uni), (Flower), (Water)]
+Item Classifications: [Synthesis Item]
+Elements: [Fire, Ice]
+Gathering Tools: [Staff, Bomb Rod]
+Characters: [Ryza]
+";
+
+    const ITEMS: [&str; 3] = [
+        "
+Name:
+  en: Red Supplement
+  ja: 赤の添加剤
+ID: red-supplement
+Item Number: 59
+Level: 1
+Category: [(Supplement)]
+Element: [Fire]
+Materials: [(Flower), (Water)]
+Synthesis:
+  Required Materials: 2
+",
+        "
+Name: Explosive Uni
+Item Number: 1
+Level: 2
+CC Cost: 3
+Category: [(Bomb)]
+Element: [Fire, Ice]
+Materials: [(Uni), 赤の添加剤]
+Synthesis:
+  Material Loops:
+  - Effect 1:
+      Material: red-supplement
+",
+        "
+Name: Uni
+Item Number: 3
+Level: 1
+Category: [(Uni)]
+Element: [Ice]
+Gather Locations: [Little Adventure Island]
+Gathering Tools: [Staff]
+",
+    ];
+
+    fn items() -> Vec<Item> {
+        let mut items: Vec<Item> = ITEMS
+            .iter()
+            .map(|contents| model::load_item(contents).unwrap())
+            .collect();
+        model::resolve_references(&mut items);
+        items
+    }
+
+    fn sets(items: &[Item]) -> ItemValidationSets {
+        let mut sets = validation_sets::build_item_validation_sets(LISTS).unwrap();
+        for item in items {
+            sets.materials.extend(item.reference_names());
+        }
+        sets
+    }
+
+    /// the names of the items matching the query
+    fn names(query: &str) -> Vec<String> {
+        let items = items();
+        let query = Query::parse(query, &sets(&items), &items).unwrap();
+        query
+            .filter(&items)
+            .iter()
+            .map(|item| item.name.clone())
+            .collect()
+    }
+
+    fn error(query: &str) -> QueryError {
+        let items = items();
+        match Query::parse(query, &sets(&items), &items) {
+            Ok(_) => panic!("{} parsed", query),
+            Err(error) => error,
+        }
+    }
+
+    #[test]
+    fn every_term_has_to_match() {
+        assert_eq!(names("element:Fire"), ["Red Supplement", "Explosive Uni"]);
+        assert_eq!(names("element:Fire element:Ice"), ["Explosive Uni"]);
+        assert!(names("element:Fire gathered").is_empty());
+        assert_eq!(names(""), ["Red Supplement", "Explosive Uni", "Uni"]);
+    }
+
+    #[test]
+    fn negation_applies_to_its_own_term() {
+        assert_eq!(names("-element:Fire"), ["Uni"]);
+        assert_eq!(names("element:Ice -gathered"), ["Explosive Uni"]);
+        // a lone dash is a word, not a negation
+        assert!(names("-").is_empty());
+    }
+
+    #[test]
+    fn longer_operators_are_read_first() {
+        assert_eq!(names("level<=1"), ["Red Supplement", "Uni"]);
+        assert!(names("level<1").is_empty());
+        assert_eq!(names("level>=2"), ["Explosive Uni"]);
+        assert_eq!(names("level>1"), ["Explosive Uni"]);
+        assert_eq!(names("level=2"), ["Explosive Uni"]);
+        assert_eq!(names("level:2"), ["Explosive Uni"]);
+    }
+
+    #[test]
+    fn numeric_comparisons() {
+        assert_eq!(names("number<10"), ["Explosive Uni", "Uni"]);
+        assert_eq!(names("number:59"), ["Red Supplement"]);
+        // items without a CC Cost match no comparison on it
+        assert_eq!(names("cc<=3"), ["Explosive Uni"]);
+        assert_eq!(names("-cc>0"), ["Red Supplement", "Uni"]);
+        assert!(matches!(error("level<ten"), QueryError::BadNumber(_)));
+        assert!(matches!(error("element<2"), QueryError::NotANumberField(_)));
+    }
+
+    #[test]
+    fn quoted_values_keep_their_spaces() {
+        assert_eq!(names("tool:Staff"), ["Uni"]);
+        assert_eq!(names("location:\"little adventure island\""), ["Uni"]);
+        assert_eq!(names("\"red supplement\""), ["Red Supplement"]);
+        assert!(names("tool:\"Bomb Rod\"").is_empty());
+        assert!(matches!(
+            error("tool:\"Bomb Rod"),
+            QueryError::UnclosedQuote
+        ));
+    }
+
+    #[test]
+    fn values_are_checked_against_the_lists() {
+        assert_eq!(names("category:bomb"), ["Explosive Uni"]);
+        assert_eq!(names("category:(Bomb)"), ["Explosive Uni"]);
+        assert_eq!(names("material:flower"), ["Red Supplement"]);
+        assert!(matches!(
+            error("element:Lightning"),
+            QueryError::UnknownValue(..)
+        ));
+        assert!(matches!(
+            error("material:\"Blue Supplement\""),
+            QueryError::UnknownValue(..)
+        ));
+    }
+
+    #[test]
+    fn unknown_fields() {
+        assert!(
+            matches!(error("colour:red"), QueryError::UnknownField(field) if field == "colour")
+        );
+        // words with punctuation that isn't a field are part of a name
+        assert_eq!(names("uni"), ["Explosive Uni", "Uni"]);
+        assert!(names("s-rank").is_empty());
+    }
+
+    #[test]
+    fn material_by_english_name() {
+        assert_eq!(names("material:\"Red Supplement\""), ["Explosive Uni"]);
+    }
+
+    #[test]
+    fn material_by_id() {
+        assert_eq!(names("material:red-supplement"), ["Explosive Uni"]);
+        assert_eq!(names("material:RED-SUPPLEMENT"), ["Explosive Uni"]);
+    }
+
+    #[test]
+    fn material_by_translated_name() {
+        assert_eq!(names("material:赤の添加剤"), ["Explosive Uni"]);
+    }
+}
@@ -0,0 +1,522 @@
+//! Renaming an item: its Name, its file, and every reference to it in the other item files.
+//!
+//! The typed model decides which files refer to the item and how many times, but the files are
+//! edited line by line rather than written out again, so comments and layout are kept. Each edited
+//! file is read back into the model to check that every reference was rewritten, and nothing is
+//! written unless all of them were.
+//!
+//! Only references by the English name change. References by ID or by a translated name still
+//! point at the item afterwards, since the item keeps its ID: an item without an `ID` key gets
+//! one holding its old ID.
+
+use crate::dataset::Dataset;
+use crate::item_file::{self, numbered_file_name};
+use crate::model::{self, Item};
+
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::{fmt, fmt::Display};
+
+/// the suffix of a file being written, before it replaces the real one. Item files are only
+/// `.yml` and `.yaml` files, so these are never loaded as items.
+const TEMPORARY_SUFFIX: &str = ".renaming";
+
+/// a file the rename changes
+pub struct FileChange {
+    /// the name of the file within the items folder
+    pub file: String,
+    /// the name of the file after the rename, which only differs for the renamed item
+    pub new_file: String,
+    pub contents: String,
+    /// how many references to the item were rewritten
+    pub references: usize,
+}
+
+#[derive(Debug)]
+pub enum RenameError {
+    UnknownItem(String),
+    /// the new name already belongs to an item or a category
+    NameTaken(String),
+    FileExists(String),
+    /// a file refers to the item in a way that can't be rewritten line by line, e.g. in a list
+    /// written as `[a, b]`
+    Unrewritable(String),
+}
+
+impl Display for RenameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RenameError::UnknownItem(name) => write!(f, "no item named {}", name),
+            RenameError::NameTaken(name) => write!(f, "{} is already the name of an item", name),
+            RenameError::FileExists(file) => write!(f, "{} already exists", file),
+            RenameError::Unrewritable(file) => write!(
+                f,
+                "can't rewrite the references in {}, rename them by hand first",
+                file
+            ),
+        }
+    }
+}
+
+/// the changes that rename the item called `old` (by its ID or any of its names) to `new`.
+/// The English name is the one that changes.
+pub fn plan(dataset: &Dataset, old: &str, new: &str) -> Result<Vec<FileChange>, RenameError> {
+    let new = new.trim();
+    let (own_file, item) = dataset
+        .item_contents
+        .iter()
+        .filter_map(|file| Some((file, Item::from_yaml(file.document().ok()?))))
+        .find(|(_, item)| item.is_named(old))
+        .ok_or_else(|| RenameError::UnknownItem(old.to_string()))?;
+    if new.is_empty() || dataset.item_validation_sets.materials.contains(new) {
+        return Err(RenameError::NameTaken(new.to_string()));
+    }
+
+    let new_file = match own_file.name.rfind('/') {
+        Some(index) => format!(
+            "{}/{}",
+            &own_file.name[..index],
+            numbered_file_name(item.item_number, new)
+        ),
+        None => numbered_file_name(item.item_number, new),
+    };
+    if new_file != own_file.name && dataset.file(&new_file).is_some() {
+        return Err(RenameError::FileExists(new_file));
+    }
+
+    let mut changes = Vec::new();
+    for file in &dataset.item_contents {
+        let references = match file.document() {
+            Ok(yaml) => count_references(&Item::from_yaml(yaml), &item.name),
+            Err(_) => continue,
+        };
+        let is_own_file = file.name == own_file.name;
+        if references == 0 && !is_own_file {
+            continue;
+        }
+
+        let own_id = if is_own_file && item.id.is_none() {
+            Some(item.id())
+        } else {
+            None
+        };
+        let contents = rewrite(
+            &file.contents,
+            &item.name,
+            new,
+            is_own_file,
+            own_id.as_deref(),
+        );
+
+        // check the rewritten file with the model, rather than trusting the line edits
+        let rewritten = model::load_item(&contents)
+            .map_err(|_| RenameError::Unrewritable(file.name.clone()))?;
+        let renamed = !is_own_file || (rewritten.name == new && rewritten.id() == item.id());
+        if !renamed || count_references(&rewritten, &item.name) != 0 {
+            return Err(RenameError::Unrewritable(file.name.clone()));
+        }
+
+        changes.push(FileChange {
+            file: file.name.clone(),
+            new_file: if is_own_file {
+                new_file.clone()
+            } else {
+                file.name.clone()
+            },
+            contents,
+            references,
+        });
+    }
+    Ok(changes)
+}
+
+/// write the changes into the items folder. Every file is written beside its destination first,
+/// and they're only moved into place once all of them are written, so a failure to write leaves
+/// the items folder as it was. The renamed item's old file is removed last. If moving a file into
+/// place or removing the old file fails, the files already changed are put back from their old
+/// contents.
+pub fn apply(items_folder: &Path, changes: &[FileChange]) -> io::Result<()> {
+    for change in changes {
+        let path = items_folder.join(&change.new_file);
+        if change.new_file != change.file && path.exists() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                RenameError::FileExists(change.new_file.clone()).to_string(),
+            ));
+        }
+    }
+
+    let mut backups = Vec::new();
+    for change in changes {
+        backups.push(fs::read(items_folder.join(&change.file))?);
+    }
+
+    let mut written = Vec::new();
+    for change in changes {
+        let temporary = items_folder.join(format!("{}{}", change.new_file, TEMPORARY_SUFFIX));
+        if let Err(error) = fs::write(&temporary, &change.contents) {
+            for path in &written {
+                let _ = fs::remove_file(path);
+            }
+            return Err(error);
+        }
+        written.push(temporary);
+    }
+    for (index, (change, temporary)) in changes.iter().zip(&written).enumerate() {
+        if let Err(error) = fs::rename(temporary, items_folder.join(&change.new_file)) {
+            for path in &written[index..] {
+                let _ = fs::remove_file(path);
+            }
+            restore(items_folder, &changes[..index], &backups);
+            return Err(error);
+        }
+    }
+    for change in changes
+        .iter()
+        .filter(|change| change.new_file != change.file)
+    {
+        if let Err(error) = fs::remove_file(items_folder.join(&change.file)) {
+            restore(items_folder, changes, &backups);
+            return Err(error);
+        }
+    }
+    Ok(())
+}
+
+/// put the files of the changes back as they were, from their old contents. This is already
+/// recovering from an error, so failures here are ignored.
+fn restore(items_folder: &Path, changes: &[FileChange], backups: &[Vec<u8>]) {
+    for (change, contents) in changes.iter().zip(backups) {
+        let _ = fs::write(items_folder.join(&change.file), contents);
+        if change.new_file != change.file {
+            let _ = fs::remove_file(items_folder.join(&change.new_file));
+        }
+    }
+}
+
+/// how many times the item refers to the named item
+fn count_references(item: &Item, name: &str) -> usize {
+    item.referenced_items()
+        .iter()
+        .filter(|reference| **reference == name)
+        .count()
+}
+
+/// rewrite the references to `old` in an item file: the entries of its Materials list, and its
+/// loops' `Material` and `Recipe` values. In the renamed item's own file the Name (or its `en`
+/// entry) changes too, and the old ID is added if it's given. Line endings are kept, whether
+/// they're `\n` or `\r\n`.
+fn rewrite(
+    contents: &str,
+    old: &str,
+    new: &str,
+    is_own_file: bool,
+    old_id: Option<&str>,
+) -> String {
+    let mut lines = Vec::new();
+    let mut top_key = "";
+    let mut id_written = old_id.is_none();
+    for line in contents.lines() {
+        let text = line.trim_start();
+        let indent = &line[..line.len() - text.len()];
+        let is_top_key = indent.is_empty() && !text.is_empty() && !text.starts_with(['-', '#']);
+        if is_top_key {
+            // the ID goes straight after the Name
+            if top_key == "Name" && !id_written {
+                lines.push(id_line(old_id));
+                id_written = true;
+            }
+            top_key = text.split(':').next().unwrap_or("");
+        }
+
+        let rewritten = if let Some(value) = text.strip_prefix('-') {
+            if top_key == "Materials" {
+                replace_value(value, old, new).map(|value| format!("{}-{}", indent, value))
+            } else {
+                None
+            }
+        } else if is_top_key && is_own_file && top_key == "Name" {
+            text.strip_prefix("Name:")
+                .and_then(|value| replace_value(value, old, new))
+                .map(|value| format!("Name:{}", value))
+        } else if !indent.is_empty() && is_own_file && top_key == "Name" {
+            text.strip_prefix("en:")
+                .and_then(|value| replace_value(value, old, new))
+                .map(|value| format!("{}en:{}", indent, value))
+        } else {
+            ["Material:", "Recipe:"].iter().find_map(|key| {
+                text.strip_prefix(key)
+                    .and_then(|value| replace_value(value, old, new))
+                    .map(|value| format!("{}{}{}", indent, key, value))
+            })
+        };
+        lines.push(rewritten.unwrap_or_else(|| line.to_string()));
+    }
+    if !id_written {
+        lines.push(id_line(old_id));
+    }
+
+    let line_ending = if contents.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+    let mut rewritten = lines.join(line_ending);
+    if contents.ends_with('\n') {
+        rewritten.push_str(line_ending);
+    }
+    rewritten
+}
+
+fn id_line(id: Option<&str>) -> String {
+    format!("ID: {}", id.unwrap_or_default())
+}
+
+/// replace a value holding `old` with `new`, keeping the spacing and any comment around it.
+/// `text` is what follows the key or list dash. Returns None if the value is something else.
+fn replace_value(text: &str, old: &str, new: &str) -> Option<String> {
+    let value = text.trim_start();
+    let leading = &text[..text.len() - value.len()];
+    let (written, trailing) = value.split_at(value_end(value));
+    let spacing = &written[written.trim_end().len()..];
+    if leading.is_empty() || unquote(written.trim_end()) != old {
+        return None;
+    }
+    Some(format!(
+        "{}{}{}{}",
+        leading,
+        item_file::scalar(new),
+        spacing,
+        trailing
+    ))
+}
+
+/// where a scalar value ends: after its closing quote, or before its comment
+fn value_end(value: &str) -> usize {
+    let quote = match value.chars().next() {
+        Some(quote) if quote == '\'' || quote == '"' => quote,
+        _ => return value.find(" #").unwrap_or(value.len()),
+    };
+    let mut chars = value.char_indices().skip(1).peekable();
+    while let Some((index, c)) = chars.next() {
+        if quote == '"' && c == '\\' {
+            chars.next();
+        } else if c == quote {
+            // '' inside single quotes is a quote, not the end of the value
+            if quote == '\'' && chars.peek().map(|(_, c)| *c) == Some('\'') {
+                chars.next();
+            } else {
+                return index + 1;
+            }
+        }
+    }
+    value.len()
+}
+
+fn unquote(value: &str) -> String {
+    if value.len() >= 2 && value.starts_with('\'') && value.ends_with('\'') {
+        value[1..value.len() - 1].replace("''", "'")
+    } else if value.len() >= 2 && value.starts_with('"') && value.ends_with('"') {
+        value[1..value.len() - 1].replace("\\\"", "\"")
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    const RED_SUPPLEMENT: &str = "\
+Name: Red Supplement
+Item Number: 59
+Category:
+- (Supplement)
+";
+
+    const EXPLOSIVE_UNI: &str = "\
+Name: Explosive Uni
+Item Number: 1
+Materials:
+- (Uni)
+- Red Supplement # the red one
+Synthesis:
+  Material Loops:
+  - Effect 1:
+      Material: 'Red Supplement'
+      Levels:
+      - Recipe Morph:
+          Recipe: \"Red Supplement\"
+  - Effect 2:
+      Material: (Uni)
+";
+
+    const UNI: &str = "\
+Name: Uni
+Item Number: 3
+";
+
+    /// a data folder in the temporary folder holding the given item files, removed when dropped
+    struct DataFolder(PathBuf);
+
+    impl DataFolder {
+        fn new(test: &str, items: &[(&str, &str)]) -> DataFolder {
+            let path = std::env::temp_dir().join(format!(
+                "item-validator-rename-{}-{}",
+                test,
+                std::process::id()
+            ));
+            let _ = fs::remove_dir_all(&path);
+            fs::create_dir_all(path.join("items")).unwrap();
+            fs::write(
+                path.join("lists.yml"),
+                "Item Categories: [(Supplement), (Uni)]\n",
+            )
+            .unwrap();
+            fs::write(path.join("rules.yml"), "Rules: []\n").unwrap();
+            for (name, contents) in items {
+                fs::write(path.join("items").join(name), contents).unwrap();
+            }
+            DataFolder(path)
+        }
+
+        fn dataset(&self) -> Dataset {
+            Dataset::load(&self.0.to_string_lossy()).unwrap()
+        }
+
+        fn item_file(&self, name: &str) -> Option<String> {
+            fs::read_to_string(self.0.join("items").join(name)).ok()
+        }
+    }
+
+    impl Drop for DataFolder {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn data_folder(test: &str) -> DataFolder {
+        DataFolder::new(
+            test,
+            &[
+                ("059-red-supplement.yml", RED_SUPPLEMENT),
+                ("001-explosive-uni.yml", EXPLOSIVE_UNI),
+                ("003-uni.yml", UNI),
+            ],
+        )
+    }
+
+    #[test]
+    fn rewrites_materials_loops_and_recipe_morphs() {
+        let folder = data_folder("references");
+        let changes = plan(&folder.dataset(), "Red Supplement", "Crimson Supplement").unwrap();
+        let uni = changes
+            .iter()
+            .find(|change| change.file == "001-explosive-uni.yml")
+            .unwrap();
+        assert_eq!(uni.references, 3);
+        assert_eq!(uni.new_file, uni.file);
+        assert_eq!(
+            uni.contents,
+            EXPLOSIVE_UNI
+                .replace("- Red Supplement #", "- Crimson Supplement #")
+                .replace("'Red Supplement'", "Crimson Supplement")
+                .replace("\"Red Supplement\"", "Crimson Supplement")
+        );
+        // the other item doesn't refer to it
+        assert!(!changes.iter().any(|change| change.file == "003-uni.yml"));
+    }
+
+    #[test]
+    fn renames_the_item_and_keeps_its_id() {
+        let folder = data_folder("own-file");
+        let dataset = folder.dataset();
+        let changes = plan(&dataset, "red-supplement", "Crimson Supplement").unwrap();
+        let own = changes
+            .iter()
+            .find(|change| change.file == "059-red-supplement.yml")
+            .unwrap();
+        assert_eq!(own.new_file, "059-crimson-supplement.yml");
+        assert_eq!(
+            own.contents,
+            RED_SUPPLEMENT.replace(
+                "Name: Red Supplement\n",
+                "Name: Crimson Supplement\nID: red-supplement\n"
+            )
+        );
+
+        apply(&folder.0.join("items"), &changes).unwrap();
+        assert!(folder.item_file("059-red-supplement.yml").is_none());
+        assert_eq!(
+            folder.item_file("059-crimson-supplement.yml").as_ref(),
+            Some(&own.contents)
+        );
+        let items = folder.dataset().items();
+        assert!(items
+            .iter()
+            .any(|item| item.name == "Crimson Supplement" && item.id() == "red-supplement"));
+    }
+
+    #[test]
+    fn keeps_crlf_line_endings() {
+        let contents = EXPLOSIVE_UNI.replace('\n', "\r\n");
+        let rewritten = rewrite(
+            &contents,
+            "Red Supplement",
+            "Crimson Supplement",
+            false,
+            None,
+        );
+        assert_eq!(
+            rewritten.matches("\r\n").count(),
+            rewritten.matches('\n').count()
+        );
+        assert_eq!(rewritten.matches("Crimson Supplement").count(), 3);
+        assert!(rewritten.ends_with("Material: (Uni)\r\n"));
+
+        let own = rewrite(
+            &RED_SUPPLEMENT.replace('\n', "\r\n"),
+            "Red Supplement",
+            "Crimson Supplement",
+            true,
+            Some("red-supplement"),
+        );
+        assert!(own.starts_with("Name: Crimson Supplement\r\nID: red-supplement\r\n"));
+    }
+
+    #[test]
+    fn refuses_a_name_that_is_taken() {
+        let folder = data_folder("taken");
+        let dataset = folder.dataset();
+        for taken in ["Uni", "(Uni)", "explosive-uni", ""] {
+            assert!(matches!(
+                plan(&dataset, "Red Supplement", taken),
+                Err(RenameError::NameTaken(_))
+            ));
+        }
+        assert!(matches!(
+            plan(&dataset, "Blue Supplement", "Crimson Supplement"),
+            Err(RenameError::UnknownItem(_))
+        ));
+    }
+
+    #[test]
+    fn refuses_lists_it_cannot_rewrite() {
+        let folder = DataFolder::new(
+            "unrewritable",
+            &[
+                ("059-red-supplement.yml", RED_SUPPLEMENT),
+                (
+                    "001-explosive-uni.yml",
+                    "Name: Explosive Uni\nMaterials: [(Uni), Red Supplement]\n",
+                ),
+            ],
+        );
+        assert!(matches!(
+            plan(&folder.dataset(), "Red Supplement", "Crimson Supplement"),
+            Err(RenameError::Unrewritable(file)) if file == "001-explosive-uni.yml"
+        ));
+    }
+}
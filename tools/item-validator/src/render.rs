@@ -0,0 +1,362 @@
+//! Drawing the material loops of an item as an SVG node graph, like the synthesis screen.
+//!
+//! Loops are laid out in columns by `Distance`, with each column ordered so loops sit next to the
+//! loop they're `Linked From`. Each loop is colored by the element its levels need, lists its
+//! levels with their element thresholds, and locked loops have dashed links and show their
+//! `Unlock` values.
+
+use crate::model::{ElementValue, MaterialLoop, Synthesis};
+
+const COLUMN_WIDTH: i64 = 260;
+const LINE_HEIGHT: i64 = 14;
+const MARGIN: i64 = 50;
+const RADIUS: i64 = 26;
+const LEGEND_HEIGHT: i64 = 40;
+
+/// the colors of the elements, as used on the synthesis screen
+pub const ELEMENT_COLORS: [(&str, &str); 4] = [
+    ("Fire", "#e5553b"),
+    ("Ice", "#4a9fe0"),
+    ("Lightning", "#e3bd1e"),
+    ("Wind", "#4fb45a"),
+];
+const NO_ELEMENT_COLOR: &str = "#bbbbbb";
+
+/// the color of an element, grey for unknown elements
+pub fn element_color(element: &str) -> &'static str {
+    ELEMENT_COLORS
+        .iter()
+        .find(|(name, _)| *name == element)
+        .map(|(_, color)| *color)
+        .unwrap_or(NO_ELEMENT_COLOR)
+}
+
+/// the element a loop's levels ask for, which sets its color
+fn loop_element(material_loop: &MaterialLoop) -> Option<&str> {
+    material_loop
+        .levels
+        .iter()
+        .flat_map(|level| level.elements.first())
+        .map(|element| element.element.as_str())
+        .next()
+}
+
+/// where each loop is drawn
+struct Node<'a> {
+    material_loop: &'a MaterialLoop,
+    x: i64,
+    y: i64,
+}
+
+struct Layout<'a> {
+    nodes: Vec<Node<'a>>,
+    width: i64,
+    height: i64,
+}
+
+impl<'a> Layout<'a> {
+    fn new(synthesis: &'a Synthesis) -> Layout<'a> {
+        let mut nodes: Vec<Node> = Vec::new();
+        let max_distance = synthesis
+            .material_loops
+            .iter()
+            .map(|material_loop| material_loop.distance.max(0))
+            .max()
+            .unwrap_or(0);
+
+        let mut height = 0;
+        for distance in 0..=max_distance {
+            let mut column: Vec<&MaterialLoop> = synthesis
+                .material_loops
+                .iter()
+                .filter(|material_loop| material_loop.distance.max(0) == distance)
+                .collect();
+            // keep loops next to the loop they're linked from
+            column.sort_by_key(|material_loop| {
+                let parent_y = material_loop
+                    .linked_from_position
+                    .and_then(|position| node_at(&nodes, position))
+                    .map(|node| node.y)
+                    .unwrap_or(0);
+                (parent_y, material_loop.position)
+            });
+
+            let mut y = MARGIN;
+            for material_loop in column {
+                let x = MARGIN + distance * COLUMN_WIDTH;
+                nodes.push(Node {
+                    material_loop,
+                    x,
+                    y: y + RADIUS,
+                });
+                y += node_height(material_loop);
+            }
+            height = height.max(y);
+        }
+
+        Layout {
+            nodes,
+            width: MARGIN * 2 + max_distance * COLUMN_WIDTH + COLUMN_WIDTH - RADIUS,
+            height: height + MARGIN + LEGEND_HEIGHT,
+        }
+    }
+}
+
+fn node_at<'a, 'b>(nodes: &'b [Node<'a>], position: i64) -> Option<&'b Node<'a>> {
+    nodes
+        .iter()
+        .find(|node| node.material_loop.position == position)
+}
+
+/// the room a loop needs: its circle and labels, or its list of levels if that's longer
+fn node_height(material_loop: &MaterialLoop) -> i64 {
+    let labels = if material_loop.unlock.is_empty() {
+        2
+    } else {
+        3
+    };
+    let circle = RADIUS * 2 + LINE_HEIGHT * labels;
+    let levels = (material_loop.levels.len() as i64 + 1) * LINE_HEIGHT;
+    circle.max(levels) + LINE_HEIGHT
+}
+
+fn elements_text(elements: &[ElementValue]) -> String {
+    let elements: Vec<String> = elements
+        .iter()
+        .map(|element| match element.value {
+            Some(value) => format!("{} {}", element.element, value),
+            None => element.element.clone(),
+        })
+        .collect();
+    elements.join(", ")
+}
+
+/// an SVG document showing the loop tree of a synthesis
+pub fn loop_tree_svg(synthesis: &Synthesis) -> String {
+    let layout = Layout::new(synthesis);
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" viewBox=\"0 0 {0} {1}\" font-family=\"sans-serif\" font-size=\"11\">\n",
+        layout.width, layout.height
+    );
+    // a white halo keeps text readable where links cross it
+    svg.push_str("  <style>text { stroke: white; stroke-width: 3px; stroke-linejoin: round; paint-order: stroke; }</style>\n");
+
+    // links first, so the nodes are drawn over them
+    for node in &layout.nodes {
+        let material_loop = node.material_loop;
+        let from = match material_loop
+            .linked_from_position
+            .and_then(|position| node_at(&layout.nodes, position))
+        {
+            Some(from) => from,
+            None => continue,
+        };
+        let locked = !material_loop.unlock.is_empty();
+        svg.push_str(&format!(
+            "  <line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"#888\" stroke-width=\"2\"{}/>\n",
+            from.x,
+            from.y,
+            node.x,
+            node.y,
+            if locked { " stroke-dasharray=\"6 4\"" } else { "" }
+        ));
+    }
+
+    for node in &layout.nodes {
+        let material_loop = node.material_loop;
+        let color = loop_element(material_loop)
+            .map(element_color)
+            .unwrap_or(NO_ELEMENT_COLOR);
+        svg.push_str(&format!(
+            "  <circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"{}\" stroke=\"#333\" stroke-width=\"2\"/>\n",
+            node.x, node.y, RADIUS, color
+        ));
+        svg.push_str(&format!(
+            "  <text x=\"{}\" y=\"{}\" text-anchor=\"middle\" font-weight=\"bold\">{}</text>\n",
+            node.x,
+            node.y + 4,
+            material_loop.position
+        ));
+        svg.push_str(&format!(
+            "  <text x=\"{}\" y=\"{}\" text-anchor=\"middle\">{}</text>\n",
+            node.x,
+            node.y + RADIUS + LINE_HEIGHT,
+            escape(&material_loop.name)
+        ));
+        svg.push_str(&format!(
+            "  <text x=\"{}\" y=\"{}\" text-anchor=\"middle\" fill=\"#555\">{}</text>\n",
+            node.x,
+            node.y + RADIUS + LINE_HEIGHT * 2,
+            escape(&material_loop.material)
+        ));
+        if !material_loop.unlock.is_empty() {
+            svg.push_str(&format!(
+                "  <text x=\"{}\" y=\"{}\" text-anchor=\"middle\" font-style=\"italic\">Unlock: {}</text>\n",
+                node.x,
+                node.y + RADIUS + LINE_HEIGHT * 3,
+                escape(&elements_text(&material_loop.unlock))
+            ));
+        }
+
+        // the levels, with the element values they need
+        let text_x = node.x + RADIUS + 8;
+        let mut text_y = node.y - RADIUS + LINE_HEIGHT;
+        for level in &material_loop.levels {
+            let effect = match &level.recipe {
+                Some(recipe) => format!("{} \u{2192} {}", level.effect, recipe),
+                None => level.effect.clone(),
+            };
+            let threshold_color = level
+                .elements
+                .first()
+                .map(|element| element_color(&element.element))
+                .unwrap_or(NO_ELEMENT_COLOR);
+            svg.push_str(&format!(
+                "  <text x=\"{}\" y=\"{}\"><tspan fill=\"{}\" font-weight=\"bold\">{}</tspan> {}</text>\n",
+                text_x,
+                text_y,
+                threshold_color,
+                escape(&elements_text(&level.elements)),
+                escape(&effect)
+            ));
+            text_y += LINE_HEIGHT;
+        }
+    }
+
+    // a legend of the element colors
+    let legend_y = layout.height - LEGEND_HEIGHT / 2;
+    for (index, (element, color)) in ELEMENT_COLORS.iter().enumerate() {
+        let x = MARGIN + index as i64 * 90;
+        svg.push_str(&format!(
+            "  <circle cx=\"{}\" cy=\"{}\" r=\"6\" fill=\"{}\"/>\n  <text x=\"{}\" y=\"{}\">{}</text>\n",
+            x,
+            legend_y,
+            color,
+            x + 10,
+            legend_y + 4,
+            element
+        ));
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// escape text for use in SVG (or HTML)
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model;
+
+    const ITEM: &str = "
+Name: Explosive Uni
+Item Number: 1
+Level: 2
+Synthesis:
+  Required Materials: 3
+  Required Alchemy Level: 1
+  Material Loops:
+  - Effect 1:
+      Distance: 0
+      Position: 0
+      Material: (Uni)
+      Levels:
+      - Scatter:
+          Element:
+          - Fire: 2
+  - Effect 2:
+      Distance: 1
+      Position: 2
+      Linked From Position: 0
+      Material: Red & Blue
+      Levels:
+      - Power <1>:
+          Element:
+          - Ice: 3
+      Unlock:
+      - Fire: 4
+  - Recipe:
+      Distance: 1
+      Position: 1
+      Linked From Position: 0
+      Material: (Gunpowder)
+      Levels:
+      - Recipe Morph:
+          Recipe: Craft
+          Element:
+          - Wind
+";
+
+    fn svg() -> String {
+        let item = model::load_item(ITEM).unwrap();
+        loop_tree_svg(item.synthesis.as_ref().unwrap())
+    }
+
+    #[test]
+    fn loops_are_drawn_in_columns_by_distance() {
+        let svg = svg();
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.ends_with("</svg>\n"));
+        // one circle per loop, then one per element in the legend
+        assert_eq!(svg.matches("r=\"26\"").count(), 3);
+        assert_eq!(svg.matches("r=\"6\"").count(), ELEMENT_COLORS.len());
+
+        // the loop at Distance 0 starts the first column, and the loops it links to start the
+        // second, in order of position
+        let position_label = |position: i64, x: i64, y: i64| {
+            format!(
+                "<text x=\"{}\" y=\"{}\" text-anchor=\"middle\" font-weight=\"bold\">{}</text>",
+                x,
+                y + 4,
+                position
+            )
+        };
+        let top = MARGIN + RADIUS;
+        let second_column = MARGIN + COLUMN_WIDTH;
+        assert!(svg.contains(&position_label(0, MARGIN, top)));
+        assert!(svg.contains(&position_label(1, second_column, top)));
+        let below_1 = top
+            + node_height(
+                &model::load_item(ITEM)
+                    .unwrap()
+                    .synthesis
+                    .unwrap()
+                    .material_loops[2],
+            );
+        assert!(svg.contains(&position_label(2, second_column, below_1)));
+    }
+
+    #[test]
+    fn locked_loops_have_dashed_links_and_unlock_values() {
+        let svg = svg();
+        assert_eq!(svg.matches("<line ").count(), 2);
+        assert_eq!(svg.matches("stroke-dasharray").count(), 1);
+        assert!(svg.contains(">Unlock: Fire 4</text>"));
+    }
+
+    #[test]
+    fn levels_show_their_thresholds_colors_and_recipes() {
+        let svg = svg();
+        assert!(svg.contains(&format!(
+            "<tspan fill=\"{}\" font-weight=\"bold\">Fire 2</tspan> Scatter</text>",
+            element_color("Fire")
+        )));
+        assert!(svg.contains("Wind</tspan> Recipe Morph \u{2192} Craft</text>"));
+        // names and effects are escaped
+        assert!(svg.contains(">Red &amp; Blue</text>"));
+        assert!(svg.contains("Power &lt;1&gt;</text>"));
+    }
+
+    #[test]
+    fn unknown_elements_are_grey() {
+        assert_eq!(element_color("Ice"), "#4a9fe0");
+        assert_eq!(element_color("Light"), NO_ELEMENT_COLOR);
+        assert_eq!(escape("\"A\" & <B>"), "&quot;A&quot; &amp; &lt;B&gt;");
+    }
+}
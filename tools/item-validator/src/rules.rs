@@ -0,0 +1,242 @@
+extern crate yaml_rust;
+use crate::validate_item::ValidationResults;
+use std::fmt;
+use yaml_rust::{ScanError, Yaml, YamlLoader};
+
+const RULE_KEYS: [&str; 4] = [
+    "When Classification",
+    "Unless Classification",
+    "Requires",
+    "Forbids",
+];
+
+/// a conditional requirement from rules.yml, e.g. "Battle Item requires CC Cost"
+pub struct Rule {
+    pub when_classification: Option<String>,
+    pub unless_classification: Option<String>,
+    pub requires: Vec<String>,
+    pub forbids: Vec<String>,
+}
+
+pub struct ItemRules {
+    pub rules: Vec<Rule>,
+}
+
+impl Rule {
+    /// a rule applies when the item has the `When` classification (if any), and doesn't have
+    /// the `Unless` classification (if any)
+    fn applies_to(&self, yaml: &Yaml) -> bool {
+        let when = match &self.when_classification {
+            Some(classification) => has_classification(yaml, classification),
+            None => true,
+        };
+        let unless = match &self.unless_classification {
+            Some(classification) => has_classification(yaml, classification),
+            None => false,
+        };
+        when && !unless
+    }
+
+    /// describes which items the rule applies to, for validation messages
+    fn describe(&self) -> String {
+        match (&self.when_classification, &self.unless_classification) {
+            (Some(when), Some(unless)) => format!("{} items not classified as {}", when, unless),
+            (Some(when), None) => format!("{} items", when),
+            (None, Some(unless)) => format!("items not classified as {}", unless),
+            (None, None) => "all items".to_string(),
+        }
+    }
+}
+
+impl ItemRules {
+    /// check the item against every rule that applies to it
+    pub fn validate(&self, yaml: &Yaml) -> ValidationResults {
+        let mut results = ValidationResults::new();
+
+        for rule in self.rules.iter().filter(|rule| rule.applies_to(yaml)) {
+            for key in &rule.requires {
+                if let Yaml::BadValue = yaml[key.as_str()] {
+                    results.valid = false;
+                    results.fail_messages.push(format!(
+                        "'{}' key is missing (required for {})",
+                        key,
+                        rule.describe()
+                    ));
+                }
+            }
+            for key in &rule.forbids {
+                if let Yaml::BadValue = yaml[key.as_str()] {
+                    results
+                        .pass_messages
+                        .push(format!("{} is absent, as expected", key));
+                } else {
+                    results.valid = false;
+                    results.fail_messages.push(format!(
+                        "'{}' key is not allowed for {}",
+                        key,
+                        rule.describe()
+                    ));
+                }
+            }
+        }
+        results
+    }
+}
+
+#[derive(Debug)]
+pub enum RulesError {
+    Yaml(ScanError),
+    /// a key that isn't one of `RULE_KEYS` (e.g. a misspelled `Requires`), with the rule it's in
+    UnknownKey(String, String),
+}
+
+impl fmt::Display for RulesError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RulesError::Yaml(error) => write!(f, "{}", error),
+            RulesError::UnknownKey(rule, key) => {
+                write!(f, "unknown key '{}' in the rule for {}", key, rule)
+            }
+        }
+    }
+}
+
+impl From<ScanError> for RulesError {
+    fn from(error: ScanError) -> Self {
+        RulesError::Yaml(error)
+    }
+}
+
+pub fn build_item_rules(contents: &str) -> Result<ItemRules, RulesError> {
+    let docs = YamlLoader::load_from_str(contents)?;
+    // YAML files can actually contain multiple files inside, we want the first one (if any)
+    let yaml = docs.first().unwrap_or(&Yaml::BadValue);
+
+    let mut rules = Vec::new();
+    if let Yaml::Array(list) = &yaml["Rules"] {
+        for rule in list {
+            let item_rule = Rule {
+                when_classification: rule["When Classification"].as_str().map(String::from),
+                unless_classification: rule["Unless Classification"].as_str().map(String::from),
+                requires: string_list(&rule["Requires"]),
+                forbids: string_list(&rule["Forbids"]),
+            };
+            // a misspelled key would quietly drop a requirement, so it's an error
+            if let Yaml::Hash(hash) = rule {
+                for key in hash.keys() {
+                    let key = key.as_str().unwrap_or_default();
+                    if !RULE_KEYS.contains(&key) {
+                        return Err(RulesError::UnknownKey(
+                            item_rule.describe(),
+                            key.to_string(),
+                        ));
+                    }
+                }
+            }
+            rules.push(item_rule);
+        }
+    }
+    Ok(ItemRules { rules })
+}
+
+fn string_list(yaml: &Yaml) -> Vec<String> {
+    if let Yaml::Array(list) = yaml {
+        list.iter()
+            .filter_map(|value| value.as_str())
+            .map(String::from)
+            .collect()
+    } else {
+        Vec::new()
+    }
+}
+
+/// true if the item lists the given value under its Classifications
+pub fn has_classification(yaml: &Yaml, classification: &str) -> bool {
+    if let Yaml::Array(list) = &yaml["Classifications"] {
+        list.iter()
+            .filter_map(|value| value.as_str())
+            .any(|value| value == classification)
+    } else {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RULES: &str = "
+Rules:
+- When Classification: Battle Item
+  Requires:
+  - CC Cost
+- When Classification: Materials
+  Requires:
+  - Gather Locations
+  Forbids:
+  - Synthesis
+- Unless Classification: Materials
+  Requires:
+  - Synthesis
+";
+
+    /// the fail messages for an item with the given classifications and other keys
+    fn fail_messages(classifications: &str, keys: &str) -> Vec<String> {
+        let rules = build_item_rules(RULES).unwrap();
+        let contents = format!(
+            "Name: Uni\nClassifications: [{}]\n{}",
+            classifications, keys
+        );
+        let yaml = &YamlLoader::load_from_str(&contents).unwrap()[0];
+        let results = rules.validate(yaml);
+        assert_eq!(results.valid, results.fail_messages.is_empty());
+        results.fail_messages
+    }
+
+    #[test]
+    fn when_rules_apply_only_to_their_classification() {
+        assert_eq!(
+            fail_messages("Battle Item", "Synthesis: {}\n"),
+            ["'CC Cost' key is missing (required for Battle Item items)"]
+        );
+        assert!(fail_messages("Battle Item", "Synthesis: {}\nCC Cost: 2\n").is_empty());
+        assert!(fail_messages("Attack Items", "Synthesis: {}\n").is_empty());
+    }
+
+    #[test]
+    fn unless_rules_apply_to_everything_else() {
+        assert_eq!(
+            fail_messages("Attack Items", ""),
+            ["'Synthesis' key is missing (required for items not classified as Materials)"]
+        );
+        assert!(fail_messages("Materials", "Gather Locations: [Field]\n").is_empty());
+    }
+
+    #[test]
+    fn forbidden_keys_fail() {
+        assert_eq!(
+            fail_messages("Materials", "Gather Locations: [Field]\nSynthesis: {}\n"),
+            ["'Synthesis' key is not allowed for Materials items"]
+        );
+        assert_eq!(
+            fail_messages("Materials", "Synthesis: {}\n"),
+            [
+                "'Gather Locations' key is missing (required for Materials items)",
+                "'Synthesis' key is not allowed for Materials items",
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown_rule_keys_are_rejected() {
+        match build_item_rules(
+            "Rules:\n- When Classification: Materials\n  Require:\n  - Gather Locations\n",
+        ) {
+            Err(error) => assert_eq!(
+                error.to_string(),
+                "unknown key 'Require' in the rule for Materials items"
+            ),
+            Ok(_) => panic!("expected the misspelled key to be rejected"),
+        }
+    }
+}
@@ -0,0 +1,302 @@
+//! A JSON Schema for item files, so editors with YAML schema support can complete keys and values
+//! and flag mistakes without running the validator.
+//!
+//! The schema follows the typed model, with the allowed values of lists filled in from `lists.yml`
+//! and the classification rules of `rules.yml` written as `if`/`then` conditions. Item names are
+//! included as materials, so the schema needs regenerating when items are added.
+
+use crate::model::{DEFAULT_LANGUAGE, STAT_NAMES};
+use crate::rules::{ItemRules, Rule};
+use crate::validate_item::{
+    CC_COST_RANGE, ELEMENT_VALUE_RANGE, ITEM_NUMBER_RANGE, LEVEL_RANGE, LOOP_INDEX_RANGE,
+    REQUIRED_MATERIALS_RANGE, STAT_RANGE,
+};
+use crate::validation_sets::ItemValidationSets;
+
+use serde_json::{json, Map, Value};
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::ops::RangeInclusive;
+
+/// write the schema for the given lists and rules to a file at the given path
+pub fn export(sets: &ItemValidationSets, rules: &ItemRules, path: &str) -> io::Result<()> {
+    fs::write(
+        path,
+        serde_json::to_string_pretty(&item_schema(sets, rules))?,
+    )
+}
+
+pub fn item_schema(sets: &ItemValidationSets, rules: &ItemRules) -> Value {
+    json!({
+        "$schema": "http://json-schema.org/draft-07/schema#",
+        "title": "Atelier Ryza item",
+        "type": "object",
+        "required": ["Name", "Item Number", "Level", "Category", "Classifications", "Element"],
+        "additionalProperties": false,
+        "properties": {
+            "Name": name(),
+            "ID": {"type": "string", "pattern": "^[a-z0-9-]+$"},
+            "Item Number": integer(&ITEM_NUMBER_RANGE),
+            "Level": integer(&LEVEL_RANGE),
+            "Classifications": list(one_of(&sets.classifications)),
+            "CC Cost": integer(&CC_COST_RANGE),
+            "Category": list(one_of(&sets.categories)),
+            "Element": element_list(&sets.elements),
+            "Materials": list(one_of(&sets.materials)),
+            "Gather Locations": list(json!({"type": "string"})),
+            "Gathering Tools": list(one_of(&sets.gathering_tools)),
+            "Synthesis": synthesis(sets),
+            "Equipment": equipment(sets),
+        },
+        "allOf": rules.rules.iter().map(rule).collect::<Vec<_>>(),
+    })
+}
+
+fn synthesis(sets: &ItemValidationSets) -> Value {
+    let levels = json!({
+        "type": "object",
+        "required": ["Element"],
+        "additionalProperties": false,
+        "properties": {
+            "Element": element_list(&sets.elements),
+            "Recipe": one_of(&sets.materials),
+            "Translations": translations(),
+        },
+    });
+    // a Recipe Morph level is the only one that needs a Recipe
+    let mut recipe_morph = levels.clone();
+    recipe_morph["required"] = json!(["Element", "Recipe"]);
+
+    let material_loop = json!({
+        "type": "object",
+        "required": ["Distance", "Position", "Material", "Levels"],
+        "additionalProperties": false,
+        "properties": {
+            "Distance": integer(&LOOP_INDEX_RANGE),
+            "Position": integer(&LOOP_INDEX_RANGE),
+            "Linked From Position": integer(&LOOP_INDEX_RANGE),
+            "Material": one_of(&sets.materials),
+            "Levels": list(json!({
+                "type": "object",
+                "minProperties": 1,
+                "maxProperties": 1,
+                "properties": {"Recipe Morph": recipe_morph},
+                "additionalProperties": levels,
+            })),
+            "Unlock": element_list(&sets.elements),
+        },
+    });
+
+    json!({
+        "type": "object",
+        "required": ["Required Materials", "Required Alchemy Level", "Material Loops"],
+        "additionalProperties": false,
+        "properties": {
+            "Required Materials": integer(&REQUIRED_MATERIALS_RANGE),
+            "Required Alchemy Level": integer(&LEVEL_RANGE),
+            // each loop is a map from its name (e.g. `Effect 1`) to its details
+            "Material Loops": list(json!({
+                "type": "object",
+                "minProperties": 1,
+                "maxProperties": 1,
+                "additionalProperties": material_loop,
+            })),
+        },
+    })
+}
+
+fn equipment(sets: &ItemValidationSets) -> Value {
+    let stats: Map<String, Value> = STAT_NAMES
+        .iter()
+        .map(|stat| (stat.to_string(), integer(&STAT_RANGE)))
+        .collect();
+    json!({
+        "type": "object",
+        "required": ["Stats"],
+        "additionalProperties": false,
+        "properties": {
+            "Stats": {
+                "type": "object",
+                "additionalProperties": false,
+                "properties": stats,
+            },
+            "Equippable By": list(one_of(&sets.characters)),
+        },
+    })
+}
+
+/// a name in English, or a map from languages to names that includes English
+fn name() -> Value {
+    json!({
+        "oneOf": [
+            {"type": "string", "minLength": 1},
+            {
+                "type": "object",
+                "required": [DEFAULT_LANGUAGE],
+                "additionalProperties": {"type": "string", "minLength": 1},
+            },
+        ],
+    })
+}
+
+/// a map from languages to translated text
+fn translations() -> Value {
+    json!({"type": "object", "additionalProperties": {"type": "string", "minLength": 1}})
+}
+
+/// a rule from rules.yml as a condition on the item's classifications
+fn rule(rule: &Rule) -> Value {
+    let mut conditions = Vec::new();
+    if let Some(classification) = &rule.when_classification {
+        conditions.push(has_classification(classification));
+    }
+    if let Some(classification) = &rule.unless_classification {
+        conditions.push(json!({"not": has_classification(classification)}));
+    }
+
+    let mut then = vec![json!({"required": rule.requires})];
+    then.extend(
+        rule.forbids
+            .iter()
+            .map(|key| json!({"not": {"required": [key]}})),
+    );
+    // a rule without a classification applies to every item
+    if conditions.is_empty() {
+        return json!({"allOf": then});
+    }
+    json!({
+        "if": {"allOf": conditions},
+        "then": {"allOf": then},
+    })
+}
+
+fn has_classification(classification: &str) -> Value {
+    json!({
+        "required": ["Classifications"],
+        "properties": {"Classifications": {"contains": {"const": classification}}},
+    })
+}
+
+fn integer(range: &RangeInclusive<i64>) -> Value {
+    json!({"type": "integer", "minimum": range.start(), "maximum": range.end()})
+}
+
+fn list(entry: Value) -> Value {
+    json!({"type": "array", "items": entry})
+}
+
+/// a string from one of the lists, sorted so the schema doesn't change between runs
+fn one_of(set: &HashSet<String>) -> Value {
+    let mut values: Vec<&String> = set.iter().collect();
+    values.sort();
+    json!({"type": "string", "enum": values})
+}
+
+/// a list of elements, with or without values, like `- Fire` or `- Fire: 1`
+fn element_list(elements: &HashSet<String>) -> Value {
+    let mut names: Vec<&String> = elements.iter().collect();
+    names.sort();
+    let values: Map<String, Value> = names
+        .iter()
+        .map(|name| (name.to_string(), integer(&ELEMENT_VALUE_RANGE)))
+        .collect();
+    list(json!({
+        "oneOf": [
+            one_of(elements),
+            {
+                "type": "object",
+                "minProperties": 1,
+                "maxProperties": 1,
+                "additionalProperties": false,
+                "properties": values,
+            },
+        ],
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{rules, validation_sets};
+
+    const LISTS: &str = "
+Item Categories: [(Uni), (Bomb)]
+Item Classifications: [Materials, Battle Item]
+Elements: [Ice, Fire]
+Characters: [Ryza]
+";
+
+    const RULES: &str = "
+Rules:
+- When Classification: Battle Item
+  Requires:
+  - CC Cost
+- When Classification: Materials
+  Unless Classification: Battle Item
+  Requires:
+  - Gather Locations
+  Forbids:
+  - Synthesis
+- Requires:
+  - Name
+";
+
+    fn schema() -> Value {
+        let sets = validation_sets::build_item_validation_sets(LISTS).unwrap();
+        let rules = rules::build_item_rules(RULES).unwrap();
+        item_schema(&sets, &rules)
+    }
+
+    #[test]
+    fn rules_become_if_then_conditions() {
+        let schema = schema();
+        assert_eq!(
+            schema["allOf"][0],
+            json!({
+                "if": {"allOf": [{
+                    "required": ["Classifications"],
+                    "properties": {"Classifications": {"contains": {"const": "Battle Item"}}},
+                }]},
+                "then": {"allOf": [{"required": ["CC Cost"]}]},
+            })
+        );
+        assert_eq!(
+            schema["allOf"][1]["if"]["allOf"][1]["not"]["properties"]["Classifications"],
+            json!({"contains": {"const": "Battle Item"}})
+        );
+        assert_eq!(
+            schema["allOf"][1]["then"],
+            json!({"allOf": [
+                {"required": ["Gather Locations"]},
+                {"not": {"required": ["Synthesis"]}},
+            ]})
+        );
+        // a rule without a classification applies to every item
+        assert_eq!(
+            schema["allOf"][2],
+            json!({"allOf": [{"required": ["Name"]}]})
+        );
+    }
+
+    #[test]
+    fn lists_and_ranges_come_from_the_data() {
+        let schema = schema();
+        let properties = &schema["properties"];
+        assert_eq!(
+            properties["Category"]["items"],
+            json!({"type": "string", "enum": ["(Bomb)", "(Uni)"]})
+        );
+        assert_eq!(
+            properties["Level"],
+            json!({"type": "integer", "minimum": 1, "maximum": 50})
+        );
+        let element = &properties["Element"]["items"]["oneOf"];
+        assert_eq!(element[0]["enum"], json!(["Fire", "Ice"]));
+        assert_eq!(element[1]["properties"]["Ice"]["maximum"], 99);
+        assert_eq!(
+            properties["Equipment"]["properties"]["Equippable By"]["items"]["enum"],
+            json!(["Ryza"])
+        );
+    }
+}
@@ -0,0 +1,322 @@
+//! Fuzzy search over item names, loop effects, categories and gather locations, for players who
+//! only remember part of a name ("uni spike") or how to spell it roughly ("suplement").
+//!
+//! Every word of the search has to match a word of the same text, either exactly, as the start of
+//! a word, inside a word, or (for words of three letters or more) closely enough by Jaro-Winkler
+//! similarity. Each item is listed once, under its best matching text, best items first.
+
+use crate::model::{self, Item};
+
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+
+/// how similar a word has to be to count as a fuzzy match, from 0 to 1
+const FUZZY_THRESHOLD: f64 = 0.85;
+
+/// what part of an item a search matched. Earlier fields rank first when the scores are equal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SearchField {
+    Name,
+    Effect,
+    Category,
+    Location,
+}
+
+impl SearchField {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SearchField::Name => "name",
+            SearchField::Effect => "effect",
+            SearchField::Category => "category",
+            SearchField::Location => "location",
+        }
+    }
+}
+
+pub struct SearchResult {
+    /// the index of the item in the items the index was built from
+    pub item: usize,
+    pub field: SearchField,
+    /// the text that matched, e.g. the effect `Uni Spike S`
+    pub text: String,
+    /// how well the text matched, from 0 to 1
+    pub score: f64,
+}
+
+/// the searchable texts of a list of items
+pub struct SearchIndex {
+    entries: Vec<Entry>,
+}
+
+struct Entry {
+    item: usize,
+    field: SearchField,
+    text: String,
+    lowercase: String,
+    words: Vec<String>,
+}
+
+impl SearchIndex {
+    pub fn new(items: &[Item]) -> SearchIndex {
+        let mut entries = Vec::new();
+        for (index, item) in items.iter().enumerate() {
+            let mut add = |field: SearchField, text: &str| {
+                if !text.trim().is_empty() {
+                    entries.push(Entry::new(index, field, text));
+                }
+            };
+            add(SearchField::Name, &item.name);
+            for name in item.names.values() {
+                add(SearchField::Name, name);
+            }
+            for level in item
+                .synthesis
+                .iter()
+                .flat_map(|synthesis| &synthesis.material_loops)
+                .flat_map(|material_loop| &material_loop.levels)
+            {
+                add(SearchField::Effect, &level.effect);
+                for effect in level.effect_names.values() {
+                    add(SearchField::Effect, effect);
+                }
+            }
+            for category in &item.categories {
+                add(SearchField::Category, model::parse_material(category).1);
+            }
+            for location in &item.gather_locations {
+                add(SearchField::Location, location);
+            }
+        }
+        SearchIndex { entries }
+    }
+
+    /// the items matching the search, best first, each with its best matching text
+    pub fn search(&self, search: &str) -> Vec<SearchResult> {
+        let search_words = words(&search.to_lowercase());
+        if search_words.is_empty() {
+            return Vec::new();
+        }
+
+        let mut best: BTreeMap<usize, SearchResult> = BTreeMap::new();
+        for entry in &self.entries {
+            let score = entry.score(&search_words);
+            if score == 0.0 {
+                continue;
+            }
+            let result = SearchResult {
+                item: entry.item,
+                field: entry.field,
+                text: entry.text.clone(),
+                score,
+            };
+            match best.get(&entry.item) {
+                Some(other) if rank(&result, other) != Ordering::Less => {}
+                _ => {
+                    best.insert(entry.item, result);
+                }
+            }
+        }
+        let mut best: Vec<SearchResult> = best.into_values().collect();
+        best.sort_by(rank);
+        best
+    }
+}
+
+impl Entry {
+    fn new(item: usize, field: SearchField, text: &str) -> Entry {
+        let lowercase = text.to_lowercase();
+        Entry {
+            item,
+            field,
+            text: text.to_string(),
+            words: words(&lowercase),
+            lowercase,
+        }
+    }
+
+    /// the average score of the search words, or 0 if any of them doesn't match
+    fn score(&self, search_words: &[String]) -> f64 {
+        let mut total = 0.0;
+        for word in search_words {
+            let score = self.word_score(word);
+            if score == 0.0 {
+                return 0.0;
+            }
+            total += score;
+        }
+        total / search_words.len() as f64
+    }
+
+    fn word_score(&self, word: &str) -> f64 {
+        if self.words.iter().any(|text_word| text_word == word) {
+            1.0
+        } else if self
+            .words
+            .iter()
+            .any(|text_word| text_word.starts_with(word))
+        {
+            0.9
+        } else if self.lowercase.contains(word) {
+            0.8
+        } else if word.chars().count() >= 3 {
+            let similarity = self
+                .words
+                .iter()
+                .map(|text_word| strsim::jaro_winkler(word, text_word))
+                .fold(0.0, f64::max);
+            if similarity >= FUZZY_THRESHOLD {
+                similarity * 0.7
+            } else {
+                0.0
+            }
+        } else {
+            0.0
+        }
+    }
+}
+
+/// better scores first, then by field, then by item
+fn rank(a: &SearchResult, b: &SearchResult) -> Ordering {
+    b.score
+        .partial_cmp(&a.score)
+        .unwrap_or(Ordering::Equal)
+        .then(a.field.cmp(&b.field))
+        .then(a.item.cmp(&b.item))
+}
+
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ITEMS: [&str; 3] = [
+        "
+Name:
+  en: Red Supplement
+  ja: 赤の添加剤
+Category: [(Supplement)]
+Synthesis:
+  Material Loops:
+  - Effect 1:
+      Levels:
+      - Synth Quality +5:
+          Translations:
+            ja: 調合品質+5
+",
+        "
+Name: Explosive Uni
+Category: [(Bomb)]
+Synthesis:
+  Material Loops:
+  - Effect 1:
+      Levels:
+      - Uni Spike S:
+      - Uni Spike M:
+",
+        "
+Name: Uni
+Category: [(Uni)]
+Gather Locations: [Little Adventure Island]
+",
+    ];
+
+    fn index() -> SearchIndex {
+        let items: Vec<Item> = ITEMS
+            .iter()
+            .map(|contents| model::load_item(contents).unwrap())
+            .collect();
+        SearchIndex::new(&items)
+    }
+
+    /// the matched item, field and text of each result
+    fn results(search: &str) -> Vec<(usize, SearchField, String)> {
+        index()
+            .search(search)
+            .into_iter()
+            .map(|result| (result.item, result.field, result.text))
+            .collect()
+    }
+
+    #[test]
+    fn nothing_matches_an_empty_search() {
+        assert!(results("").is_empty());
+        assert!(results(" - ").is_empty());
+    }
+
+    #[test]
+    fn each_item_is_listed_once_under_its_best_text() {
+        assert_eq!(
+            results("uni"),
+            [
+                (1, SearchField::Name, "Explosive Uni".to_string()),
+                (2, SearchField::Name, "Uni".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn the_start_of_a_word_scores_below_a_whole_word() {
+        let scores: Vec<f64> = index()
+            .search("supp")
+            .iter()
+            .map(|result| result.score)
+            .collect();
+        assert_eq!(scores, [0.9]);
+    }
+
+    #[test]
+    fn typos_within_the_threshold_match() {
+        let found = index().search("red suplement");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].item, 0);
+        assert_eq!(found[0].field, SearchField::Name);
+        assert!(found[0].score < 1.0 && found[0].score > 0.8);
+
+        // "ilsand" is close enough to "island", "isle" isn't
+        assert_eq!(results("ilsand").len(), 1);
+        assert!(strsim::jaro_winkler("isle", "island") < FUZZY_THRESHOLD);
+        assert!(results("isle").is_empty());
+        // short words have to match exactly or as part of a word
+        assert!(results("ux").is_empty());
+    }
+
+    #[test]
+    fn every_word_has_to_match_the_same_text() {
+        assert!(results("red spike").is_empty());
+        assert_eq!(results("spike m").len(), 1);
+    }
+
+    #[test]
+    fn matches_effects_in_any_language() {
+        assert_eq!(
+            results("spike s"),
+            [(1, SearchField::Effect, "Uni Spike S".to_string())]
+        );
+        assert_eq!(
+            results("調合品質"),
+            [(0, SearchField::Effect, "調合品質+5".to_string())]
+        );
+    }
+
+    #[test]
+    fn matches_categories_and_locations() {
+        assert_eq!(
+            results("bomb"),
+            [(1, SearchField::Category, "Bomb".to_string())]
+        );
+        assert_eq!(
+            results("adventure island"),
+            [(
+                2,
+                SearchField::Location,
+                "Little Adventure Island".to_string()
+            )]
+        );
+    }
+}
@@ -17,7 +17,7 @@ pub fn get_settings() -> Settings {
 
     let docs =
         YamlLoader::load_from_str(&contents).expect("unable to parse settings file as a YAML file");
-    let yaml = &docs[0];
+    let yaml = docs.first().unwrap_or(&Yaml::BadValue);
 
     if let Yaml::String(data_folder) = &yaml["Data Folder"] {
         Settings {
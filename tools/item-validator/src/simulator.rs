@@ -0,0 +1,243 @@
+//! A simplified synthesis simulator.
+//!
+//! The simulator doesn't pick materials: it assumes the player can put up to `max_element_value`
+//! of any element into each loop. A loop is reached when the loop it's linked from is reached and
+//! its `Unlock` values are within that limit, and each reached loop climbs its levels until a
+//! level asks for more than the limit.
+
+use crate::model::{ElementValue, Item, MaterialLoop, STAT_NAMES};
+
+/// with this limit every level of every loop is reached
+pub const UNLIMITED: i64 = i64::MAX;
+
+/// an effect added to the item by one of its material loops
+#[derive(Debug, Clone, PartialEq)]
+pub struct RealisedEffect {
+    pub loop_name: String,
+    pub position: i64,
+    pub effect: String,
+    /// the level reached in the loop, starting from 1
+    pub level: usize,
+}
+
+/// the outcome of a synthesis: the effects it realised and the trait slots it opened
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Roll {
+    pub effects: Vec<RealisedEffect>,
+    pub trait_slots: usize,
+}
+
+impl Roll {
+    /// the stat bonus from effects named like "ATK +5"
+    pub fn stat_bonus(&self, stat: &str) -> i64 {
+        self.effects
+            .iter()
+            .filter_map(|effect| parse_stat_effect(&effect.effect))
+            .filter(|(name, _)| *name == stat)
+            .map(|(_, bonus)| bonus)
+            .sum()
+    }
+}
+
+/// the best roll of an item with the given element limit per loop.
+///
+/// Loops sharing an "Effect" name (e.g. two "Effect 2" loops) fill the same effect slot, so only
+/// the deepest one reached counts. Recipe Morph levels change the item into another one, so they
+/// aren't part of the roll.
+pub fn best_roll(item: &Item, max_element_value: i64) -> Roll {
+    let mut roll = Roll::default();
+    let synthesis = match &item.synthesis {
+        Some(synthesis) => synthesis,
+        None => return roll,
+    };
+
+    let reached = |material_loop: &MaterialLoop| -> bool {
+        let mut current = material_loop;
+        loop {
+            if !within(&current.unlock, max_element_value) {
+                return false;
+            }
+            match current.linked_from_position {
+                None => return true,
+                Some(position) => match synthesis.material_loop(position) {
+                    // guard against loops linked to themselves (or further along)
+                    Some(parent) if parent.distance < current.distance => current = parent,
+                    _ => return false,
+                },
+            }
+        }
+    };
+
+    let mut effect_slots: Vec<(i64, RealisedEffect)> = Vec::new();
+    for material_loop in synthesis.material_loops.iter().filter(|l| reached(l)) {
+        let levels_reached = material_loop
+            .levels
+            .iter()
+            .take_while(|level| within(&level.elements, max_element_value))
+            .count();
+        if levels_reached == 0 {
+            continue;
+        }
+
+        if material_loop.name == "Traits" {
+            roll.trait_slots += levels_reached;
+            continue;
+        }
+        let level = &material_loop.levels[levels_reached - 1];
+        if level.recipe.is_some() || level.effect == "Recipe Morph" {
+            continue;
+        }
+
+        let effect = RealisedEffect {
+            loop_name: material_loop.name.clone(),
+            position: material_loop.position,
+            effect: level.effect.clone(),
+            level: levels_reached,
+        };
+        if material_loop.name.starts_with("Effect") {
+            match effect_slots
+                .iter_mut()
+                .find(|(_, slot)| slot.loop_name == material_loop.name)
+            {
+                Some(slot) if slot.0 < material_loop.distance => {
+                    *slot = (material_loop.distance, effect)
+                }
+                Some(_) => {}
+                None => effect_slots.push((material_loop.distance, effect)),
+            }
+        } else {
+            roll.effects.push(effect);
+        }
+    }
+    roll.effects
+        .extend(effect_slots.into_iter().map(|(_, effect)| effect));
+    roll.effects.sort_by_key(|effect| effect.position);
+    roll
+}
+
+/// true if every element value is within the limit
+fn within(elements: &[ElementValue], max_element_value: i64) -> bool {
+    elements
+        .iter()
+        .all(|element| element.value.unwrap_or(0) <= max_element_value)
+}
+
+/// reads effects like "ATK +5" as a stat and a bonus
+pub fn parse_stat_effect(effect: &str) -> Option<(&'static str, i64)> {
+    let mut words = effect.split_whitespace();
+    let stat = words.next()?;
+    let bonus = words.next()?.strip_prefix('+')?.parse().ok()?;
+    if words.next().is_some() {
+        return None;
+    }
+    STAT_NAMES
+        .iter()
+        .find(|name| **name == stat)
+        .map(|name| (*name, bonus))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model;
+
+    const ITEM: &str = "
+Name: Test Sword
+Item Number: 900
+Level: 5
+Classifications: [Equip Item, Weapon]
+Synthesis:
+  Required Materials: 2
+  Required Alchemy Level: 3
+  Material Loops:
+  - Effect 1:
+      Distance: 0
+      Position: 0
+      Material: (Ore)
+      Levels:
+      - ATK +5:
+          Element:
+          - Fire: 2
+      - ATK +10:
+          Element:
+          - Fire: 4
+  - Effect 2:
+      Distance: 1
+      Position: 1
+      Linked From Position: 0
+      Material: (Ore)
+      Levels:
+      - SPD +2:
+          Element:
+          - Ice: 1
+  - Effect 2:
+      Distance: 2
+      Position: 2
+      Linked From Position: 1
+      Material: (Ore)
+      Levels:
+      - SPD +4:
+          Element:
+          - Ice: 1
+      Unlock:
+      - Ice: 3
+  - Traits:
+      Distance: 1
+      Position: 3
+      Linked From Position: 0
+      Material: (Ore)
+      Levels:
+      - Added 1 trait slot(s):
+          Element:
+          - Fire: 1
+      - Added 1 trait slot(s):
+          Element:
+          - Fire: 3
+";
+
+    fn effects(roll: &Roll) -> Vec<&str> {
+        roll.effects
+            .iter()
+            .map(|effect| effect.effect.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn unlimited_rolls_reach_the_deepest_effect_loop() {
+        let item = model::load_item(ITEM).unwrap();
+        let roll = best_roll(&item, UNLIMITED);
+        // the two Effect 2 loops share a slot, so only the deeper one counts
+        assert_eq!(effects(&roll), vec!["ATK +10", "SPD +4"]);
+        assert_eq!(roll.effects[0].level, 2);
+        assert_eq!(roll.trait_slots, 2);
+        assert_eq!(roll.stat_bonus("ATK"), 10);
+        assert_eq!(roll.stat_bonus("SPD"), 4);
+        assert_eq!(roll.stat_bonus("DEF"), 0);
+    }
+
+    #[test]
+    fn element_limits_stop_levels_and_locked_loops() {
+        let item = model::load_item(ITEM).unwrap();
+        let roll = best_roll(&item, 2);
+        // the deeper Effect 2 loop needs 3 Ice to unlock, so the shallower one fills the slot
+        assert_eq!(effects(&roll), vec!["ATK +5", "SPD +2"]);
+        assert_eq!(roll.trait_slots, 1);
+        assert_eq!(roll.stat_bonus("ATK"), 5);
+    }
+
+    #[test]
+    fn items_without_a_synthesis_roll_nothing() {
+        let item = model::load_item("Name: Uni\nItem Number: 1\nLevel: 1\n").unwrap();
+        assert_eq!(best_roll(&item, UNLIMITED), Roll::default());
+    }
+
+    #[test]
+    fn stat_effects_need_a_stat_and_a_signed_bonus() {
+        assert_eq!(parse_stat_effect("ATK +5"), Some(("ATK", 5)));
+        assert_eq!(parse_stat_effect("SPD +12"), Some(("SPD", 12)));
+        assert_eq!(parse_stat_effect("ATK+5"), None);
+        assert_eq!(parse_stat_effect("ATK 5"), None);
+        assert_eq!(parse_stat_effect("Attack +5"), None);
+        assert_eq!(parse_stat_effect("ATK +5 L"), None);
+    }
+}
@@ -0,0 +1,493 @@
+//! A static HTML encyclopedia of the dataset.
+//!
+//! Every item gets a page under `items/`, and every category and gather location gets a page
+//! listing the items that belong to it. Pages link to each other through materials, categories,
+//! recipe morphs and gather locations, so the site can be browsed offline.
+//!
+//! Item names and effects are shown in the site's language, falling back to English for anything
+//! that hasn't been translated. Page names always come from the English names, so links between
+//! sites in different languages line up.
+
+use crate::item_file::slug;
+use crate::model::{self, ElementValue, Item, MaterialKind};
+use crate::render::{self, escape};
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+const STYLE: &str = "
+body { font-family: sans-serif; max-width: 60em; margin: 1em auto; padding: 0 1em; color: #222; }
+table { border-collapse: collapse; }
+td, th { border: 1px solid #ccc; padding: 0.2em 0.6em; text-align: left; vertical-align: top; }
+nav { margin-bottom: 1em; }
+.missing { color: #a00; }
+";
+
+/// the page of an item, named like its item file (e.g. `001-explosive-uni.html`)
+pub fn item_page(item: &Item) -> String {
+    format!("{:03}-{}.html", item.item_number, slug(&item.name))
+}
+
+/// write the site into the given folder, creating it if needed, with item names and effects in
+/// the given language (e.g. `en` or `ja`)
+pub fn generate(items: &[Item], folder: &str, language: &str) -> io::Result<()> {
+    let folder = Path::new(folder);
+    for sub_folder in &["items", "categories", "locations"] {
+        fs::create_dir_all(folder.join(sub_folder))?;
+    }
+    let site = Site { items, language };
+
+    fs::write(folder.join("index.html"), site.index())?;
+    for item in items {
+        fs::write(folder.join("items").join(item_page(item)), site.item(item))?;
+    }
+    for category in site.categories() {
+        fs::write(
+            folder
+                .join("categories")
+                .join(format!("{}.html", slug(&category))),
+            site.category(&category),
+        )?;
+    }
+    for location in site.locations() {
+        fs::write(
+            folder
+                .join("locations")
+                .join(format!("{}.html", slug(&location))),
+            site.location(&location),
+        )?;
+    }
+    Ok(())
+}
+
+struct Site<'a> {
+    items: &'a [Item],
+    language: &'a str,
+}
+
+impl Site<'_> {
+    fn find(&self, name: &str) -> Option<&Item> {
+        self.items.iter().find(|item| item.name == name)
+    }
+
+    /// every category an item belongs to or is made from, without parentheses
+    fn categories(&self) -> BTreeSet<String> {
+        let mut categories = BTreeSet::new();
+        for item in self.items {
+            for value in item.categories.iter().chain(&item.materials) {
+                if let (MaterialKind::Category, category) = model::parse_material(value) {
+                    categories.insert(category.to_string());
+                }
+            }
+        }
+        categories
+    }
+
+    fn locations(&self) -> BTreeSet<String> {
+        self.items
+            .iter()
+            .flat_map(|item| item.gather_locations.iter().cloned())
+            .collect()
+    }
+
+    /// a link to the page of a material, which is either a category or an item.
+    /// Items without a file yet are marked as missing.
+    fn material_link(&self, material: &str, depth: usize) -> String {
+        let up = "../".repeat(depth);
+        match model::parse_material(material) {
+            (MaterialKind::Category, category) => format!(
+                "<a href=\"{}categories/{}.html\">{}</a>",
+                up,
+                slug(category),
+                escape(material)
+            ),
+            (MaterialKind::Item, name) => match self.find(name) {
+                Some(item) => format!(
+                    "<a href=\"{}items/{}\">{}</a>",
+                    up,
+                    item_page(item),
+                    escape(item.name_in(self.language))
+                ),
+                None => format!("<span class=\"missing\">{}</span>", escape(name)),
+            },
+        }
+    }
+
+    fn location_link(&self, location: &str, depth: usize) -> String {
+        format!(
+            "<a href=\"{}locations/{}.html\">{}</a>",
+            "../".repeat(depth),
+            slug(location),
+            escape(location)
+        )
+    }
+
+    fn index(&self) -> String {
+        let mut items: Vec<&Item> = self.items.iter().collect();
+        items.sort_by_key(|item| item.item_number);
+
+        let mut body = String::from("<h1>Atelier Ryza Items</h1>\n<table>\n");
+        body.push_str("<tr><th>No.</th><th>Name</th><th>Level</th><th>Category</th></tr>\n");
+        for item in items {
+            body.push_str(&format!(
+                "<tr><td>{}</td><td><a href=\"items/{}\">{}</a></td><td>{}</td><td>{}</td></tr>\n",
+                item.item_number,
+                item_page(item),
+                escape(item.name_in(self.language)),
+                item.level,
+                self.material_links(&item.categories, 0)
+            ));
+        }
+        body.push_str("</table>\n<h2>Gather Locations</h2>\n<ul>\n");
+        for location in self.locations() {
+            body.push_str(&format!("<li>{}</li>\n", self.location_link(&location, 0)));
+        }
+        body.push_str("</ul>\n");
+        page("Atelier Ryza Items", 0, &body)
+    }
+
+    fn item(&self, item: &Item) -> String {
+        let mut body = format!(
+            "<h1>{}</h1>\n<p>No. {}, Level {}</p>\n<table>\n",
+            escape(item.name_in(self.language)),
+            item.item_number,
+            item.level
+        );
+        row(
+            &mut body,
+            "Classifications",
+            &escape(&item.classifications.join(", ")),
+        );
+        row(
+            &mut body,
+            "Category",
+            &self.material_links(&item.categories, 1),
+        );
+        row(&mut body, "Element", &elements(&item.elements));
+        if let Some(cc_cost) = item.cc_cost {
+            row(&mut body, "CC Cost", &cc_cost.to_string());
+        }
+        if !item.materials.is_empty() {
+            row(
+                &mut body,
+                "Materials",
+                &self.material_links(&item.materials, 1),
+            );
+        }
+        if !item.gather_locations.is_empty() {
+            let links: Vec<String> = item
+                .gather_locations
+                .iter()
+                .map(|location| self.location_link(location, 1))
+                .collect();
+            row(&mut body, "Gather Locations", &links.join(", "));
+        }
+        if !item.gathering_tools.is_empty() {
+            row(
+                &mut body,
+                "Gathering Tools",
+                &escape(&item.gathering_tools.join(", ")),
+            );
+        }
+        if let Some(equipment) = &item.equipment {
+            let stats: Vec<String> = model::STAT_NAMES
+                .iter()
+                .map(|stat| format!("{} {}", stat, equipment.stats.get(stat).unwrap_or(0)))
+                .collect();
+            row(&mut body, "Stats", &stats.join(", "));
+            if !equipment.equippable_by.is_empty() {
+                row(
+                    &mut body,
+                    "Equippable By",
+                    &escape(&equipment.equippable_by.join(", ")),
+                );
+            }
+        }
+        body.push_str("</table>\n");
+
+        if let Some(synthesis) = &item.synthesis {
+            body.push_str(&format!(
+                "<h2>Synthesis</h2>\n<p>Required Materials: {}, Required Alchemy Level: {}</p>\n",
+                synthesis.required_materials, synthesis.required_alchemy_level
+            ));
+            body.push_str(&render::loop_tree_svg(synthesis));
+            body.push_str("<table>\n<tr><th>Position</th><th>Loop</th><th>Material</th><th>Levels</th><th>Unlock</th></tr>\n");
+            for material_loop in &synthesis.material_loops {
+                let levels: Vec<String> = material_loop
+                    .levels
+                    .iter()
+                    .map(|level| {
+                        let recipe = match &level.recipe {
+                            Some(recipe) => format!(" &rarr; {}", self.material_link(recipe, 1)),
+                            None => String::new(),
+                        };
+                        format!(
+                            "{}{} ({})",
+                            escape(level.effect_in(self.language)),
+                            recipe,
+                            elements(&level.elements)
+                        )
+                    })
+                    .collect();
+                body.push_str(&format!(
+                    "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+                    material_loop.position,
+                    escape(&material_loop.name),
+                    self.material_link(&material_loop.material, 1),
+                    levels.join("<br>"),
+                    elements(&material_loop.unlock)
+                ));
+            }
+            body.push_str("</table>\n");
+        }
+
+        let used_in = self.used_in(item);
+        if !used_in.is_empty() {
+            body.push_str("<h2>Used In</h2>\n<ul>\n");
+            for other in used_in {
+                body.push_str(&format!(
+                    "<li>{}</li>\n",
+                    self.material_link(&other.name, 1)
+                ));
+            }
+            body.push_str("</ul>\n");
+        }
+        let morphed_from = self.morphed_from(&item.name);
+        if !morphed_from.is_empty() {
+            body.push_str("<h2>Recipe Morph From</h2>\n<ul>\n");
+            for other in morphed_from {
+                body.push_str(&format!(
+                    "<li>{}</li>\n",
+                    self.material_link(&other.name, 1)
+                ));
+            }
+            body.push_str("</ul>\n");
+        }
+        page(item.name_in(self.language), 1, &body)
+    }
+
+    fn category(&self, category: &str) -> String {
+        let written = format!("({})", category);
+        let mut body = format!("<h1>{}</h1>\n<h2>Items</h2>\n<ul>\n", escape(&written));
+        for item in self
+            .items
+            .iter()
+            .filter(|item| item.categories.contains(&written))
+        {
+            body.push_str(&format!("<li>{}</li>\n", self.material_link(&item.name, 1)));
+        }
+        body.push_str("</ul>\n<h2>Used In</h2>\n<ul>\n");
+        for item in self
+            .items
+            .iter()
+            .filter(|item| item.uses_material(&written))
+        {
+            body.push_str(&format!("<li>{}</li>\n", self.material_link(&item.name, 1)));
+        }
+        body.push_str("</ul>\n");
+        page(&written, 1, &body)
+    }
+
+    fn location(&self, location: &str) -> String {
+        let mut body = format!(
+            "<h1>{}</h1>\n<h2>Gathered Here</h2>\n<ul>\n",
+            escape(location)
+        );
+        for item in self
+            .items
+            .iter()
+            .filter(|item| item.gather_locations.iter().any(|l| l == location))
+        {
+            body.push_str(&format!("<li>{}</li>\n", self.material_link(&item.name, 1)));
+        }
+        body.push_str("</ul>\n");
+        page(location, 1, &body)
+    }
+
+    fn material_links(&self, materials: &[String], depth: usize) -> String {
+        let links: Vec<String> = materials
+            .iter()
+            .map(|material| self.material_link(material, depth))
+            .collect();
+        links.join(", ")
+    }
+
+    /// the items that can use this item as a material, by name or by one of its categories
+    fn used_in(&self, item: &Item) -> Vec<&Item> {
+        self.items
+            .iter()
+            .filter(|other| other.name != item.name)
+            .filter(|other| {
+                other.uses_material(&item.name)
+                    || item
+                        .categories
+                        .iter()
+                        .any(|category| other.uses_material(category))
+            })
+            .collect()
+    }
+
+    /// the items with a Recipe Morph into the named item
+    fn morphed_from(&self, name: &str) -> Vec<&Item> {
+        self.items
+            .iter()
+            .filter(|item| {
+                item.synthesis.iter().any(|synthesis| {
+                    synthesis.material_loops.iter().any(|material_loop| {
+                        material_loop
+                            .levels
+                            .iter()
+                            .any(|level| level.recipe.as_deref() == Some(name))
+                    })
+                })
+            })
+            .collect()
+    }
+}
+
+fn elements(elements: &[ElementValue]) -> String {
+    let elements: Vec<String> = elements
+        .iter()
+        .map(|element| match element.value {
+            Some(value) => format!("{} {}", escape(&element.element), value),
+            None => escape(&element.element),
+        })
+        .collect();
+    elements.join(", ")
+}
+
+fn row(body: &mut String, heading: &str, value: &str) {
+    body.push_str(&format!(
+        "<tr><th>{}</th><td>{}</td></tr>\n",
+        heading, value
+    ));
+}
+
+/// wrap a page body with the shared header, `depth` folders below the site's root
+fn page(title: &str, depth: usize, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>{}</style>\n</head>\n<body>\n<nav><a href=\"{}index.html\">All items</a></nav>\n{}</body>\n</html>\n",
+        escape(title),
+        STYLE,
+        "../".repeat(depth),
+        body
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    const UNI: &str = "
+Name:
+  en: Uni
+  ja: ウニ
+Item Number: 2
+Level: 1
+Classifications: [Materials]
+Category: [(Uni)]
+Gather Locations: [Sandy Field]
+";
+
+    const EXPLOSIVE_UNI: &str = "
+Name: Explosive Uni
+Item Number: 1
+Level: 2
+Classifications: [Attack Items]
+Category: [(Bomb)]
+Materials: [Uni, (Gunpowder), Missing Thing]
+Synthesis:
+  Required Materials: 3
+  Required Alchemy Level: 1
+  Material Loops:
+  - Recipe:
+      Distance: 0
+      Position: 0
+      Material: (Uni)
+      Levels:
+      - Recipe Morph:
+          Recipe: Uni
+          Element:
+          - Fire: 2
+          Translations:
+            ja: レシピ変化
+";
+
+    /// a site generated in the temporary folder, removed when dropped
+    struct SiteFolder(PathBuf);
+
+    impl SiteFolder {
+        fn new(test: &str, language: &str) -> SiteFolder {
+            let path = std::env::temp_dir().join(format!(
+                "item-validator-site-{}-{}",
+                test,
+                std::process::id()
+            ));
+            let _ = fs::remove_dir_all(&path);
+            let items = vec![
+                model::load_item(UNI).unwrap(),
+                model::load_item(EXPLOSIVE_UNI).unwrap(),
+            ];
+            generate(&items, &path.to_string_lossy(), language).unwrap();
+            SiteFolder(path)
+        }
+
+        fn page(&self, path: &str) -> String {
+            fs::read_to_string(self.0.join(path)).unwrap()
+        }
+    }
+
+    impl Drop for SiteFolder {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn pages_link_items_categories_and_locations() {
+        let site = SiteFolder::new("links", "en");
+
+        let index = site.page("index.html");
+        // items are listed by number
+        let explosive_uni = index.find("items/001-explosive-uni.html").unwrap();
+        let uni = index.find("items/002-uni.html").unwrap();
+        assert!(explosive_uni < uni);
+        assert!(index.contains("<a href=\"locations/sandy-field.html\">Sandy Field</a>"));
+
+        let page = site.page("items/001-explosive-uni.html");
+        assert!(page.contains("<a href=\"../index.html\">All items</a>"));
+        assert!(page.contains("<a href=\"../items/002-uni.html\">Uni</a>"));
+        assert!(page.contains("<a href=\"../categories/gunpowder.html\">(Gunpowder)</a>"));
+        assert!(page.contains("<span class=\"missing\">Missing Thing</span>"));
+        assert!(page.contains("Recipe Morph &rarr; <a href=\"../items/002-uni.html\">Uni</a>"));
+        assert!(page.contains("<svg "));
+
+        let page = site.page("items/002-uni.html");
+        assert!(page.contains("<h2>Used In</h2>"));
+        assert!(page.contains("<h2>Recipe Morph From</h2>"));
+        assert!(page.contains("<a href=\"../items/001-explosive-uni.html\">Explosive Uni</a>"));
+
+        let category = site.page("categories/uni.html");
+        assert!(category.contains("<h1>(Uni)</h1>"));
+        assert!(category.contains("<li><a href=\"../items/002-uni.html\">Uni</a></li>"));
+        assert!(category
+            .contains("<li><a href=\"../items/001-explosive-uni.html\">Explosive Uni</a></li>"));
+        let location = site.page("locations/sandy-field.html");
+        assert!(location.contains("<li><a href=\"../items/002-uni.html\">Uni</a></li>"));
+    }
+
+    #[test]
+    fn translated_sites_fall_back_to_english() {
+        let site = SiteFolder::new("translated", "ja");
+        let page = site.page("items/002-uni.html");
+        assert!(page.contains("<h1>ウニ</h1>"));
+        assert!(page.contains("<title>ウニ</title>"));
+
+        let page = site.page("items/001-explosive-uni.html");
+        assert!(page.contains("<h1>Explosive Uni</h1>"));
+        assert!(page.contains("レシピ変化 &rarr; <a href=\"../items/002-uni.html\">ウニ</a>"));
+    }
+}
@@ -1,10 +1,26 @@
+use crate::model::{DEFAULT_LANGUAGE, STAT_NAMES};
+use crate::rules::ItemRules;
 use crate::validation_sets::ItemValidationSets;
 
 use std::collections::HashSet;
+use std::ops::RangeInclusive;
 
 extern crate yaml_rust;
 use yaml_rust::{ScanError, Yaml, YamlLoader};
 
+/// the range of item numbers listed in the game's item encyclopedia
+pub(crate) const ITEM_NUMBER_RANGE: RangeInclusive<i64> = 1..=999;
+/// item levels (and alchemy levels) are capped at 50 in the game
+pub(crate) const LEVEL_RANGE: RangeInclusive<i64> = 1..=50;
+pub(crate) const CC_COST_RANGE: RangeInclusive<i64> = 1..=10;
+pub(crate) const REQUIRED_MATERIALS_RANGE: RangeInclusive<i64> = 1..=10;
+/// element values, either on an item or as a material loop threshold
+pub(crate) const ELEMENT_VALUE_RANGE: RangeInclusive<i64> = 1..=99;
+/// the Distance and Position values of material loops
+pub(crate) const LOOP_INDEX_RANGE: RangeInclusive<i64> = 0..=99;
+/// equipment base stats (HP, MP, ATK, DEF, SPD)
+pub(crate) const STAT_RANGE: RangeInclusive<i64> = 0..=999;
+
 pub struct ValidationResults {
     pub valid: bool,
     pub pass_messages: Vec<String>,
@@ -26,7 +42,7 @@ impl ValidationResults {
         self
     }
 
-    fn new() -> ValidationResults {
+    pub fn new() -> ValidationResults {
         ValidationResults {
             valid: true,
             pass_messages: Vec::new(),
@@ -35,56 +51,268 @@ impl ValidationResults {
     }
 }
 
+impl Default for ValidationResults {
+    fn default() -> ValidationResults {
+        ValidationResults::new()
+    }
+}
+
 /// top level validation function for an item, returns ValidationResults, which contains a flag
 /// for whether the item is valid or not, and lists of pass and fail messages
 pub fn validate_item_contents(
     contents: &str,
     item_validation_sets: &ItemValidationSets,
+    item_rules: &ItemRules,
 ) -> Result<ValidationResults, ScanError> {
-    let mut results = ValidationResults::new();
-
     let docs = YamlLoader::load_from_str(contents)?;
-    // YAML files can actually contain multiple files inside, we want the first one
-    let yaml = &docs[0];
+    Ok(validate_item_documents(
+        &docs,
+        item_validation_sets,
+        item_rules,
+    ))
+}
+
+/// validate the documents of an item file. A file holds one item, so an empty file, or one with
+/// documents after the item, fails validation.
+pub fn validate_item_documents(
+    docs: &[Yaml],
+    item_validation_sets: &ItemValidationSets,
+    item_rules: &ItemRules,
+) -> ValidationResults {
+    let yaml = match docs.first() {
+        Some(yaml) => yaml,
+        None => {
+            let mut results = ValidationResults::new();
+            results.valid = false;
+            results
+                .fail_messages
+                .push("the file is empty, it should hold one item".to_string());
+            return results;
+        }
+    };
+
+    let mut results = validate_item_yaml(yaml, item_validation_sets, item_rules);
+    if docs.len() > 1 {
+        results.valid = false;
+        results.fail_messages.push(format!(
+            "the file has {} YAML documents, only the first is read (put each item in its own file)",
+            docs.len()
+        ));
+    }
+    results
+}
+
+/// validate an item that's already been parsed
+pub fn validate_item_yaml(
+    yaml: &Yaml,
+    item_validation_sets: &ItemValidationSets,
+    item_rules: &ItemRules,
+) -> ValidationResults {
+    let mut results = ValidationResults::new();
 
     // validate the presence of the keys that all items have
-    results.include(validate_key(&yaml, "Name", true));
-    results.include(validate_key(&yaml, "Item Number", true));
-    results.include(validate_key(&yaml, "Level", true));
+    results.include(validate_name(yaml));
+    results.include(validate_id(yaml));
+    results.include(validate_integer(
+        yaml,
+        "Item Number",
+        &ITEM_NUMBER_RANGE,
+        true,
+    ));
+    results.include(validate_integer(yaml, "Level", &LEVEL_RANGE, true));
+    results.include(validate_integer(yaml, "CC Cost", &CC_COST_RANGE, false));
     results.include(validate_list(
-        &yaml,
+        yaml,
         "Category",
         &item_validation_sets.categories,
         true,
     ));
     results.include(validate_list(
-        &yaml,
+        yaml,
         "Classifications",
         &item_validation_sets.classifications,
         true,
     ));
     results.include(validate_list(
-        &yaml,
+        yaml,
         "Element",
         &item_validation_sets.elements,
         true,
     ));
+    results.include(validate_element_values(yaml, "Element"));
 
-    let synthesis_required = synthesis::should_have_synthesis(&yaml);
-
+    // keys that depend on the item's classifications are required (or forbidden) by rules.yml,
+    // so their contents are only validated here when present
+    results.include(item_rules.validate(yaml));
     results.include(validate_list(
-        &yaml,
+        yaml,
         "Materials",
         &item_validation_sets.materials,
-        synthesis_required,
+        false,
+    ));
+    results.include(validate_key(yaml, "Gather Locations", false));
+    results.include(validate_list(
+        yaml,
+        "Gathering Tools",
+        &item_validation_sets.gathering_tools,
+        false,
     ));
-    if synthesis_required {
+    if !yaml["Synthesis"].is_badvalue() {
         results.include(synthesis::validate_synthesis(
             &yaml["Synthesis"],
             item_validation_sets,
         ));
     }
-    Ok(results)
+    if !yaml["Equipment"].is_badvalue() {
+        results.include(validate_equipment(&yaml["Equipment"], item_validation_sets));
+    }
+    results
+}
+
+/// validate the equipment details of an equip item: its base stats, and who can equip it
+fn validate_equipment(yaml: &Yaml, item_validation_sets: &ItemValidationSets) -> ValidationResults {
+    let mut results = ValidationResults::new();
+
+    let stats = &yaml["Stats"];
+    match stats {
+        Yaml::Hash(stat_map) => {
+            for stat in stat_map.keys() {
+                let stat = stat.as_str().unwrap_or("?");
+                if STAT_NAMES.contains(&stat) {
+                    results.include(validate_integer(stats, stat, &STAT_RANGE, true));
+                } else {
+                    results.valid = false;
+                    results.fail_messages.push(format!(
+                        "Stats: {} is an unknown stat (expected one of {})",
+                        stat,
+                        STAT_NAMES.join(", ")
+                    ));
+                }
+            }
+        }
+        Yaml::BadValue => {
+            results.valid = false;
+            results
+                .fail_messages
+                .push("'Stats' key is missing".to_string());
+        }
+        _ => {
+            results.valid = false;
+            results.fail_messages.push(format!(
+                "Stats must be a map of stat values, found {}",
+                describe_value(stats)
+            ));
+        }
+    }
+    results.include(validate_list(
+        yaml,
+        "Equippable By",
+        &item_validation_sets.characters,
+        false,
+    ));
+
+    // prefix validation messages with the Equipment key
+    results.pass_messages = results
+        .pass_messages
+        .drain(0..)
+        .map(|msg| format!("{}: {}", "Equipment", msg))
+        .collect();
+    results.fail_messages = results
+        .fail_messages
+        .drain(0..)
+        .map(|msg| format!("{}: {}", "Equipment", msg))
+        .collect();
+    results
+}
+
+/// the Name is either English text, or a map of language codes to names that includes English
+fn validate_name(yaml: &Yaml) -> ValidationResults {
+    let mut results = validate_key(yaml, "Name", true);
+    let name = &yaml["Name"];
+    if let Yaml::Hash(_) = name {
+        results.include(validate_translations(name, "Name"));
+        if name[DEFAULT_LANGUAGE].as_str().is_none() {
+            results.valid = false;
+            results.fail_messages.push(format!(
+                "Name: the English ({}) name is missing",
+                DEFAULT_LANGUAGE
+            ));
+        }
+    }
+    results
+}
+
+/// the optional stable ID, which other items can use to refer to the item whatever its name
+fn validate_id(yaml: &Yaml) -> ValidationResults {
+    let mut results = ValidationResults::new();
+    match &yaml["ID"] {
+        Yaml::BadValue => {}
+        Yaml::String(id)
+            if !id.is_empty()
+                && id
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-') =>
+        {
+            results.pass_messages.push(format!("ID is present: {}", id));
+        }
+        value => {
+            results.valid = false;
+            results.fail_messages.push(format!(
+                "ID must be lowercase letters, digits and hyphens (e.g. explosive-uni), found {}",
+                describe_value(value)
+            ));
+        }
+    }
+    results
+}
+
+/// translations are a map of language codes (e.g. `ja`) to text. Missing translations are fine.
+fn validate_translations(yaml: &Yaml, key: &str) -> ValidationResults {
+    let mut results = ValidationResults::new();
+    match yaml {
+        Yaml::BadValue => {}
+        Yaml::Hash(hash) => {
+            for (language, text) in hash {
+                let language = match language.as_str() {
+                    Some(language)
+                        if !language.is_empty()
+                            && language
+                                .chars()
+                                .all(|c| c.is_ascii_alphanumeric() || c == '-') =>
+                    {
+                        language
+                    }
+                    _ => {
+                        results.valid = false;
+                        results.fail_messages.push(format!(
+                            "{}: {} is not a language code (e.g. en or ja)",
+                            key,
+                            describe_value(language)
+                        ));
+                        continue;
+                    }
+                };
+                match text.as_str() {
+                    Some(text) if !text.trim().is_empty() => {}
+                    _ => {
+                        results.valid = false;
+                        results
+                            .fail_messages
+                            .push(format!("{}: the {} text is missing", key, language));
+                    }
+                }
+            }
+        }
+        _ => {
+            results.valid = false;
+            results.fail_messages.push(format!(
+                "{} must be a map of language codes to text, found {}",
+                key,
+                describe_value(yaml)
+            ));
+        }
+    }
+    results
 }
 
 /// Check to see if a particular key is a child of the given yaml position
@@ -121,6 +349,113 @@ fn validate_key(yaml: &Yaml, key: &str, required: bool) -> ValidationResults {
     results
 }
 
+/// Check to see if a particular key holds an integer within the given range
+/// (if the key isn't required, it's absence goes unremarked)
+fn validate_integer(
+    yaml: &Yaml,
+    key: &str,
+    range: &RangeInclusive<i64>,
+    required: bool,
+) -> ValidationResults {
+    let mut results = ValidationResults::new();
+
+    let value = &yaml[key];
+    match value {
+        Yaml::BadValue if required => {
+            results
+                .fail_messages
+                .push(format!("'{}' key is missing", key));
+            results.valid = false;
+        }
+        Yaml::BadValue => {} // if this key isn't required, BadValue means do nothing
+        Yaml::Integer(value) if range.contains(value) => {
+            results
+                .pass_messages
+                .push(format!("{} is present: {}", key, value));
+        }
+        Yaml::Integer(value) => {
+            results.fail_messages.push(format!(
+                "{} must be between {} and {}, found {}",
+                key,
+                range.start(),
+                range.end(),
+                value
+            ));
+            results.valid = false;
+        }
+        _ => {
+            results.fail_messages.push(format!(
+                "{} must be an integer between {} and {}, found {}",
+                key,
+                range.start(),
+                range.end(),
+                describe_value(value)
+            ));
+            results.valid = false;
+        }
+    }
+    results
+}
+
+/// Check the values of an element list (e.g. `- Fire: 2`) are integers in a sane range.
+/// Elements listed without a value (e.g. `- Fire`) are left to `validate_list`.
+fn validate_element_values(yaml: &Yaml, key: &str) -> ValidationResults {
+    let mut results = ValidationResults::new();
+
+    if let Yaml::Array(list) = &yaml[key] {
+        for hash_map in list.iter().filter_map(|value| value.as_hash()) {
+            for (element, value) in hash_map {
+                let element = element.as_str().unwrap_or("?");
+                match value {
+                    Yaml::Integer(value) if ELEMENT_VALUE_RANGE.contains(value) => {}
+                    Yaml::Integer(value) => {
+                        results.valid = false;
+                        results.fail_messages.push(format!(
+                            "{}: {} value must be between {} and {}, found {}",
+                            key,
+                            element,
+                            ELEMENT_VALUE_RANGE.start(),
+                            ELEMENT_VALUE_RANGE.end(),
+                            value
+                        ));
+                    }
+                    _ => {
+                        results.valid = false;
+                        results.fail_messages.push(format!(
+                            "{}: {} value must be an integer between {} and {}, found {}",
+                            key,
+                            element,
+                            ELEMENT_VALUE_RANGE.start(),
+                            ELEMENT_VALUE_RANGE.end(),
+                            describe_value(value)
+                        ));
+                    }
+                }
+            }
+        }
+        if results.valid {
+            results
+                .pass_messages
+                .push(format!("{} values are in range", key));
+        }
+    }
+    results
+}
+
+/// a short description of a YAML value, for diagnostics
+fn describe_value(value: &Yaml) -> String {
+    match value {
+        Yaml::Real(value) => format!("real number {}", value),
+        Yaml::Integer(value) => format!("integer {}", value),
+        Yaml::String(value) => format!("string '{}'", value),
+        Yaml::Boolean(value) => format!("boolean {}", value),
+        Yaml::Array(_) => "a list".to_string(),
+        Yaml::Hash(_) => "a map".to_string(),
+        Yaml::Null => "no value".to_string(),
+        _ => "an unreadable value".to_string(),
+    }
+}
+
 /// Check to see if a particular key is a child of the given yaml position
 /// (if the key isn't required, it's absence goes unremarked)
 fn validate_key_and_value(
@@ -146,9 +481,10 @@ fn validate_key_and_value(
                     .pass_messages
                     .push(format!("key {}: known value '{}'", key, value));
             } else {
-                results
-                    .fail_messages
-                    .push(format!("key {}: unknown value '{}' (typo, or item file needed)", key, value));
+                results.fail_messages.push(format!(
+                    "key {}: unknown value '{}' (typo, or item file needed)",
+                    key, value
+                ));
             }
         }
         _ => {
@@ -217,40 +553,53 @@ fn validate_list_values(
                 .pass_messages
                 .push(format!("{} values are known", key));
         }
-    } else if required {
-        results.valid = false; // not a yaml list (a vector)
+    } else if required || !value.is_badvalue() {
+        // not a yaml list (a vector), including a key left empty
+        results.valid = false;
         results.fail_messages.push(format!("{} is not a list", key));
     }
 
     results
 }
 
+/// fail for the keys of a map that aren't among the known ones, such as a key that's indented
+/// like the ones around it but belongs to a list above it
+fn validate_known_keys(yaml: &Yaml, known_keys: &[&str]) -> ValidationResults {
+    let mut results = ValidationResults::new();
+    if let Yaml::Hash(hash) = yaml {
+        for key in hash.keys() {
+            let key = key.as_str().unwrap_or("?");
+            if !known_keys.contains(&key) {
+                results.valid = false;
+                results
+                    .fail_messages
+                    .push(format!("{} is not a known key", key));
+            }
+        }
+    }
+    results
+}
+
 /// the synthesis part of validation is complex enough to warrant its own module
 mod synthesis {
-    use crate::validate_item::{validate_key, validate_key_and_value, validate_list};
+    use crate::validate_item::{
+        validate_element_values, validate_integer, validate_key_and_value, validate_known_keys,
+        validate_list, validate_translations,
+    };
     use crate::validate_item::{ItemValidationSets, ValidationResults};
+    use crate::validate_item::{LEVEL_RANGE, LOOP_INDEX_RANGE, REQUIRED_MATERIALS_RANGE};
     use std::collections::HashMap;
     use yaml_rust::Yaml;
 
-    /// if the item has a classification of "Materials", then the item is a gathered item. Otherwise,
-    /// it's a synthesized item.
-    pub fn should_have_synthesis(yaml: &Yaml) -> bool {
-        let value = &yaml["Classifications"];
-
-        let no_synthesis_classification = "Materials";
-        let mut has_synthesis_classification = true;
-
-        if let Yaml::Array(list) = value {
-            for value in list {
-                if let Yaml::String(value) = value {
-                    if value == no_synthesis_classification {
-                        has_synthesis_classification = false
-                    }
-                }
-            }
-        }
-        has_synthesis_classification
-    }
+    /// the keys of a material loop's details
+    const MATERIAL_LOOP_KEYS: [&str; 6] = [
+        "Distance",
+        "Position",
+        "Linked From Position",
+        "Material",
+        "Levels",
+        "Unlock",
+    ];
 
     pub fn validate_synthesis(
         yaml: &Yaml,
@@ -263,8 +612,18 @@ mod synthesis {
                 .fail_messages
                 .push(String::from("Synthesis key is missing."));
         } else {
-            results.include(validate_key(yaml, "Required Materials", true));
-            results.include(validate_key(yaml, "Required Alchemy Level", true));
+            results.include(validate_integer(
+                yaml,
+                "Required Materials",
+                &REQUIRED_MATERIALS_RANGE,
+                true,
+            ));
+            results.include(validate_integer(
+                yaml,
+                "Required Alchemy Level",
+                &LEVEL_RANGE,
+                true,
+            ));
             results.include(validate_material_loops(
                 &yaml["Material Loops"],
                 item_validation_sets,
@@ -321,10 +680,14 @@ mod synthesis {
     fn validate_unique_positions(material_loops: &[Yaml]) -> ValidationResults {
         let mut results = ValidationResults::new();
         let mut hash_map = HashMap::new();
-        for material_loop in material_loops.iter().filter_map(|yaml| yaml.as_hash()) {
-            // populate hash_map with position -> distance maps,
-            // ensuring unique positions for each loop
-            for (name, details) in material_loop {
+        let material_loops: Vec<_> = material_loops
+            .iter()
+            .filter_map(|yaml| yaml.as_hash())
+            .collect();
+        // populate hash_map with position -> distance maps,
+        // ensuring unique positions for each loop
+        for material_loop in &material_loops {
+            for (name, details) in *material_loop {
                 if let (Yaml::String(name), Yaml::Integer(position), Yaml::Integer(distance)) =
                     (name, &details["Position"], &details["Distance"])
                 {
@@ -342,8 +705,10 @@ mod synthesis {
                     }
                 }
             }
-            // now, confirm Linked From Position keys
-            for (name, details) in material_loop {
+        }
+        // now, confirm Linked From Position keys, which can link to loops later in the list
+        for material_loop in &material_loops {
+            for (name, details) in *material_loop {
                 if let (
                     Yaml::String(name),
                     Yaml::Integer(position),
@@ -355,12 +720,18 @@ mod synthesis {
                     &details["Linked From Position"],
                     &details["Distance"],
                 ) {
-                    let linked_from_distance = hash_map[linked_from_position];
-                    if linked_from_distance >= distance {
-                        results.fail_messages.push(format!(
-                            "loop '{}' position {}: linked to loop with same or higher distance value",
-                            name, position
-                        ));
+                    match hash_map.get(linked_from_position) {
+                        Some(linked_from_distance) if *linked_from_distance >= distance => {
+                            results.fail_messages.push(format!(
+                                "loop '{}' position {}: linked to loop with same or higher distance value",
+                                name, position
+                            ));
+                        }
+                        Some(_) => {}
+                        None => results.fail_messages.push(format!(
+                            "loop '{}' position {}: Linked From Position {} does not exist",
+                            name, position, linked_from_position
+                        )),
                     }
                 }
             }
@@ -376,8 +747,19 @@ mod synthesis {
 
         if let Yaml::Hash(material_loop_hash) = yaml {
             for (name, details) in material_loop_hash {
-                results.include(validate_key(details, "Distance", true));
-                results.include(validate_key(details, "Position", true));
+                results.include(validate_known_keys(details, &MATERIAL_LOOP_KEYS));
+                results.include(validate_integer(
+                    details,
+                    "Distance",
+                    &LOOP_INDEX_RANGE,
+                    true,
+                ));
+                results.include(validate_integer(
+                    details,
+                    "Position",
+                    &LOOP_INDEX_RANGE,
+                    true,
+                ));
                 // consider changing from list of 1 to validate key and value
                 results.include(validate_key_and_value(
                     details,
@@ -385,16 +767,22 @@ mod synthesis {
                     &item_validation_sets.materials,
                     true,
                 ));
-                results.include(validate_key(details, "Linked From Position", false));
+                results.include(validate_integer(
+                    details,
+                    "Linked From Position",
+                    &LOOP_INDEX_RANGE,
+                    false,
+                ));
                 results.include(validate_list(
                     details,
                     "Unlock",
                     &item_validation_sets.elements,
                     false,
                 ));
+                results.include(validate_element_values(details, "Unlock"));
                 results.include(validate_loop_levels(
                     &details["Levels"],
-                    &item_validation_sets,
+                    item_validation_sets,
                 ));
 
                 // prefix validation messages with the material loop name/type
@@ -429,14 +817,19 @@ mod synthesis {
                 for (loop_effect, details) in level {
                     if let Yaml::String(loop_effect) = loop_effect {
                         results.include(validate_list(
-                            &details,
+                            details,
                             "Element",
                             &item_validation_sets.elements,
                             true,
                         ));
+                        results.include(validate_element_values(details, "Element"));
+                        results.include(validate_translations(
+                            &details["Translations"],
+                            "Translations",
+                        ));
                         let is_recipe_morph = loop_effect == "Recipe Morph";
                         results.include(validate_key_and_value(
-                            &details,
+                            details,
                             "Recipe",
                             &item_validation_sets.materials,
                             is_recipe_morph,
@@ -460,3 +853,245 @@ mod synthesis {
         results
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_contents::{FileContents, FileError};
+    use crate::rules;
+    use crate::validation_sets;
+
+    const LISTS: &str = "
+Item Categories: [(Bomb), (Uni)]
+Item Classifications: [Attack Items]
+Elements: [Fire, Ice]
+";
+
+    /// the fail messages for an item file
+    fn fail_messages(contents: &str) -> Vec<String> {
+        let sets = validation_sets::build_item_validation_sets(LISTS).unwrap();
+        let rules = rules::build_item_rules("Rules: []").unwrap();
+        validate_item_contents(contents, &sets, &rules)
+            .unwrap()
+            .fail_messages
+    }
+
+    /// an item whose loops are at the given (position, distance, linked from position)
+    fn item_with_loops(loops: &[(i64, i64, Option<i64>)]) -> String {
+        let mut contents = "\
+Name: Explosive Uni
+Item Number: 1
+Level: 2
+Classifications: [Attack Items]
+Category: [(Bomb)]
+Element: [Fire]
+Synthesis:
+  Required Materials: 2
+  Required Alchemy Level: 1
+  Material Loops:
+"
+        .to_string();
+        for (index, (position, distance, linked_from)) in loops.iter().enumerate() {
+            contents.push_str(&format!(
+                "  - Effect {}:\n      Distance: {}\n      Position: {}\n",
+                index + 1,
+                distance,
+                position
+            ));
+            if let Some(linked_from) = linked_from {
+                contents.push_str(&format!("      Linked From Position: {}\n", linked_from));
+            }
+            contents.push_str(
+                "      Material: (Uni)\n      Levels:\n      - Scatter:\n          Element:\n          - Fire: 1\n",
+            );
+        }
+        contents
+    }
+
+    #[test]
+    fn linked_loops_validate() {
+        let contents = item_with_loops(&[(1, 0, None), (2, 1, Some(1)), (3, 2, Some(2))]);
+        assert_eq!(fail_messages(&contents), Vec::<String>::new());
+    }
+
+    #[test]
+    fn links_to_a_missing_position_fail() {
+        let contents = item_with_loops(&[(1, 0, None), (2, 1, Some(7))]);
+        assert_eq!(
+            fail_messages(&contents),
+            ["Synthesis: loop 'Effect 2' position 2: Linked From Position 7 does not exist"]
+        );
+    }
+
+    #[test]
+    fn links_to_later_loops_are_checked_by_distance() {
+        // the loop it's linked from can come later in the list
+        let contents = item_with_loops(&[(2, 1, Some(1)), (1, 0, None)]);
+        assert_eq!(fail_messages(&contents), Vec::<String>::new());
+
+        let contents = item_with_loops(&[(1, 1, Some(2)), (2, 1, None)]);
+        assert_eq!(
+            fail_messages(&contents),
+            ["Synthesis: loop 'Effect 1' position 1: linked to loop with same or higher distance value"]
+        );
+    }
+
+    #[test]
+    fn duplicate_positions_fail() {
+        let contents = item_with_loops(&[(1, 0, None), (1, 1, None)]);
+        assert_eq!(
+            fail_messages(&contents),
+            ["Synthesis: loop 'Effect 2' has duplicate position value: 1"]
+        );
+    }
+
+    /// true if `validate_integer` accepts the value under the given range
+    fn integer_passes(range: &RangeInclusive<i64>, value: &str) -> bool {
+        let docs = YamlLoader::load_from_str(&format!("Key: {}", value)).unwrap();
+        let results = validate_integer(&docs[0], "Key", range, true);
+        assert_eq!(results.valid, results.fail_messages.is_empty());
+        results.valid
+    }
+
+    #[test]
+    fn ranges_include_both_ends() {
+        for range in &[
+            ITEM_NUMBER_RANGE,
+            LEVEL_RANGE,
+            CC_COST_RANGE,
+            REQUIRED_MATERIALS_RANGE,
+            ELEMENT_VALUE_RANGE,
+            LOOP_INDEX_RANGE,
+            STAT_RANGE,
+        ] {
+            let (start, end) = (*range.start(), *range.end());
+            assert!(
+                !integer_passes(range, &(start - 1).to_string()),
+                "{:?}",
+                range
+            );
+            assert!(integer_passes(range, &start.to_string()), "{:?}", range);
+            assert!(integer_passes(range, &end.to_string()), "{:?}", range);
+            assert!(
+                !integer_passes(range, &(end + 1).to_string()),
+                "{:?}",
+                range
+            );
+            assert!(!integer_passes(range, "1.5"), "{:?}", range);
+        }
+    }
+
+    #[test]
+    fn range_boundaries() {
+        assert!(!integer_passes(&ITEM_NUMBER_RANGE, "0"));
+        assert!(integer_passes(&ITEM_NUMBER_RANGE, "1"));
+        assert!(integer_passes(&ITEM_NUMBER_RANGE, "999"));
+        assert!(!integer_passes(&ITEM_NUMBER_RANGE, "1000"));
+
+        assert!(!integer_passes(&LEVEL_RANGE, "0"));
+        assert!(integer_passes(&LEVEL_RANGE, "50"));
+        assert!(!integer_passes(&LEVEL_RANGE, "51"));
+
+        assert!(!integer_passes(&CC_COST_RANGE, "0"));
+        assert!(integer_passes(&CC_COST_RANGE, "10"));
+        assert!(!integer_passes(&CC_COST_RANGE, "11"));
+
+        assert!(!integer_passes(&ELEMENT_VALUE_RANGE, "0"));
+        assert!(integer_passes(&ELEMENT_VALUE_RANGE, "99"));
+        assert!(!integer_passes(&ELEMENT_VALUE_RANGE, "100"));
+
+        // loops start at Distance and Position 0
+        assert!(integer_passes(&LOOP_INDEX_RANGE, "0"));
+        assert!(integer_passes(&LOOP_INDEX_RANGE, "99"));
+        assert!(!integer_passes(&LOOP_INDEX_RANGE, "100"));
+
+        assert!(integer_passes(&STAT_RANGE, "0"));
+        assert!(integer_passes(&STAT_RANGE, "999"));
+        assert!(!integer_passes(&STAT_RANGE, "1000"));
+    }
+
+    #[test]
+    fn integers_of_the_wrong_type_fail() {
+        let docs =
+            YamlLoader::load_from_str("Real: 1.5\nString: one\nQuoted: '1'\nEmpty:\n").unwrap();
+        for (key, found) in &[
+            ("Real", "real number 1.5"),
+            ("String", "string 'one'"),
+            ("Quoted", "string '1'"),
+            ("Empty", "no value"),
+        ] {
+            let results = validate_integer(&docs[0], key, &LEVEL_RANGE, true);
+            assert!(!results.valid);
+            assert_eq!(
+                results.fail_messages,
+                [format!(
+                    "{} must be an integer between 1 and 50, found {}",
+                    key, found
+                )]
+            );
+        }
+    }
+
+    #[test]
+    fn missing_integers_fail_only_when_required() {
+        let docs = YamlLoader::load_from_str("Level: 1").unwrap();
+        let results = validate_integer(&docs[0], "CC Cost", &CC_COST_RANGE, true);
+        assert_eq!(results.fail_messages, ["'CC Cost' key is missing"]);
+
+        let results = validate_integer(&docs[0], "CC Cost", &CC_COST_RANGE, false);
+        assert!(results.valid);
+        assert!(results.fail_messages.is_empty() && results.pass_messages.is_empty());
+    }
+
+    #[test]
+    fn out_of_range_values_in_item_files_fail() {
+        let contents = item_with_loops(&[(1, 0, None)])
+            .replace("Item Number: 1", "Item Number: 1000")
+            .replace("- Fire: 1", "- Fire: 100")
+            .replace("Position: 1", "Position: 100");
+        assert_eq!(
+            fail_messages(&contents),
+            [
+                "Item Number must be between 1 and 999, found 1000",
+                "Synthesis: Effect 1: Position must be between 0 and 99, found 100",
+                "Synthesis: Effect 1: Scatter: Element: Fire value must be between 1 and 99, found 100",
+            ]
+        );
+    }
+
+    #[test]
+    fn empty_files_fail() {
+        for contents in &["", "# just a comment\n"] {
+            assert_eq!(
+                fail_messages(contents),
+                ["the file is empty, it should hold one item"]
+            );
+            let file = FileContents::parse(String::from("empty.yml"), contents.to_string());
+            assert!(matches!(file.document(), Err(FileError::Empty)));
+        }
+    }
+
+    #[test]
+    fn further_documents_fail_instead_of_being_ignored() {
+        let item = item_with_loops(&[(1, 0, None)]);
+        let contents = format!("{}---\nName: Second Uni\n", item);
+        assert_eq!(
+            fail_messages(&contents),
+            ["the file has 2 YAML documents, only the first is read (put each item in its own file)"]
+        );
+        let file = FileContents::parse(String::from("two.yml"), contents);
+        assert_eq!(
+            file.document().unwrap()["Name"].as_str(),
+            Some("Explosive Uni")
+        );
+    }
+
+    #[test]
+    fn empty_lists_and_rules_load() {
+        assert!(validation_sets::build_item_validation_sets("")
+            .unwrap()
+            .elements
+            .is_empty());
+        assert!(rules::build_item_rules("").unwrap().rules.is_empty());
+    }
+}
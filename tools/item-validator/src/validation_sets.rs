@@ -2,6 +2,7 @@ use std::collections::HashSet;
 
 extern crate yaml_rust;
 use crate::file_contents::FileContents;
+use crate::model::Item;
 use yaml_rust::{ScanError, Yaml, YamlLoader};
 
 pub struct ItemValidationSets {
@@ -9,6 +10,7 @@ pub struct ItemValidationSets {
     pub categories: HashSet<String>,
     pub classifications: HashSet<String>,
     pub gathering_tools: HashSet<String>,
+    pub characters: HashSet<String>,
     pub materials: HashSet<String>,
 }
 
@@ -19,6 +21,7 @@ impl ItemValidationSets {
             categories: HashSet::new(),
             classifications: HashSet::new(),
             gathering_tools: HashSet::new(),
+            characters: HashSet::new(),
             materials: HashSet::new(),
         }
     }
@@ -26,22 +29,23 @@ impl ItemValidationSets {
 
 pub fn build_item_validation_sets(contents: &str) -> Result<ItemValidationSets, ScanError> {
     let docs = YamlLoader::load_from_str(contents)?;
-    // YAML files can actually contain multiple files inside, we want the first one
-    let yaml = &docs[0];
+    // YAML files can actually contain multiple files inside, we want the first one (if any)
+    let yaml = docs.first().unwrap_or(&Yaml::BadValue);
     let mut validation_sets = ItemValidationSets::new();
-    add_to_set(&yaml, "Item Categories", &mut validation_sets.categories);
-    add_to_set(&yaml, "Item Categories", &mut validation_sets.materials);
+    add_to_set(yaml, "Item Categories", &mut validation_sets.categories);
+    add_to_set(yaml, "Item Categories", &mut validation_sets.materials);
     add_to_set(
-        &yaml,
+        yaml,
         "Item Classifications",
         &mut validation_sets.classifications,
     );
-    add_to_set(&yaml, "Elements", &mut validation_sets.elements);
+    add_to_set(yaml, "Elements", &mut validation_sets.elements);
     add_to_set(
-        &yaml,
+        yaml,
         "Gathering Tools",
         &mut validation_sets.gathering_tools,
     );
+    add_to_set(yaml, "Characters", &mut validation_sets.characters);
     Ok(validation_sets)
 }
 
@@ -58,17 +62,15 @@ fn add_to_set(yaml: &Yaml, key: &str, set: &mut HashSet<String>) {
     set.shrink_to_fit();
 }
 
+/// add the items to the materials, by their IDs and their names in every language. Files that
+/// couldn't be loaded are skipped.
 pub fn add_materials_to_validation_sets(
     item_validation_sets: &mut ItemValidationSets,
     item_contents: &[FileContents],
-) -> Result<(), ScanError> {
-    for file in item_contents {
-        let docs = YamlLoader::load_from_str(&file.contents)?;
-        // YAML files can actually contain multiple files inside, we want the first one
-        let yaml = &docs[0];
-        if let Yaml::String(material) = &yaml["Name"] {
-            item_validation_sets.materials.insert(material.to_string());
-        }
+) {
+    for yaml in item_contents.iter().filter_map(|file| file.document().ok()) {
+        item_validation_sets
+            .materials
+            .extend(Item::from_yaml(yaml).reference_names());
     }
-    Ok(())
 }
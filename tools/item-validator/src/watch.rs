@@ -0,0 +1,93 @@
+//! Watching the data folder for changes to item files, lists.yml, rules.yml and the ignore file.
+
+use crate::file_contents::{self, IGNORE_FILE};
+
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::time::Duration;
+
+/// editors often write a file in several steps, so changes are gathered until things go quiet
+const SETTLE_TIME: Duration = Duration::from_millis(200);
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Change {
+    Lists,
+    Rules,
+    Ignore,
+    /// an item file was added, changed or removed, by its path within the items folder
+    ItemFile(String),
+    /// a folder in the items folder was added, removed or renamed, so the item files in it all
+    /// changed. A removed path without an extension might have been a folder, so it's one too.
+    Folder(String),
+}
+
+pub struct DataWatcher {
+    items_folder: PathBuf,
+    // kept so the watches stay active
+    _watcher: RecommendedWatcher,
+    events: Receiver<notify::Result<notify::Event>>,
+}
+
+impl DataWatcher {
+    pub fn new(data_folder: &str) -> notify::Result<DataWatcher> {
+        let (sender, events) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(sender)?;
+        let items_folder = Path::new(data_folder).join("items");
+        // the data folder itself, rather than lists.yml and rules.yml, so files that are replaced
+        // when they're saved are still watched
+        watcher.watch(Path::new(data_folder), RecursiveMode::NonRecursive)?;
+        watcher.watch(&items_folder, RecursiveMode::Recursive)?;
+        Ok(DataWatcher {
+            items_folder,
+            _watcher: watcher,
+            events,
+        })
+    }
+
+    /// wait for the next batch of changes. Returns an error if watching failed.
+    pub fn next_changes(&self) -> notify::Result<BTreeSet<Change>> {
+        let mut changes = BTreeSet::new();
+        loop {
+            // wait for something to happen, then gather changes until things settle
+            let event = if changes.is_empty() {
+                self.events.recv().map_err(|_| stopped())?
+            } else {
+                match self.events.recv_timeout(SETTLE_TIME) {
+                    Ok(event) => event,
+                    Err(RecvTimeoutError::Timeout) => return Ok(changes),
+                    Err(RecvTimeoutError::Disconnected) => return Err(stopped()),
+                }
+            }?;
+            if !matches!(event.kind, EventKind::Access(_)) {
+                changes.extend(event.paths.iter().filter_map(|path| self.change(path)));
+            }
+        }
+    }
+
+    fn change(&self, path: &Path) -> Option<Change> {
+        if path.starts_with(&self.items_folder) {
+            let name = file_contents::item_file_name(&self.items_folder, path)?;
+            if name.is_empty() {
+                return None;
+            }
+            return if path.is_dir() || path.extension().is_none() {
+                Some(Change::Folder(name))
+            } else {
+                Some(Change::ItemFile(name))
+            };
+        }
+        match path.file_name()?.to_str()? {
+            "lists.yml" => Some(Change::Lists),
+            "rules.yml" => Some(Change::Rules),
+            IGNORE_FILE => Some(Change::Ignore),
+            _ => None,
+        }
+    }
+}
+
+fn stopped() -> notify::Error {
+    notify::Error::generic("the file watcher stopped")
+}
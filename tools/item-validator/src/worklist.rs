@@ -0,0 +1,186 @@
+//! The missing-item worklist: every item that's referred to (as a material or a Recipe Morph
+//! target) but has no item file, as Markdown for contributors to pick work from.
+//!
+//! Items referenced most often come first, since adding them fixes the most files. A missing name
+//! that's close to a known material is more likely a typo than a missing file, so the closest
+//! known names are suggested alongside it.
+
+use crate::dataset::Dataset;
+use crate::item_file::slug;
+
+use std::collections::HashSet;
+
+/// how many typo suggestions are listed for a missing item
+const MAX_SUGGESTIONS: usize = 3;
+
+pub struct MissingItem {
+    pub name: String,
+    /// the items referring to the missing item
+    pub referenced_by: Vec<String>,
+    /// known materials the name might be a typo of, closest first
+    pub suggestions: Vec<String>,
+}
+
+/// the missing items of the dataset, most referenced first
+pub fn missing_items(dataset: &Dataset) -> Vec<MissingItem> {
+    let mut missing: Vec<MissingItem> = dataset
+        .missing_items()
+        .into_iter()
+        .map(|(name, referenced_by)| MissingItem {
+            suggestions: suggestions(&name, &dataset.item_validation_sets.materials),
+            name,
+            referenced_by,
+        })
+        .collect();
+    // missing_items() is sorted by name, which the stable sort keeps for equal counts
+    missing.sort_by_key(|item| std::cmp::Reverse(item.referenced_by.len()));
+    missing
+}
+
+/// the worklist as a Markdown document
+pub fn markdown(missing: &[MissingItem]) -> String {
+    let mut markdown = String::from("# Missing items\n\n");
+    if missing.is_empty() {
+        markdown.push_str("Every referenced item has an item file.\n");
+        return markdown;
+    }
+    markdown.push_str(&format!(
+        "{} items are referenced but have no item file. Check the typo suggestions before \
+         creating a file: fixing the referring items may be all that's needed.\n\n",
+        missing.len()
+    ));
+    markdown.push_str("| Item | References | Referenced by | Possible typo of |\n");
+    markdown.push_str("| --- | --- | --- | --- |\n");
+    for item in missing {
+        markdown.push_str(&format!(
+            "| {} | {} | {} | {} |\n",
+            cell(&item.name),
+            item.referenced_by.len(),
+            cell(&item.referenced_by.join(", ")),
+            cell(&item.suggestions.join(", "))
+        ));
+    }
+    markdown
+}
+
+/// the known names within a few edits of the given name, closest first. Case is ignored, so
+/// `red supplement` finds `Red Supplement`. The known materials hold items by ID as well as by
+/// name, so an item is only suggested once, by whichever comes first.
+fn suggestions(name: &str, known: &HashSet<String>) -> Vec<String> {
+    // allow one edit, plus one for every five characters
+    let max_distance = 1 + name.chars().count() / 5;
+    let name = name.to_lowercase();
+    let mut close: Vec<(usize, &String)> = known
+        .iter()
+        .map(|known| (strsim::levenshtein(&name, &known.to_lowercase()), known))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .collect();
+    close.sort();
+
+    // IDs are the slugs of English names; categories are kept apart from items of the same name
+    let mut seen = HashSet::new();
+    close
+        .into_iter()
+        .filter(|(_, known)| seen.insert((known.starts_with('('), slug(known))))
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, known)| known.clone())
+        .collect()
+}
+
+/// escape the characters that would break a Markdown table cell
+fn cell(text: &str) -> String {
+    text.replace('|', "\\|")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn materials(names: &[&str]) -> HashSet<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn close_names_are_suggested_closest_first() {
+        let known = materials(&[
+            "Red Supplement",
+            "red-supplement",
+            "Blue Supplement",
+            "(Uni)",
+            "Uni",
+            "uni",
+        ]);
+        // items are suggested by name or ID, not both
+        assert_eq!(suggestions("red suplement", &known), ["Red Supplement"]);
+        assert_eq!(suggestions("Uno", &known), ["Uni"]);
+        assert_eq!(suggestions("(Uno)", &known), ["(Uni)"]);
+        assert_eq!(suggestions("Gunpowder", &known), Vec::<String>::new());
+    }
+
+    #[test]
+    fn the_worklist_is_a_markdown_table() {
+        let missing = vec![MissingItem {
+            name: String::from("Craft | Plus"),
+            referenced_by: vec![String::from("Explosive Uni"), String::from("Uni Bag")],
+            suggestions: vec![String::from("Craft")],
+        }];
+        assert_eq!(
+            markdown(&missing),
+            "# Missing items\n\n\
+             1 items are referenced but have no item file. Check the typo suggestions before \
+             creating a file: fixing the referring items may be all that's needed.\n\n\
+             | Item | References | Referenced by | Possible typo of |\n\
+             | --- | --- | --- | --- |\n\
+             | Craft \\| Plus | 2 | Explosive Uni, Uni Bag | Craft |\n"
+        );
+        assert_eq!(
+            markdown(&[]),
+            "# Missing items\n\nEvery referenced item has an item file.\n"
+        );
+    }
+
+    #[test]
+    fn the_most_referenced_items_come_first() {
+        let folder =
+            std::env::temp_dir().join(format!("item-validator-worklist-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&folder);
+        fs::create_dir_all(folder.join("items")).unwrap();
+        fs::write(folder.join("lists.yml"), "Item Categories: [(Uni)]\n").unwrap();
+        fs::write(folder.join("rules.yml"), "Rules: []\n").unwrap();
+        let item = |name: &str, materials: &str| {
+            format!(
+                "Name: {}\nItem Number: 1\nLevel: 1\nMaterials: [{}]\n",
+                name, materials
+            )
+        };
+        for (file, contents) in &[
+            ("001-uni.yml", item("Uni", "(Uni)")),
+            ("002-bomb.yml", item("Bomb", "Gunpowder, Unii")),
+            ("003-big-bomb.yml", item("Big Bomb", "Gunpowder, Bomb")),
+        ] {
+            fs::write(folder.join("items").join(file), contents).unwrap();
+        }
+        let dataset = Dataset::load(&folder.to_string_lossy()).unwrap();
+        fs::remove_dir_all(&folder).unwrap();
+
+        let missing = missing_items(&dataset);
+        let summary: Vec<(&str, usize, &[String])> = missing
+            .iter()
+            .map(|item| {
+                (
+                    item.name.as_str(),
+                    item.referenced_by.len(),
+                    &item.suggestions[..],
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("Gunpowder", 2, &[][..]),
+                ("Unii", 1, &[String::from("Uni")][..]),
+            ]
+        );
+    }
+}
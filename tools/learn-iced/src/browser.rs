@@ -0,0 +1,470 @@
+//! Browsing the dataset: a filterable list of items, and the details of the selected item.
+//!
+//! The search box searches names, effects, categories and gather locations, forgiving typos, and
+//! lists the best matches first along with what they matched. A number finds the item with that
+//! item number.
+//!
+//! Materials and Recipe Morph targets in the details are links: items open their details, and
+//! categories filter the list down to the items in them.
+
+use crate::{style, Field};
+
+use iced::{
+    button, pick_list, scrollable, Align, Button, Column, Element, Length,
+    PickList, Row, Scrollable, Text, TextInput,
+};
+use item_validator::model::{self, ElementValue, Item, MaterialKind};
+use item_validator::search::{SearchField, SearchIndex};
+use item_validator::validation_sets::ItemValidationSets;
+
+use std::collections::HashSet;
+
+/// the pick list option that turns a filter off
+const ANY: &str = "Any";
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    SearchChanged(String),
+    CategoryFiltered(String),
+    ElementFiltered(String),
+    ClassificationFiltered(String),
+    ItemSelected(usize),
+    MaterialFollowed(String),
+}
+
+/// what the list is filtered by, None meaning any. The search is done with the search index.
+#[derive(Default)]
+struct Filter {
+    search: String,
+    category: Option<String>,
+    element: Option<String>,
+    classification: Option<String>,
+}
+
+impl Filter {
+    fn matches(&self, item: &Item) -> bool {
+        self.category
+            .as_ref()
+            .map_or(true, |category| item.categories.contains(category))
+            && self.element.as_ref().map_or(true, |element| {
+                item.elements.iter().any(|e| &e.element == element)
+            })
+            && self.classification.as_ref().map_or(true, |classification| {
+                item.has_classification(classification)
+            })
+    }
+}
+
+pub struct Browser {
+    /// every item, in item number order
+    items: Vec<Item>,
+    search_index: SearchIndex,
+    selected: Option<usize>,
+    filter: Filter,
+
+    category_options: Vec<String>,
+    element_options: Vec<String>,
+    classification_options: Vec<String>,
+
+    search: Field,
+    category_picker: pick_list::State<String>,
+    element_picker: pick_list::State<String>,
+    classification_picker: pick_list::State<String>,
+    list_scroll: scrollable::State,
+    details_scroll: scrollable::State,
+    item_buttons: Vec<button::State>,
+    link_buttons: Vec<button::State>,
+}
+
+impl Browser {
+    pub fn new(items: Vec<Item>, sets: &ItemValidationSets) -> Browser {
+        let mut browser = Browser {
+            items: Vec::new(),
+            search_index: SearchIndex::new(&[]),
+            selected: None,
+            filter: Filter::default(),
+
+            category_options: options(&sets.categories),
+            element_options: options(&sets.elements),
+            classification_options: options(&sets.classifications),
+
+            search: Field::default(),
+            category_picker: pick_list::State::default(),
+            element_picker: pick_list::State::default(),
+            classification_picker: pick_list::State::default(),
+            list_scroll: scrollable::State::new(),
+            details_scroll: scrollable::State::new(),
+            item_buttons: Vec::new(),
+            link_buttons: Vec::new(),
+        };
+        browser.set_items(items);
+        browser
+    }
+
+    /// replace the items, e.g. after the data folder has been reloaded
+    pub fn set_items(&mut self, mut items: Vec<Item>) {
+        items.sort_by_key(|item| item.item_number);
+        self.selected = None;
+        self.item_buttons
+            .resize_with(items.len(), button::State::new);
+        self.search_index = SearchIndex::new(&items);
+        self.items = items;
+    }
+
+    /// the indexes of the items to list, in order, with what the search matched when it isn't
+    /// the item's name
+    fn rows(&self) -> Vec<(usize, Option<String>)> {
+        let search = self.filter.search.trim();
+        let rows: Vec<(usize, Option<String>)> = if search.is_empty() {
+            (0..self.items.len()).map(|index| (index, None)).collect()
+        } else if let Ok(number) = search.parse::<i64>() {
+            self.items
+                .iter()
+                .enumerate()
+                .filter(|(_, item)| item.item_number == number)
+                .map(|(index, _)| (index, None))
+                .collect()
+        } else {
+            self.search_index
+                .search(search)
+                .into_iter()
+                .map(|result| {
+                    let is_name = result.field == SearchField::Name
+                        && result.text == self.items[result.item].name;
+                    let matched = if is_name {
+                        None
+                    } else {
+                        Some(format!(
+                            "{}: {}",
+                            result.field.as_str(),
+                            result.text
+                        ))
+                    };
+                    (result.item, matched)
+                })
+                .collect()
+        };
+        rows.into_iter()
+            .filter(|(index, _)| self.filter.matches(&self.items[*index]))
+            .collect()
+    }
+
+    pub fn update(&mut self, message: Message) {
+        match message {
+            Message::SearchChanged(value) => {
+                self.search.value = value.clone();
+                self.filter.search = value;
+            }
+            Message::CategoryFiltered(value) => {
+                self.filter.category = filter_value(value)
+            }
+            Message::ElementFiltered(value) => {
+                self.filter.element = filter_value(value)
+            }
+            Message::ClassificationFiltered(value) => {
+                self.filter.classification = filter_value(value)
+            }
+            Message::ItemSelected(index) => self.selected = Some(index),
+            Message::MaterialFollowed(material) => {
+                let (kind, name) = model::parse_material(&material);
+                match kind {
+                    MaterialKind::Category => {
+                        self.filter.category = Some(material.clone())
+                    }
+                    MaterialKind::Item => {
+                        if let Some(index) =
+                            self.items.iter().position(|item| item.name == name)
+                        {
+                            self.selected = Some(index);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn view(&mut self, theme: style::Theme) -> Element<Message> {
+        let rows = self.rows();
+        let filters = Column::new()
+            .spacing(5)
+            .push(
+                TextInput::new(
+                    &mut self.search.state,
+                    "Search names, effects and locations, or a number",
+                    &self.search.value,
+                    Message::SearchChanged,
+                )
+                .padding(5)
+                .style(theme),
+            )
+            .push(filter_row(
+                "Category",
+                &mut self.category_picker,
+                &self.category_options,
+                &self.filter.category,
+                Message::CategoryFiltered,
+            ))
+            .push(filter_row(
+                "Element",
+                &mut self.element_picker,
+                &self.element_options,
+                &self.filter.element,
+                Message::ElementFiltered,
+            ))
+            .push(filter_row(
+                "Classification",
+                &mut self.classification_picker,
+                &self.classification_options,
+                &self.filter.classification,
+                Message::ClassificationFiltered,
+            ));
+
+        // search results come in any order, so each row takes its item's button
+        let mut item_buttons: Vec<Option<&mut button::State>> =
+            self.item_buttons.iter_mut().map(Some).collect();
+        let mut list = Scrollable::new(&mut self.list_scroll)
+            .spacing(2)
+            .height(Length::Fill)
+            .style(theme);
+        for (index, matched) in rows {
+            let item = &self.items[index];
+            let label = match matched {
+                Some(matched) => format!(
+                    "{:03} {} ({})",
+                    item.item_number, item.name, matched
+                ),
+                None => format!("{:03} {}", item.item_number, item.name),
+            };
+            if let Some(state) = item_buttons[index].take() {
+                list = list.push(
+                    Button::new(state, Text::new(label))
+                        .width(Length::Fill)
+                        .on_press(Message::ItemSelected(index))
+                        .style(theme),
+                );
+            }
+        }
+
+        let details: Element<Message> = match self.selected {
+            Some(index) => {
+                let item = &self.items[index];
+                self.link_buttons
+                    .resize_with(links(item).len(), button::State::new);
+                Scrollable::new(&mut self.details_scroll)
+                    .height(Length::Fill)
+                    .style(theme)
+                    .push(details(item, &mut self.link_buttons, theme))
+                    .into()
+            }
+            None => Text::new("Select an item to see its details").into(),
+        };
+
+        Row::new()
+            .spacing(20)
+            .padding(20)
+            .push(
+                Column::new()
+                    .spacing(10)
+                    .width(Length::FillPortion(1))
+                    .push(filters)
+                    .push(list),
+            )
+            .push(Column::new().width(Length::FillPortion(2)).push(details))
+            .into()
+    }
+}
+
+/// the materials and recipes the details of an item link to, in the order they're shown
+fn links(item: &Item) -> Vec<&str> {
+    let mut links: Vec<&str> = item
+        .categories
+        .iter()
+        .chain(&item.materials)
+        .map(String::as_str)
+        .collect();
+    for material_loop in item
+        .synthesis
+        .iter()
+        .flat_map(|synthesis| &synthesis.material_loops)
+    {
+        links.push(&material_loop.material);
+        links.extend(
+            material_loop
+                .levels
+                .iter()
+                .filter_map(|level| level.recipe.as_deref()),
+        );
+    }
+    links
+}
+
+fn details<'a>(
+    item: &Item,
+    link_buttons: &'a mut [button::State],
+    theme: style::Theme,
+) -> Column<'a, Message> {
+    let mut link_buttons = link_buttons.iter_mut();
+    let mut link = |material: &str| -> Element<'a, Message> {
+        match link_buttons.next() {
+            Some(state) => Button::new(state, Text::new(material))
+                .padding(2)
+                .on_press(Message::MaterialFollowed(material.to_string()))
+                .style(theme)
+                .into(),
+            None => Text::new(material).into(),
+        }
+    };
+
+    let mut column = Column::new()
+        .spacing(10)
+        .push(Text::new(&item.name).size(30))
+        .push(Text::new(format!(
+            "No. {}, Level {}",
+            item.item_number, item.level
+        )))
+        .push(Text::new(format!(
+            "Classifications: {}",
+            item.classifications.join(", ")
+        )));
+    column = column.push(
+        item.categories.iter().fold(
+            Row::new()
+                .spacing(5)
+                .align_items(Align::Center)
+                .push(Text::new("Category:")),
+            |row, category| row.push(link(category)),
+        ),
+    );
+    column = column
+        .push(Text::new(format!("Element: {}", elements(&item.elements))));
+    if let Some(cc_cost) = item.cc_cost {
+        column = column.push(Text::new(format!("CC Cost: {}", cc_cost)));
+    }
+    if !item.materials.is_empty() {
+        column = column.push(
+            item.materials.iter().fold(
+                Row::new()
+                    .spacing(5)
+                    .align_items(Align::Center)
+                    .push(Text::new("Materials:")),
+                |row, material| row.push(link(material)),
+            ),
+        );
+    }
+    if !item.gather_locations.is_empty() {
+        column = column.push(Text::new(format!(
+            "Gather Locations: {}",
+            item.gather_locations.join(", ")
+        )));
+    }
+    if !item.gathering_tools.is_empty() {
+        column = column.push(Text::new(format!(
+            "Gathering Tools: {}",
+            item.gathering_tools.join(", ")
+        )));
+    }
+    if let Some(equipment) = &item.equipment {
+        let stats: Vec<String> = model::STAT_NAMES
+            .iter()
+            .map(|stat| {
+                format!("{} {}", stat, equipment.stats.get(stat).unwrap_or(0))
+            })
+            .collect();
+        column = column.push(Text::new(format!("Stats: {}", stats.join(", "))));
+        if !equipment.equippable_by.is_empty() {
+            column = column.push(Text::new(format!(
+                "Equippable By: {}",
+                equipment.equippable_by.join(", ")
+            )));
+        }
+    }
+
+    if let Some(synthesis) = &item.synthesis {
+        column = column.push(Text::new("Synthesis").size(24)).push(Text::new(
+            format!(
+                "Required Materials: {}, Required Alchemy Level: {}",
+                synthesis.required_materials, synthesis.required_alchemy_level
+            ),
+        ));
+        for material_loop in &synthesis.material_loops {
+            let mut loop_column = Column::new().spacing(2).push(
+                Row::new()
+                    .spacing(5)
+                    .align_items(Align::Center)
+                    .push(Text::new(format!(
+                        "{}. {}:",
+                        material_loop.position, material_loop.name
+                    )))
+                    .push(link(&material_loop.material)),
+            );
+            for level in &material_loop.levels {
+                let mut row = Row::new()
+                    .spacing(5)
+                    .align_items(Align::Center)
+                    .push(Text::new(format!(
+                        "    {} ({})",
+                        level.effect,
+                        elements(&level.elements)
+                    )));
+                if let Some(recipe) = &level.recipe {
+                    row = row.push(Text::new("\u{2192}")).push(link(recipe));
+                }
+                loop_column = loop_column.push(row);
+            }
+            if !material_loop.unlock.is_empty() {
+                loop_column = loop_column.push(Text::new(format!(
+                    "    Unlock: {}",
+                    elements(&material_loop.unlock)
+                )));
+            }
+            column = column.push(loop_column);
+        }
+    }
+    column
+}
+
+fn filter_row<'a>(
+    label: &str,
+    picker: &'a mut pick_list::State<String>,
+    options: &'a [String],
+    selected: &Option<String>,
+    on_selected: fn(String) -> Message,
+) -> Row<'a, Message> {
+    Row::new()
+        .spacing(10)
+        .align_items(Align::Center)
+        .push(Text::new(label).width(Length::Units(110)))
+        .push(PickList::new(
+            picker,
+            options,
+            Some(selected.clone().unwrap_or_else(|| ANY.to_string())),
+            on_selected,
+        ))
+}
+
+fn filter_value(value: String) -> Option<String> {
+    if value == ANY {
+        None
+    } else {
+        Some(value)
+    }
+}
+
+/// the values of a list, sorted, after the option that turns the filter off
+fn options(set: &HashSet<String>) -> Vec<String> {
+    let mut values: Vec<String> = set.iter().cloned().collect();
+    values.sort();
+    values.insert(0, ANY.to_string());
+    values
+}
+
+fn elements(elements: &[ElementValue]) -> String {
+    let elements: Vec<String> = elements
+        .iter()
+        .map(|element| match element.value {
+            Some(value) => format!("{} {}", element.element, value),
+            None => element.element.clone(),
+        })
+        .collect();
+    elements.join(", ")
+}
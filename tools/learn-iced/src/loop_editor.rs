@@ -0,0 +1,689 @@
+//! A visual editor for the material loops of a synthesis.
+//!
+//! Loops are nodes on a canvas laid out in columns by Distance, like the synthesis screen. Click
+//! an empty spot in a column to add a loop there, click a loop to edit it, and drag from one loop
+//! to a loop in the next column to link them. Positions and Linked From Positions are worked out
+//! from the drawing when the item is written.
+
+use crate::{style, Field};
+
+use iced::canvas::{self, Canvas, Cursor, Frame, Geometry, Path, Stroke};
+use iced::{
+    button, mouse, pick_list, Align, Button, Color, Column, Element,
+    HorizontalAlignment, Length, PickList, Point, Rectangle, Row, Text,
+    TextInput, VerticalAlignment,
+};
+use item_validator::model::{ElementValue, LoopLevel, MaterialLoop, Synthesis};
+use item_validator::{item_file, render};
+
+const COLUMN_WIDTH: f32 = 160.0;
+const COLUMNS: i64 = 6;
+const RADIUS: f32 = 20.0;
+const CANVAS_HEIGHT: u16 = 400;
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    LoopAdded(i64, f32),
+    LoopSelected(usize),
+    LoopLinked(usize, usize),
+    LoopUnlinked,
+    LoopRemoved,
+    RequiredMaterialsChanged(String),
+    AlchemyLevelChanged(String),
+    NameChanged(String),
+    MaterialChanged(String),
+    LevelAdded,
+    LevelEffectChanged(usize, String),
+    LevelElementChanged(usize, String),
+    LevelValueChanged(usize, String),
+    LevelRecipeChanged(usize, String),
+    LevelRemoved(usize),
+    UnlockAdded(String),
+    UnlockValueChanged(usize, String),
+    UnlockRemoved(usize),
+}
+
+/// the loops as drawn on the canvas
+#[derive(Default)]
+struct LoopGraph {
+    nodes: Vec<Node>,
+    selected: Option<usize>,
+    /// the loop a link is being dragged from
+    dragging_from: Option<usize>,
+}
+
+struct Node {
+    distance: i64,
+    y: f32,
+    /// the index of the loop this loop is linked from
+    linked_from: Option<usize>,
+    label: String,
+    color: Color,
+}
+
+/// the details of a loop, edited beside the canvas
+#[derive(Default)]
+struct LoopForm {
+    name: Field,
+    material: Field,
+    levels: Vec<LevelForm>,
+    add_level: button::State,
+    unlock: Vec<UnlockForm>,
+    unlock_picker: pick_list::State<String>,
+    unlink: button::State,
+    remove: button::State,
+}
+
+#[derive(Default)]
+struct LevelForm {
+    effect: Field,
+    element: Option<String>,
+    element_picker: pick_list::State<String>,
+    value: Field,
+    recipe: Field,
+    remove: button::State,
+}
+
+struct UnlockForm {
+    element: String,
+    value: Field,
+    remove: button::State,
+}
+
+pub struct LoopEditor {
+    element_options: Vec<String>,
+    graph: LoopGraph,
+    forms: Vec<LoopForm>,
+    required_materials: Field,
+    alchemy_level: Field,
+}
+
+impl LoopEditor {
+    pub fn new(element_options: Vec<String>) -> LoopEditor {
+        LoopEditor {
+            element_options,
+            graph: LoopGraph::default(),
+            forms: Vec::new(),
+            required_materials: Field::default(),
+            alchemy_level: Field::default(),
+        }
+    }
+
+    pub fn update(&mut self, message: Message) {
+        let selected = self.graph.selected;
+        match message {
+            Message::LoopAdded(distance, y) => {
+                self.graph.nodes.push(Node {
+                    distance,
+                    y,
+                    linked_from: None,
+                    label: String::new(),
+                    color: Color::BLACK,
+                });
+                self.forms.push(LoopForm::default());
+                self.graph.selected = Some(self.graph.nodes.len() - 1);
+            }
+            Message::LoopSelected(index) => self.graph.selected = Some(index),
+            Message::LoopLinked(from, to) => {
+                // a loop is linked from the column just before its own, which also rules
+                // out cycles
+                if self.graph.nodes[from].distance + 1
+                    == self.graph.nodes[to].distance
+                {
+                    self.graph.nodes[to].linked_from = Some(from);
+                }
+            }
+            Message::LoopUnlinked => {
+                if let Some(index) = selected {
+                    self.graph.nodes[index].linked_from = None;
+                }
+            }
+            Message::LoopRemoved => {
+                if let Some(index) = selected {
+                    self.remove_loop(index);
+                }
+            }
+            Message::RequiredMaterialsChanged(value) => {
+                self.required_materials.value = value
+            }
+            Message::AlchemyLevelChanged(value) => {
+                self.alchemy_level.value = value
+            }
+            message => {
+                if let Some(index) = selected {
+                    self.forms[index].update(message);
+                }
+            }
+        }
+        self.update_labels();
+    }
+
+    fn remove_loop(&mut self, index: usize) {
+        self.graph.nodes.remove(index);
+        self.forms.remove(index);
+        for node in &mut self.graph.nodes {
+            node.linked_from = match node.linked_from {
+                Some(from) if from == index => None,
+                Some(from) if from > index => Some(from - 1),
+                linked_from => linked_from,
+            };
+        }
+        self.graph.selected = None;
+    }
+
+    /// the canvas shows each loop's name and the color of its element
+    fn update_labels(&mut self) {
+        for (node, form) in self.graph.nodes.iter_mut().zip(&self.forms) {
+            node.label = form.name.value.trim().to_string();
+            node.color = form
+                .levels
+                .iter()
+                .find_map(|level| level.element.as_deref())
+                .map(|element| hex_color(render::element_color(element)))
+                .unwrap_or_else(|| hex_color(render::element_color("")));
+        }
+    }
+
+    /// the synthesis as drawn, or None if nothing has been entered
+    pub fn synthesis(&self) -> Option<Synthesis> {
+        if self.graph.nodes.is_empty()
+            && self.required_materials.value.trim().is_empty()
+            && self.alchemy_level.value.trim().is_empty()
+        {
+            return None;
+        }
+
+        // loops higher up a column come first within their Distance
+        let mut order: Vec<usize> = (0..self.graph.nodes.len()).collect();
+        order.sort_by(|a, b| {
+            let (a, b) = (&self.graph.nodes[*a], &self.graph.nodes[*b]);
+            a.y.partial_cmp(&b.y).unwrap_or(std::cmp::Ordering::Equal)
+        });
+        let material_loops: Vec<MaterialLoop> = order
+            .iter()
+            .map(|&index| {
+                self.forms[index]
+                    .material_loop(self.graph.nodes[index].distance)
+            })
+            .collect();
+        let linked_from: Vec<Option<usize>> = order
+            .iter()
+            .map(|&index| {
+                self.graph.nodes[index].linked_from.map(|from| {
+                    order.iter().position(|&i| i == from).unwrap_or(from)
+                })
+            })
+            .collect();
+
+        Some(Synthesis {
+            required_materials: parse(&self.required_materials.value)
+                .unwrap_or(0),
+            required_alchemy_level: parse(&self.alchemy_level.value)
+                .unwrap_or(0),
+            material_loops: item_file::number_material_loops(
+                &material_loops,
+                &linked_from,
+            ),
+        })
+    }
+
+    /// problems the item file can't show, since they're about the drawing rather than the values
+    pub fn fail_messages(&self) -> Vec<String> {
+        let mut fail_messages = Vec::new();
+        for (node, form) in self.graph.nodes.iter().zip(&self.forms) {
+            if node.distance > 0 && node.linked_from.is_none() {
+                fail_messages.push(format!(
+                    "loop '{}' isn't linked from another loop",
+                    form.name.value.trim()
+                ));
+            }
+        }
+        for (key, field) in &[
+            ("Required Materials", &self.required_materials),
+            ("Required Alchemy Level", &self.alchemy_level),
+        ] {
+            if !self.graph.nodes.is_empty() && parse(&field.value).is_none() {
+                fail_messages.push(format!("'{}' must be a whole number", key));
+            }
+        }
+        fail_messages
+    }
+
+    pub fn view(&mut self, theme: style::Theme) -> Element<Message> {
+        let synthesis = Row::new()
+            .spacing(10)
+            .align_items(Align::Center)
+            .push(Text::new("Required Materials"))
+            .push(number_input(
+                &mut self.required_materials,
+                Message::RequiredMaterialsChanged,
+                theme,
+            ))
+            .push(Text::new("Required Alchemy Level"))
+            .push(number_input(
+                &mut self.alchemy_level,
+                Message::AlchemyLevelChanged,
+                theme,
+            ));
+
+        let selected = self.graph.selected;
+        let canvas = Canvas::new(&mut self.graph)
+            .width(Length::Units((COLUMN_WIDTH * COLUMNS as f32) as u16))
+            .height(Length::Units(CANVAS_HEIGHT));
+
+        let details: Element<Message> = match selected {
+            Some(index) => self.forms[index].view(&self.element_options, theme),
+            None => Text::new(
+                "Click in a column to add a loop, or click a loop to edit it. \
+                     Drag from one loop to another to link them.",
+            )
+            .into(),
+        };
+
+        Column::new()
+            .spacing(10)
+            .push(Text::new("Material Loops").size(24))
+            .push(synthesis)
+            .push(canvas)
+            .push(details)
+            .into()
+    }
+}
+
+impl LoopForm {
+    fn update(&mut self, message: Message) {
+        match message {
+            Message::NameChanged(value) => self.name.value = value,
+            Message::MaterialChanged(value) => self.material.value = value,
+            Message::LevelAdded => self.levels.push(LevelForm::default()),
+            Message::LevelEffectChanged(index, value) => {
+                self.levels[index].effect.value = value
+            }
+            Message::LevelElementChanged(index, element) => {
+                self.levels[index].element = Some(element)
+            }
+            Message::LevelValueChanged(index, value) => {
+                self.levels[index].value.value = value
+            }
+            Message::LevelRecipeChanged(index, value) => {
+                self.levels[index].recipe.value = value
+            }
+            Message::LevelRemoved(index) => {
+                self.levels.remove(index);
+            }
+            Message::UnlockAdded(element) => {
+                if !self.unlock.iter().any(|unlock| unlock.element == element) {
+                    self.unlock.push(UnlockForm {
+                        element,
+                        value: Field::default(),
+                        remove: button::State::new(),
+                    });
+                }
+            }
+            Message::UnlockValueChanged(index, value) => {
+                self.unlock[index].value.value = value
+            }
+            Message::UnlockRemoved(index) => {
+                self.unlock.remove(index);
+            }
+            _ => {}
+        }
+    }
+
+    fn material_loop(&self, distance: i64) -> MaterialLoop {
+        MaterialLoop {
+            name: self.name.value.trim().to_string(),
+            distance,
+            material: self.material.value.trim().to_string(),
+            levels: self
+                .levels
+                .iter()
+                .map(|level| LoopLevel {
+                    effect: level.effect.value.trim().to_string(),
+                    elements: level
+                        .element
+                        .iter()
+                        .map(|element| ElementValue {
+                            element: element.clone(),
+                            value: parse(&level.value.value),
+                        })
+                        .collect(),
+                    recipe: Some(level.recipe.value.trim().to_string())
+                        .filter(|recipe| !recipe.is_empty()),
+                    ..LoopLevel::default()
+                })
+                .collect(),
+            unlock: self
+                .unlock
+                .iter()
+                .map(|unlock| ElementValue {
+                    element: unlock.element.clone(),
+                    value: parse(&unlock.value.value),
+                })
+                .collect(),
+            ..MaterialLoop::default()
+        }
+    }
+
+    fn view<'a>(
+        &'a mut self,
+        element_options: &'a [String],
+        theme: style::Theme,
+    ) -> Element<'a, Message> {
+        let mut column = Column::new()
+            .spacing(5)
+            .push(labelled(
+                "Loop",
+                TextInput::new(
+                    &mut self.name.state,
+                    "Effect 1",
+                    &self.name.value,
+                    Message::NameChanged,
+                )
+                .padding(5)
+                .style(theme),
+            ))
+            .push(labelled(
+                "Material",
+                TextInput::new(
+                    &mut self.material.state,
+                    "(Uni)",
+                    &self.material.value,
+                    Message::MaterialChanged,
+                )
+                .padding(5)
+                .style(theme),
+            ))
+            .push(
+                Row::new()
+                    .spacing(10)
+                    .push(
+                        Button::new(&mut self.unlink, Text::new("Unlink"))
+                            .on_press(Message::LoopUnlinked)
+                            .style(theme),
+                    )
+                    .push(
+                        Button::new(&mut self.remove, Text::new("Remove loop"))
+                            .on_press(Message::LoopRemoved)
+                            .style(theme),
+                    ),
+            )
+            .push(Text::new("Levels"));
+
+        for (index, level) in self.levels.iter_mut().enumerate() {
+            column = column.push(
+                Row::new()
+                    .spacing(10)
+                    .align_items(Align::Center)
+                    .push(
+                        TextInput::new(
+                            &mut level.effect.state,
+                            "effect",
+                            &level.effect.value,
+                            move |value| {
+                                Message::LevelEffectChanged(index, value)
+                            },
+                        )
+                        .padding(5)
+                        .style(theme),
+                    )
+                    .push(PickList::new(
+                        &mut level.element_picker,
+                        element_options,
+                        level.element.clone(),
+                        move |element| {
+                            Message::LevelElementChanged(index, element)
+                        },
+                    ))
+                    .push(
+                        TextInput::new(
+                            &mut level.value.state,
+                            "value",
+                            &level.value.value,
+                            move |value| {
+                                Message::LevelValueChanged(index, value)
+                            },
+                        )
+                        .padding(5)
+                        .width(Length::Units(60))
+                        .style(theme),
+                    )
+                    .push(
+                        TextInput::new(
+                            &mut level.recipe.state,
+                            "recipe (Recipe Morph only)",
+                            &level.recipe.value,
+                            move |value| {
+                                Message::LevelRecipeChanged(index, value)
+                            },
+                        )
+                        .padding(5)
+                        .style(theme),
+                    )
+                    .push(
+                        Button::new(&mut level.remove, Text::new("Remove"))
+                            .on_press(Message::LevelRemoved(index))
+                            .style(theme),
+                    ),
+            );
+        }
+        column = column.push(
+            Button::new(&mut self.add_level, Text::new("Add level"))
+                .on_press(Message::LevelAdded)
+                .style(theme),
+        );
+
+        column = column.push(labelled(
+            "Unlock",
+            PickList::new(
+                &mut self.unlock_picker,
+                element_options,
+                None,
+                Message::UnlockAdded,
+            ),
+        ));
+        for (index, unlock) in self.unlock.iter_mut().enumerate() {
+            column = column.push(
+                Row::new()
+                    .spacing(10)
+                    .align_items(Align::Center)
+                    .push(Text::new(&unlock.element).width(Length::Units(140)))
+                    .push(
+                        TextInput::new(
+                            &mut unlock.value.state,
+                            "value",
+                            &unlock.value.value,
+                            move |value| {
+                                Message::UnlockValueChanged(index, value)
+                            },
+                        )
+                        .padding(5)
+                        .width(Length::Units(60))
+                        .style(theme),
+                    )
+                    .push(
+                        Button::new(&mut unlock.remove, Text::new("Remove"))
+                            .on_press(Message::UnlockRemoved(index))
+                            .style(theme),
+                    ),
+            );
+        }
+        column.into()
+    }
+}
+
+impl LoopGraph {
+    fn node_at(&self, point: Point) -> Option<usize> {
+        self.nodes.iter().position(|node| {
+            let center = node.center();
+            let (x, y) = (point.x - center.x, point.y - center.y);
+            (x * x + y * y).sqrt() <= RADIUS
+        })
+    }
+}
+
+impl Node {
+    fn center(&self) -> Point {
+        Point::new(
+            self.distance as f32 * COLUMN_WIDTH + COLUMN_WIDTH / 2.0,
+            self.y,
+        )
+    }
+}
+
+impl canvas::Program<Message> for LoopGraph {
+    fn update(
+        &mut self,
+        event: canvas::Event,
+        bounds: Rectangle,
+        cursor: Cursor,
+    ) -> Option<Message> {
+        let position = cursor.position_in(&bounds);
+        match event {
+            canvas::Event::Mouse(mouse::Event::ButtonPressed(
+                mouse::Button::Left,
+            )) => match self.node_at(position?) {
+                Some(index) => {
+                    self.dragging_from = Some(index);
+                    Some(Message::LoopSelected(index))
+                }
+                None => {
+                    let position = position?;
+                    let distance = (position.x / COLUMN_WIDTH) as i64;
+                    Some(Message::LoopAdded(
+                        distance.min(COLUMNS - 1),
+                        position.y,
+                    ))
+                }
+            },
+            canvas::Event::Mouse(mouse::Event::ButtonReleased(
+                mouse::Button::Left,
+            )) => {
+                let from = self.dragging_from.take()?;
+                match self.node_at(position?) {
+                    Some(to) if to != from => {
+                        Some(Message::LoopLinked(from, to))
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    fn draw(&self, bounds: Rectangle, cursor: Cursor) -> Vec<Geometry> {
+        let mut frame = Frame::new(bounds.size());
+
+        // the columns, one for each Distance
+        for distance in 0..COLUMNS {
+            let x = distance as f32 * COLUMN_WIDTH;
+            frame.stroke(
+                &Path::line(Point::new(x, 0.0), Point::new(x, bounds.height)),
+                Stroke::default()
+                    .with_color(Color::from_rgb8(0xDD, 0xDD, 0xDD)),
+            );
+            frame.fill_text(canvas::Text {
+                content: format!("Distance {}", distance),
+                position: Point::new(x + COLUMN_WIDTH / 2.0, 4.0),
+                color: Color::from_rgb8(0x88, 0x88, 0x88),
+                horizontal_alignment: HorizontalAlignment::Center,
+                ..canvas::Text::default()
+            });
+        }
+
+        for node in &self.nodes {
+            if let Some(from) = node.linked_from.map(|from| &self.nodes[from]) {
+                frame.stroke(
+                    &Path::line(from.center(), node.center()),
+                    link_stroke(),
+                );
+            }
+        }
+        // the link being dragged
+        if let (Some(from), Some(position)) =
+            (self.dragging_from, cursor.position_in(&bounds))
+        {
+            frame.stroke(
+                &Path::line(self.nodes[from].center(), position),
+                link_stroke(),
+            );
+        }
+
+        for (index, node) in self.nodes.iter().enumerate() {
+            let circle = Path::circle(node.center(), RADIUS);
+            frame.fill(&circle, node.color);
+            frame.stroke(
+                &circle,
+                Stroke::default()
+                    .with_width(if self.selected == Some(index) {
+                        4.0
+                    } else {
+                        2.0
+                    })
+                    .with_color(Color::from_rgb8(0x33, 0x33, 0x33)),
+            );
+            frame.fill_text(canvas::Text {
+                content: node.label.clone(),
+                position: Point::new(node.center().x, node.y + RADIUS + 4.0),
+                horizontal_alignment: HorizontalAlignment::Center,
+                vertical_alignment: VerticalAlignment::Top,
+                ..canvas::Text::default()
+            });
+        }
+
+        vec![frame.into_geometry()]
+    }
+
+    fn mouse_interaction(
+        &self,
+        bounds: Rectangle,
+        cursor: Cursor,
+    ) -> mouse::Interaction {
+        match cursor.position_in(&bounds) {
+            Some(position) if self.node_at(position).is_some() => {
+                mouse::Interaction::Pointer
+            }
+            _ => mouse::Interaction::default(),
+        }
+    }
+}
+
+fn link_stroke() -> Stroke {
+    Stroke::default()
+        .with_width(2.0)
+        .with_color(Color::from_rgb8(0x88, 0x88, 0x88))
+}
+
+fn labelled<'a>(
+    label: &str,
+    widget: impl Into<Element<'a, Message>>,
+) -> Row<'a, Message> {
+    Row::new()
+        .spacing(10)
+        .align_items(Align::Center)
+        .push(Text::new(label).width(Length::Units(140)))
+        .push(widget)
+}
+
+fn number_input<'a>(
+    field: &'a mut Field,
+    on_change: fn(String) -> Message,
+    theme: style::Theme,
+) -> TextInput<'a, Message> {
+    TextInput::new(&mut field.state, "", &field.value, on_change)
+        .padding(5)
+        .width(Length::Units(60))
+        .style(theme)
+}
+
+fn parse(value: &str) -> Option<i64> {
+    value.trim().parse().ok()
+}
+
+/// a color from the `#rrggbb` form used in the SVG renderings
+fn hex_color(hex: &str) -> Color {
+    let channel = |range| {
+        u8::from_str_radix(hex.get(range).unwrap_or("0"), 16).unwrap_or(0)
+    };
+    Color::from_rgb8(channel(1..3), channel(3..5), channel(5..7))
+}
@@ -1,515 +1,687 @@
+//! A data-entry app for item files, with a browser for the items already in the dataset.
+//!
+//! The form covers the basic details of an item, with pick lists fed from `lists.yml`, and a
+//! canvas for drawing its material loops. The item is validated with the validator's rules as it's
+//! typed, and saved into the data folder in the canonical item file format.
+
+mod browser;
+mod loop_editor;
+mod style;
+
 use iced::{
-    button, scrollable, slider, text_input, Align, Button, Checkbox, Column,
-    Container, Element, Length, ProgressBar, Radio, Row, Sandbox, Scrollable,
-    Settings, Slider, Space, Text, TextInput,
+    button, pick_list, scrollable, text_input, Align, Button, Color, Column,
+    Container, Element, Length, PickList, Radio, Row, Sandbox, Scrollable,
+    Settings, Text, TextInput,
 };
+use item_validator::dataset::Dataset;
+use item_validator::model::{ElementValue, Item};
+use item_validator::{item_file, settings, validate_item};
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+const FAIL_COLOR: Color = Color::from_rgb(0.8, 0.2, 0.2);
 
 pub fn main() {
-    Styling::run(Settings::default())
+    ItemEntry::run(Settings::default())
 }
 
+/// a text field and what's been typed into it
 #[derive(Default)]
-struct Styling {
+struct Field {
+    state: text_input::State,
+    value: String,
+}
+
+/// a value added from a pick list, with the button that removes it
+struct ListEntry {
+    value: String,
+    remove: button::State,
+}
+
+/// an element of the item, with the value typed for it
+struct ElementEntry {
+    element: String,
+    value: Field,
+    remove: button::State,
+}
+
+/// the parts of the app, picked at the top of the window
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Tab {
+    Entry,
+    Browse,
+}
+
+impl Tab {
+    const ALL: [Tab; 2] = [Tab::Entry, Tab::Browse];
+}
+
+struct ItemEntry {
     theme: style::Theme,
+    tab: Tab,
+    browser: browser::Browser,
+    data_folder: String,
+    dataset: Dataset,
+    classification_options: Vec<String>,
+    category_options: Vec<String>,
+    element_options: Vec<String>,
+    material_options: Vec<String>,
+    gathering_tool_options: Vec<String>,
+
     scroll: scrollable::State,
-    input: text_input::State,
-    input_value: String,
-    button: button::State,
-    slider: slider::State,
-    slider_value: f32,
-    toggle_value: bool,
+    name: Field,
+    item_number: Field,
+    level: Field,
+    classifications: Vec<ListEntry>,
+    classification_picker: pick_list::State<String>,
+    cc_cost: Field,
+    categories: Vec<ListEntry>,
+    category_picker: pick_list::State<String>,
+    elements: Vec<ElementEntry>,
+    element_picker: pick_list::State<String>,
+    materials: Vec<ListEntry>,
+    material_picker: pick_list::State<String>,
+    gather_locations: Vec<ListEntry>,
+    gather_location: Field,
+    add_gather_location: button::State,
+    gathering_tools: Vec<ListEntry>,
+    gathering_tool_picker: pick_list::State<String>,
+    loops: loop_editor::LoopEditor,
+    save_button: button::State,
+
+    /// problems with the item as it stands, updated on every change
+    fail_messages: Vec<String>,
+    /// the outcome of the last save
+    status: Option<String>,
 }
 
 #[derive(Debug, Clone)]
 enum Message {
     ThemeChanged(style::Theme),
-    InputChanged(String),
-    ButtonPressed,
-    SliderChanged(f32),
-    CheckboxToggled(bool),
+    TabChanged(Tab),
+    Browser(browser::Message),
+    NameChanged(String),
+    ItemNumberChanged(String),
+    LevelChanged(String),
+    ClassificationAdded(String),
+    ClassificationRemoved(usize),
+    CcCostChanged(String),
+    CategoryAdded(String),
+    CategoryRemoved(usize),
+    ElementAdded(String),
+    ElementValueChanged(usize, String),
+    ElementRemoved(usize),
+    MaterialAdded(String),
+    MaterialRemoved(usize),
+    GatherLocationChanged(String),
+    GatherLocationAdded,
+    GatherLocationRemoved(usize),
+    GatheringToolAdded(String),
+    GatheringToolRemoved(usize),
+    Loops(loop_editor::Message),
+    Save,
 }
 
-impl Sandbox for Styling {
+impl Sandbox for ItemEntry {
     type Message = Message;
 
     fn new() -> Self {
-        Styling::default()
+        let settings = settings::get_settings();
+        let dataset = Dataset::load(&settings.data_folder)
+            .unwrap_or_else(|error| panic!("{}", error));
+        let sets = &dataset.item_validation_sets;
+        let element_options = sorted(&sets.elements);
+
+        let mut item_entry = ItemEntry {
+            theme: style::Theme::default(),
+            tab: Tab::Entry,
+            browser: browser::Browser::new(dataset.items(), sets),
+            classification_options: sorted(&sets.classifications),
+            category_options: sorted(&sets.categories),
+            element_options: element_options.clone(),
+            material_options: sorted(&sets.materials),
+            gathering_tool_options: sorted(&sets.gathering_tools),
+            data_folder: settings.data_folder,
+            dataset,
+
+            scroll: scrollable::State::new(),
+            name: Field::default(),
+            item_number: Field::default(),
+            level: Field::default(),
+            classifications: Vec::new(),
+            classification_picker: pick_list::State::default(),
+            cc_cost: Field::default(),
+            categories: Vec::new(),
+            category_picker: pick_list::State::default(),
+            elements: Vec::new(),
+            element_picker: pick_list::State::default(),
+            materials: Vec::new(),
+            material_picker: pick_list::State::default(),
+            gather_locations: Vec::new(),
+            gather_location: Field::default(),
+            add_gather_location: button::State::new(),
+            gathering_tools: Vec::new(),
+            gathering_tool_picker: pick_list::State::default(),
+            loops: loop_editor::LoopEditor::new(element_options),
+            save_button: button::State::new(),
+
+            fail_messages: Vec::new(),
+            status: None,
+        };
+        item_entry.validate();
+        item_entry
     }
 
     fn title(&self) -> String {
-        String::from("Styling - Iced")
+        String::from("Item Entry")
     }
 
     fn update(&mut self, message: Message) {
         match message {
             Message::ThemeChanged(theme) => self.theme = theme,
-            Message::InputChanged(value) => self.input_value = value,
-            Message::ButtonPressed => (),
-            Message::SliderChanged(value) => self.slider_value = value,
-            Message::CheckboxToggled(value) => self.toggle_value = value,
+            Message::TabChanged(tab) => self.tab = tab,
+            Message::Browser(message) => self.browser.update(message),
+            Message::NameChanged(value) => self.name.value = value,
+            Message::ItemNumberChanged(value) => self.item_number.value = value,
+            Message::LevelChanged(value) => self.level.value = value,
+            Message::ClassificationAdded(value) => {
+                add_entry(&mut self.classifications, value)
+            }
+            Message::ClassificationRemoved(index) => {
+                self.classifications.remove(index);
+            }
+            Message::CcCostChanged(value) => self.cc_cost.value = value,
+            Message::CategoryAdded(value) => {
+                add_entry(&mut self.categories, value)
+            }
+            Message::CategoryRemoved(index) => {
+                self.categories.remove(index);
+            }
+            Message::ElementAdded(element) => {
+                if !self.elements.iter().any(|entry| entry.element == element) {
+                    self.elements.push(ElementEntry {
+                        element,
+                        value: Field::default(),
+                        remove: button::State::new(),
+                    });
+                }
+            }
+            Message::ElementValueChanged(index, value) => {
+                self.elements[index].value.value = value
+            }
+            Message::ElementRemoved(index) => {
+                self.elements.remove(index);
+            }
+            Message::MaterialAdded(value) => {
+                add_entry(&mut self.materials, value)
+            }
+            Message::MaterialRemoved(index) => {
+                self.materials.remove(index);
+            }
+            Message::GatherLocationChanged(value) => {
+                self.gather_location.value = value
+            }
+            Message::GatherLocationAdded => {
+                let value = self.gather_location.value.trim().to_string();
+                if !value.is_empty() {
+                    add_entry(&mut self.gather_locations, value);
+                    self.gather_location.value.clear();
+                }
+            }
+            Message::GatherLocationRemoved(index) => {
+                self.gather_locations.remove(index);
+            }
+            Message::GatheringToolAdded(value) => {
+                add_entry(&mut self.gathering_tools, value)
+            }
+            Message::GatheringToolRemoved(index) => {
+                self.gathering_tools.remove(index);
+            }
+            Message::Loops(message) => self.loops.update(message),
+            Message::Save => self.status = Some(self.save()),
         }
+        self.validate();
     }
 
     fn view(&mut self) -> Element<Message> {
+        let theme = self.theme;
+        let tab = self.tab;
+
         let choose_theme = style::Theme::ALL.iter().fold(
-            Column::new().spacing(10).push(Text::new("Choose a theme:")),
-            |column, theme| {
-                column.push(
+            Row::new().spacing(10).push(Text::new("Theme:")),
+            |row, option| {
+                row.push(
                     Radio::new(
-                        *theme,
-                        &format!("{:?}", theme),
-                        Some(self.theme),
+                        *option,
+                        &format!("{:?}", option),
+                        Some(theme),
                         Message::ThemeChanged,
                     )
-                    .style(self.theme),
+                    .style(theme),
+                )
+            },
+        );
+        let choose_tab = Tab::ALL.iter().fold(
+            Row::new().spacing(10).push(Text::new("View:")),
+            |row, option| {
+                row.push(
+                    Radio::new(
+                        *option,
+                        &format!("{:?}", option),
+                        Some(tab),
+                        Message::TabChanged,
+                    )
+                    .style(theme),
                 )
             },
         );
 
-        let text_input = TextInput::new(
-            &mut self.input,
-            "Type something...",
-            &self.input_value,
-            Message::InputChanged,
-        )
-        .padding(10)
-        .size(20)
-        .style(self.theme);
+        let content = match tab {
+            Tab::Entry => self.entry_view(theme),
+            Tab::Browse => self.browser.view(theme).map(Message::Browser),
+        };
 
-        let button = Button::new(&mut self.button, Text::new("Submit"))
-            .padding(10)
-            .on_press(Message::ButtonPressed)
-            .style(self.theme);
-
-        let slider = Slider::new(
-            &mut self.slider,
-            0.0..=100.0,
-            self.slider_value,
-            Message::SliderChanged,
-        )
-        .style(self.theme);
-
-        let progress_bar =
-            ProgressBar::new(0.0..=100.0, self.slider_value).style(self.theme);
-
-        let scrollable = Scrollable::new(&mut self.scroll)
-            .height(Length::Units(100))
-            .style(self.theme)
-            .push(Text::new("Scroll me!"))
-            .push(Space::with_height(Length::Units(800)))
-            .push(Text::new("You did it!"));
-
-        let checkbox = Checkbox::new(
-            self.toggle_value,
-            "Toggle me!",
-            Message::CheckboxToggled,
+        Container::new(
+            Column::new()
+                .push(
+                    Row::new()
+                        .spacing(40)
+                        .padding(20)
+                        .push(choose_tab)
+                        .push(choose_theme),
+                )
+                .push(content),
         )
         .width(Length::Fill)
-        .style(self.theme);
+        .height(Length::Fill)
+        .style(theme)
+        .into()
+    }
+}
 
-        let content = Column::new()
-            .spacing(20)
-            .padding(20)
-            .max_width(600)
-            .push(choose_theme)
-            .push(Row::new().spacing(10).push(text_input).push(button))
-            .push(slider)
-            .push(progress_bar)
-            .push(
+impl ItemEntry {
+    /// the form for entering a new item
+    fn entry_view(&mut self, theme: style::Theme) -> Element<Message> {
+        let name = text_field(
+            "Name",
+            &mut self.name,
+            "Explosive Uni",
+            Message::NameChanged,
+            theme,
+        );
+        let item_number = text_field(
+            "Item Number",
+            &mut self.item_number,
+            "1",
+            Message::ItemNumberChanged,
+            theme,
+        );
+        let level = text_field(
+            "Level",
+            &mut self.level,
+            "1",
+            Message::LevelChanged,
+            theme,
+        );
+
+        let classifications = list_field(
+            "Classifications",
+            &mut self.classifications,
+            &mut self.classification_picker,
+            &self.classification_options,
+            Message::ClassificationAdded,
+            Message::ClassificationRemoved,
+            theme,
+        );
+        let cc_cost = text_field(
+            "CC Cost",
+            &mut self.cc_cost,
+            "battle items only",
+            Message::CcCostChanged,
+            theme,
+        );
+        let categories = list_field(
+            "Category",
+            &mut self.categories,
+            &mut self.category_picker,
+            &self.category_options,
+            Message::CategoryAdded,
+            Message::CategoryRemoved,
+            theme,
+        );
+
+        let mut elements = Column::new().spacing(5).push(
+            Row::new()
+                .spacing(10)
+                .align_items(Align::Center)
+                .push(Text::new("Element").width(Length::Units(140)))
+                .push(PickList::new(
+                    &mut self.element_picker,
+                    &self.element_options[..],
+                    None,
+                    Message::ElementAdded,
+                )),
+        );
+        for (index, entry) in self.elements.iter_mut().enumerate() {
+            elements = elements.push(
                 Row::new()
                     .spacing(10)
                     .align_items(Align::Center)
-                    .push(scrollable)
-                    .push(checkbox),
+                    .push(Text::new(&entry.element).width(Length::Units(140)))
+                    .push(
+                        TextInput::new(
+                            &mut entry.value.state,
+                            "value",
+                            &entry.value.value,
+                            move |value| {
+                                Message::ElementValueChanged(index, value)
+                            },
+                        )
+                        .padding(5)
+                        .width(Length::Units(60))
+                        .style(theme),
+                    )
+                    .push(
+                        Button::new(&mut entry.remove, Text::new("Remove"))
+                            .on_press(Message::ElementRemoved(index))
+                            .style(theme),
+                    ),
             );
+        }
 
-        Container::new(content)
-            .width(Length::Fill)
-            .height(Length::Fill)
-            .center_x()
-            .center_y()
-            .style(self.theme)
-            .into()
-    }
-}
-
-mod style {
-    use iced::{
-        button, checkbox, container, progress_bar, radio, scrollable, slider,
-        text_input,
-    };
+        let materials = list_field(
+            "Materials",
+            &mut self.materials,
+            &mut self.material_picker,
+            &self.material_options,
+            Message::MaterialAdded,
+            Message::MaterialRemoved,
+            theme,
+        );
 
-    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-    pub enum Theme {
-        Light,
-        Dark,
-    }
+        // gather locations aren't listed anywhere, so they're typed in
+        let gather_locations =
+            self.gather_locations.iter_mut().enumerate().fold(
+                Column::new().spacing(5).push(
+                    Row::new()
+                        .spacing(10)
+                        .align_items(Align::Center)
+                        .push(
+                            Text::new("Gather Locations")
+                                .width(Length::Units(140)),
+                        )
+                        .push(
+                            TextInput::new(
+                                &mut self.gather_location.state,
+                                "Little Adventurer's Tower",
+                                &self.gather_location.value,
+                                Message::GatherLocationChanged,
+                            )
+                            .on_submit(Message::GatherLocationAdded)
+                            .padding(5)
+                            .style(theme),
+                        )
+                        .push(
+                            Button::new(
+                                &mut self.add_gather_location,
+                                Text::new("Add"),
+                            )
+                            .on_press(Message::GatherLocationAdded)
+                            .style(theme),
+                        ),
+                ),
+                |column, (index, entry)| {
+                    column.push(
+                        Row::new()
+                            .spacing(10)
+                            .align_items(Align::Center)
+                            .push(
+                                Text::new(&entry.value)
+                                    .width(Length::Units(140)),
+                            )
+                            .push(
+                                Button::new(
+                                    &mut entry.remove,
+                                    Text::new("Remove"),
+                                )
+                                .on_press(Message::GatherLocationRemoved(index))
+                                .style(theme),
+                            ),
+                    )
+                },
+            );
+        let gathering_tools = list_field(
+            "Gathering Tools",
+            &mut self.gathering_tools,
+            &mut self.gathering_tool_picker,
+            &self.gathering_tool_options,
+            Message::GatheringToolAdded,
+            Message::GatheringToolRemoved,
+            theme,
+        );
 
-    impl Theme {
-        pub const ALL: [Theme; 2] = [Theme::Light, Theme::Dark];
-    }
+        let save = Button::new(&mut self.save_button, Text::new("Save"))
+            .padding(10)
+            .on_press(Message::Save)
+            .style(theme);
+
+        let validation = if self.fail_messages.is_empty() {
+            Column::new().push(Text::new("No problems found"))
+        } else {
+            self.fail_messages.iter().fold(
+                Column::new().spacing(5),
+                |column, message| {
+                    column.push(Text::new(message).color(FAIL_COLOR))
+                },
+            )
+        };
 
-    impl Default for Theme {
-        fn default() -> Theme {
-            Theme::Light
+        let mut content = Column::new()
+            .spacing(20)
+            .padding(20)
+            .max_width(700)
+            .push(name)
+            .push(item_number)
+            .push(level)
+            .push(classifications)
+            .push(cc_cost)
+            .push(categories)
+            .push(elements)
+            .push(materials)
+            .push(gather_locations)
+            .push(gathering_tools)
+            .push(self.loops.view(theme).map(Message::Loops))
+            .push(validation)
+            .push(save);
+        if let Some(status) = &self.status {
+            content = content.push(Text::new(status));
         }
-    }
 
-    impl From<Theme> for Box<dyn container::StyleSheet> {
-        fn from(theme: Theme) -> Self {
-            match theme {
-                Theme::Light => Default::default(),
-                Theme::Dark => dark::Container.into(),
-            }
-        }
+        Scrollable::new(&mut self.scroll)
+            .style(theme)
+            .push(Container::new(content).width(Length::Fill).center_x())
+            .into()
     }
 
-    impl From<Theme> for Box<dyn radio::StyleSheet> {
-        fn from(theme: Theme) -> Self {
-            match theme {
-                Theme::Light => Default::default(),
-                Theme::Dark => dark::Radio.into(),
-            }
+    /// the item as it's been entered so far
+    fn item(&self) -> Item {
+        Item {
+            name: self.name.value.trim().to_string(),
+            item_number: self.item_number.value.trim().parse().unwrap_or(0),
+            level: self.level.value.trim().parse().unwrap_or(0),
+            classifications: values(&self.classifications),
+            cc_cost: self.cc_cost.value.trim().parse().ok(),
+            categories: values(&self.categories),
+            elements: self
+                .elements
+                .iter()
+                .map(|entry| ElementValue {
+                    element: entry.element.clone(),
+                    value: entry.value.value.trim().parse().ok(),
+                })
+                .collect(),
+            materials: values(&self.materials),
+            gather_locations: values(&self.gather_locations),
+            gathering_tools: values(&self.gathering_tools),
+            synthesis: self.loops.synthesis(),
+            ..Item::default()
         }
     }
 
-    impl From<Theme> for Box<dyn text_input::StyleSheet> {
-        fn from(theme: Theme) -> Self {
-            match theme {
-                Theme::Light => Default::default(),
-                Theme::Dark => dark::TextInput.into(),
+    /// check the item the same way the validator checks item files
+    fn validate(&mut self) {
+        let mut fail_messages = Vec::new();
+        for (key, field) in
+            &[("Item Number", &self.item_number), ("Level", &self.level)]
+        {
+            if field.value.trim().parse::<i64>().is_err() {
+                fail_messages.push(format!("'{}' must be a whole number", key));
             }
         }
-    }
-
-    impl From<Theme> for Box<dyn button::StyleSheet> {
-        fn from(theme: Theme) -> Self {
-            match theme {
-                Theme::Light => light::Button.into(),
-                Theme::Dark => dark::Button.into(),
-            }
+        let cc_cost = self.cc_cost.value.trim();
+        if !cc_cost.is_empty() && cc_cost.parse::<i64>().is_err() {
+            fail_messages
+                .push(String::from("'CC Cost' must be a whole number"));
         }
-    }
-
-    impl From<Theme> for Box<dyn scrollable::StyleSheet> {
-        fn from(theme: Theme) -> Self {
-            match theme {
-                Theme::Light => Default::default(),
-                Theme::Dark => dark::Scrollable.into(),
+        for entry in &self.elements {
+            let value = entry.value.value.trim();
+            if !value.is_empty() && value.parse::<i64>().is_err() {
+                fail_messages.push(format!(
+                    "the value of '{}' must be a whole number",
+                    entry.element
+                ));
             }
         }
-    }
 
-    impl From<Theme> for Box<dyn slider::StyleSheet> {
-        fn from(theme: Theme) -> Self {
-            match theme {
-                Theme::Light => Default::default(),
-                Theme::Dark => dark::Slider.into(),
-            }
-        }
-    }
+        fail_messages.extend(self.loops.fail_messages());
 
-    impl From<Theme> for Box<dyn progress_bar::StyleSheet> {
-        fn from(theme: Theme) -> Self {
-            match theme {
-                Theme::Light => Default::default(),
-                Theme::Dark => dark::ProgressBar.into(),
-            }
+        match validate_item::validate_item_contents(
+            &item_file::to_yaml(&self.item()),
+            &self.dataset.item_validation_sets,
+            &self.dataset.item_rules,
+        ) {
+            Ok(results) => fail_messages.extend(results.fail_messages),
+            Err(error) => fail_messages.push(format!("{}", error)),
         }
+        self.fail_messages = fail_messages;
     }
 
-    impl From<Theme> for Box<dyn checkbox::StyleSheet> {
-        fn from(theme: Theme) -> Self {
-            match theme {
-                Theme::Light => Default::default(),
-                Theme::Dark => dark::Checkbox.into(),
-            }
+    /// write the item into the data folder, without overwriting an existing item file. Items
+    /// that don't pass validation aren't saved
+    fn save(&mut self) -> String {
+        let item = self.item();
+        if item.name.is_empty() {
+            return String::from(
+                "The item needs a name before it can be saved",
+            );
         }
-    }
-
-    mod light {
-        use iced::{button, Background, Color, Vector};
-
-        pub struct Button;
-
-        impl button::StyleSheet for Button {
-            fn active(&self) -> button::Style {
-                button::Style {
-                    background: Some(Background::Color(Color::from_rgb(
-                        0.11, 0.42, 0.87,
-                    ))),
-                    border_radius: 12,
-                    shadow_offset: Vector::new(1.0, 1.0),
-                    text_color: Color::from_rgb8(0xEE, 0xEE, 0xEE),
-                    ..button::Style::default()
-                }
-            }
-
-            fn hovered(&self) -> button::Style {
-                button::Style {
-                    text_color: Color::WHITE,
-                    shadow_offset: Vector::new(1.0, 2.0),
-                    ..self.active()
-                }
-            }
+        if !self.fail_messages.is_empty() {
+            return String::from(
+                "The item can't be saved until the problems above are fixed",
+            );
         }
-    }
-
-    mod dark {
-        use iced::{
-            button, checkbox, container, progress_bar, radio, scrollable,
-            slider, text_input, Background, Color,
-        };
-
-        const SURFACE: Color = Color::from_rgb(
-            0x40 as f32 / 255.0,
-            0x44 as f32 / 255.0,
-            0x4B as f32 / 255.0,
-        );
-
-        const ACCENT: Color = Color::from_rgb(
-            0x6F as f32 / 255.0,
-            0xFF as f32 / 255.0,
-            0xE9 as f32 / 255.0,
-        );
-
-        const ACTIVE: Color = Color::from_rgb(
-            0x72 as f32 / 255.0,
-            0x89 as f32 / 255.0,
-            0xDA as f32 / 255.0,
+        let path = format!(
+            "{}/items/{}",
+            self.data_folder,
+            item_file::file_name(&item)
         );
-
-        const HOVERED: Color = Color::from_rgb(
-            0x67 as f32 / 255.0,
-            0x7B as f32 / 255.0,
-            0xC4 as f32 / 255.0,
-        );
-
-        pub struct Container;
-
-        impl container::StyleSheet for Container {
-            fn style(&self) -> container::Style {
-                container::Style {
-                    background: Some(Background::Color(Color::from_rgb8(
-                        0x36, 0x39, 0x3F,
-                    ))),
-                    text_color: Some(Color::WHITE),
-                    ..container::Style::default()
-                }
-            }
+        if Path::new(&path).exists() {
+            return format!("{} already exists, not saving", path);
         }
-
-        pub struct Radio;
-
-        impl radio::StyleSheet for Radio {
-            fn active(&self) -> radio::Style {
-                radio::Style {
-                    background: Background::Color(SURFACE),
-                    dot_color: ACTIVE,
-                    border_width: 1,
-                    border_color: ACTIVE,
-                }
-            }
-
-            fn hovered(&self) -> radio::Style {
-                radio::Style {
-                    background: Background::Color(Color { a: 0.5, ..SURFACE }),
-                    ..self.active()
-                }
-            }
+        if let Err(error) = fs::write(&path, item_file::to_yaml(&item)) {
+            return format!("Unable to save {}: {}", path, error);
         }
 
-        pub struct TextInput;
-
-        impl text_input::StyleSheet for TextInput {
-            fn active(&self) -> text_input::Style {
-                text_input::Style {
-                    background: Background::Color(SURFACE),
-                    border_radius: 2,
-                    border_width: 0,
-                    border_color: Color::TRANSPARENT,
-                }
-            }
-
-            fn focused(&self) -> text_input::Style {
-                text_input::Style {
-                    border_width: 1,
-                    border_color: ACCENT,
-                    ..self.active()
-                }
-            }
-
-            fn hovered(&self) -> text_input::Style {
-                text_input::Style {
-                    border_width: 1,
-                    border_color: Color { a: 0.3, ..ACCENT },
-                    ..self.focused()
-                }
-            }
-
-            fn placeholder_color(&self) -> Color {
-                Color::from_rgb(0.4, 0.4, 0.4)
-            }
-
-            fn value_color(&self) -> Color {
-                Color::WHITE
+        // the new item can now be used as a material by other items
+        match Dataset::load(&self.data_folder) {
+            Ok(dataset) => {
+                self.browser.set_items(dataset.items());
+                self.material_options =
+                    sorted(&dataset.item_validation_sets.materials);
+                self.dataset = dataset;
             }
+            Err(error) => return format!("Saved {}, but {}", path, error),
         }
+        format!("Saved {}", path)
+    }
+}
 
-        pub struct Button;
-
-        impl button::StyleSheet for Button {
-            fn active(&self) -> button::Style {
-                button::Style {
-                    background: Some(Background::Color(ACTIVE)),
-                    border_radius: 3,
-                    text_color: Color::WHITE,
-                    ..button::Style::default()
-                }
-            }
-
-            fn hovered(&self) -> button::Style {
-                button::Style {
-                    background: Some(Background::Color(HOVERED)),
-                    text_color: Color::WHITE,
-                    ..self.active()
-                }
-            }
-
-            fn pressed(&self) -> button::Style {
-                button::Style {
-                    border_width: 1,
-                    border_color: Color::WHITE,
-                    ..self.hovered()
-                }
-            }
-        }
-
-        pub struct Scrollable;
-
-        impl scrollable::StyleSheet for Scrollable {
-            fn active(&self) -> scrollable::Scrollbar {
-                scrollable::Scrollbar {
-                    background: Some(Background::Color(SURFACE)),
-                    border_radius: 2,
-                    border_width: 0,
-                    border_color: Color::TRANSPARENT,
-                    scroller: scrollable::Scroller {
-                        color: ACTIVE,
-                        border_radius: 2,
-                        border_width: 0,
-                        border_color: Color::TRANSPARENT,
-                    },
-                }
-            }
-
-            fn hovered(&self) -> scrollable::Scrollbar {
-                let active = self.active();
-
-                scrollable::Scrollbar {
-                    background: Some(Background::Color(Color {
-                        a: 0.5,
-                        ..SURFACE
-                    })),
-                    scroller: scrollable::Scroller {
-                        color: HOVERED,
-                        ..active.scroller
-                    },
-                    ..active
-                }
-            }
-
-            fn dragging(&self) -> scrollable::Scrollbar {
-                let hovered = self.hovered();
-
-                scrollable::Scrollbar {
-                    scroller: scrollable::Scroller {
-                        color: Color::from_rgb(0.85, 0.85, 0.85),
-                        ..hovered.scroller
-                    },
-                    ..hovered
-                }
-            }
-        }
-
-        pub struct Slider;
-
-        impl slider::StyleSheet for Slider {
-            fn active(&self) -> slider::Style {
-                slider::Style {
-                    rail_colors: (ACTIVE, Color { a: 0.1, ..ACTIVE }),
-                    handle: slider::Handle {
-                        shape: slider::HandleShape::Circle { radius: 9 },
-                        color: ACTIVE,
-                        border_width: 0,
-                        border_color: Color::TRANSPARENT,
-                    },
-                }
-            }
-
-            fn hovered(&self) -> slider::Style {
-                let active = self.active();
-
-                slider::Style {
-                    handle: slider::Handle {
-                        color: HOVERED,
-                        ..active.handle
-                    },
-                    ..active
-                }
-            }
-
-            fn dragging(&self) -> slider::Style {
-                let active = self.active();
-
-                slider::Style {
-                    handle: slider::Handle {
-                        color: Color::from_rgb(0.85, 0.85, 0.85),
-                        ..active.handle
-                    },
-                    ..active
-                }
-            }
-        }
+fn text_field<'a>(
+    label: &str,
+    field: &'a mut Field,
+    placeholder: &str,
+    on_change: fn(String) -> Message,
+    theme: style::Theme,
+) -> Row<'a, Message> {
+    Row::new()
+        .spacing(10)
+        .align_items(Align::Center)
+        .push(Text::new(label).width(Length::Units(140)))
+        .push(
+            TextInput::new(
+                &mut field.state,
+                placeholder,
+                &field.value,
+                on_change,
+            )
+            .padding(5)
+            .style(theme),
+        )
+}
 
-        pub struct ProgressBar;
+/// a pick list for adding values, followed by the values added so far
+fn list_field<'a>(
+    label: &str,
+    entries: &'a mut [ListEntry],
+    picker: &'a mut pick_list::State<String>,
+    options: &'a [String],
+    on_add: fn(String) -> Message,
+    on_remove: fn(usize) -> Message,
+    theme: style::Theme,
+) -> Column<'a, Message> {
+    let column = Column::new().spacing(5).push(
+        Row::new()
+            .spacing(10)
+            .align_items(Align::Center)
+            .push(Text::new(label).width(Length::Units(140)))
+            .push(PickList::new(picker, options, None, on_add)),
+    );
+    entries
+        .iter_mut()
+        .enumerate()
+        .fold(column, |column, (index, entry)| {
+            column.push(
+                Row::new()
+                    .spacing(10)
+                    .align_items(Align::Center)
+                    .push(Text::new(&entry.value).width(Length::Units(140)))
+                    .push(
+                        Button::new(&mut entry.remove, Text::new("Remove"))
+                            .on_press(on_remove(index))
+                            .style(theme),
+                    ),
+            )
+        })
+}
 
-        impl progress_bar::StyleSheet for ProgressBar {
-            fn style(&self) -> progress_bar::Style {
-                progress_bar::Style {
-                    background: Background::Color(SURFACE),
-                    bar: Background::Color(ACTIVE),
-                    border_radius: 10,
-                }
-            }
-        }
+/// add a value picked from a list, unless it's already there
+fn add_entry(entries: &mut Vec<ListEntry>, value: String) {
+    if !entries.iter().any(|entry| entry.value == value) {
+        entries.push(ListEntry {
+            value,
+            remove: button::State::new(),
+        });
+    }
+}
 
-        pub struct Checkbox;
-
-        impl checkbox::StyleSheet for Checkbox {
-            fn active(&self, is_checked: bool) -> checkbox::Style {
-                checkbox::Style {
-                    background: Background::Color(if is_checked {
-                        ACTIVE
-                    } else {
-                        SURFACE
-                    }),
-                    checkmark_color: Color::WHITE,
-                    border_radius: 2,
-                    border_width: 1,
-                    border_color: ACTIVE,
-                }
-            }
+fn values(entries: &[ListEntry]) -> Vec<String> {
+    entries.iter().map(|entry| entry.value.clone()).collect()
+}
 
-            fn hovered(&self, is_checked: bool) -> checkbox::Style {
-                checkbox::Style {
-                    background: Background::Color(Color {
-                        a: 0.8,
-                        ..if is_checked { ACTIVE } else { SURFACE }
-                    }),
-                    ..self.active(is_checked)
-                }
-            }
-        }
-    }
-}
\ No newline at end of file
+fn sorted(set: &HashSet<String>) -> Vec<String> {
+    let mut values: Vec<String> = set.iter().cloned().collect();
+    values.sort();
+    values
+}